@@ -0,0 +1,217 @@
+use crate::models::{AdminAuditDocument, AppState};
+use crate::utils::{bson_as_i64, get_quiz_quest_id};
+use chrono::Utc;
+use mongodb::bson::{doc, Bson, Document};
+use std::future::Future;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+// Entity of a collection changed by an admin through a route, found by its id or by
+// another unique field like the name of a user or partner
+pub struct AuditEntry<'a> {
+    user: &'a str,
+    route: &'a str,
+    action: AuditAction,
+    collection: &'a str,
+    key: &'a str,
+    id: Bson,
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn create(user: &'a str, route: &'a str, collection: &'a str, id: i64) -> Self {
+        Self::new(user, route, AuditAction::Create, collection, id)
+    }
+
+    pub fn update(user: &'a str, route: &'a str, collection: &'a str, id: i64) -> Self {
+        Self::new(user, route, AuditAction::Update, collection, id)
+    }
+
+    pub fn delete(user: &'a str, route: &'a str, collection: &'a str, id: i64) -> Self {
+        Self::new(user, route, AuditAction::Delete, collection, id)
+    }
+
+    // Entity identified by the value of key instead of its id
+    pub fn keyed(
+        action: AuditAction,
+        user: &'a str,
+        route: &'a str,
+        collection: &'a str,
+        key: &'a str,
+        value: &str,
+    ) -> Self {
+        AuditEntry {
+            key,
+            id: Bson::String(value.to_string()),
+            ..Self::new(user, route, action, collection, 0)
+        }
+    }
+
+    fn new(
+        user: &'a str,
+        route: &'a str,
+        action: AuditAction,
+        collection: &'a str,
+        id: i64,
+    ) -> Self {
+        AuditEntry {
+            user,
+            route,
+            action,
+            collection,
+            key: "id",
+            id: Bson::Int64(id),
+        }
+    }
+}
+
+// Fields whose values are never copied to the audit log, only the fact that they changed
+const SECRET_FIELDS: [&str; 6] = [
+    "password_hash",
+    "code",
+    "webhook_secret",
+    "api_headers",
+    "refresh_token_hash",
+    "previous_refresh_token_hash",
+];
+
+// Current state of the entity, taken before a mutation so that record_admin_audit can
+// compute what changed
+async fn get_audit_snapshot(state: &AppState, entry: &AuditEntry<'_>) -> Option<Document> {
+    state
+        .db
+        .collection::<Document>(entry.collection)
+        .find_one(doc! { entry.key: entry.id.clone() }, None)
+        .await
+        .ok()
+        .flatten()
+}
+
+async fn get_quest_ids(state: &AppState, collection: &str, document: &Document) -> Vec<i64> {
+    let quiz_id = match collection {
+        "quests" => {
+            return document
+                .get("id")
+                .and_then(bson_as_i64)
+                .into_iter()
+                .collect()
+        }
+        // quizzes only know their quest through their task
+        "quizzes" => document.get("id").and_then(bson_as_i64),
+        "quiz_questions" => document.get("quiz_id").and_then(bson_as_i64),
+        _ => None,
+    };
+    if let Some(quiz_id) = quiz_id {
+        return get_quiz_quest_id(&state.db, quiz_id)
+            .await
+            .into_iter()
+            .collect();
+    }
+    match document.get("quests") {
+        // boosts reference several quests
+        Some(Bson::Array(quests)) => quests.iter().filter_map(bson_as_i64).collect(),
+        _ => document
+            .get("quest_id")
+            .and_then(bson_as_i64)
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn get_diff(before: Option<&Document>, after: Option<&Document>) -> Document {
+    let empty = Document::new();
+    let before = before.unwrap_or(&empty);
+    let after = after.unwrap_or(&empty);
+
+    let mut diff = Document::new();
+    for key in before.keys().chain(after.keys()) {
+        if key == "_id" || diff.contains_key(key) {
+            continue;
+        }
+        let (old_value, new_value) = (before.get(key), after.get(key));
+        if old_value != new_value && SECRET_FIELDS.contains(&key.as_str()) {
+            diff.insert(key, doc! { "changed": true });
+        } else if old_value != new_value {
+            diff.insert(
+                key,
+                doc! {
+                    "before": old_value.cloned().unwrap_or(Bson::Null),
+                    "after": new_value.cloned().unwrap_or(Bson::Null),
+                },
+            );
+        }
+    }
+    diff
+}
+
+// Records who changed which entity, before is None for a creation. Failing to write
+// the audit entry is logged but doesn't fail the request, the mutation already happened.
+pub async fn record_admin_audit(
+    state: &AppState,
+    entry: &AuditEntry<'_>,
+    before: Option<Document>,
+) {
+    let after = match entry.action {
+        AuditAction::Delete => None,
+        _ => get_audit_snapshot(state, entry).await,
+    };
+    let quest_ids = match after.as_ref().or(before.as_ref()) {
+        Some(document) => get_quest_ids(state, entry.collection, document).await,
+        None => vec![],
+    };
+
+    let action = entry.action.as_str();
+    let audit = AdminAuditDocument {
+        user: entry.user.to_string(),
+        route: entry.route.to_string(),
+        collection: entry.collection.to_string(),
+        entity_id: entry.id.clone(),
+        quest_ids,
+        action: action.to_string(),
+        diff: get_diff(before.as_ref(), after.as_ref()),
+        timestamp: Utc::now().timestamp_millis(),
+    };
+    if let Err(e) = state
+        .db
+        .collection::<AdminAuditDocument>("admin_audit")
+        .insert_one(audit, None)
+        .await
+    {
+        state.logger.warning(format!(
+            "admin_audit: unable to record {} of {} {}: {}",
+            action, entry.collection, entry.id, e
+        ));
+    }
+}
+
+// Runs the write of an admin route and records it once it succeeded, along with the
+// state of the entity before the write for updates and deletions
+pub async fn with_admin_audit<T, E>(
+    state: &AppState,
+    entry: AuditEntry<'_>,
+    write: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let before = match entry.action {
+        AuditAction::Create => None,
+        _ => get_audit_snapshot(state, &entry).await,
+    };
+    let result = write.await;
+    if result.is_ok() {
+        record_admin_audit(state, &entry, before).await;
+    }
+    result
+}
@@ -0,0 +1,95 @@
+use crate::{
+    logger::Logger,
+    models::{AdminSessionDocument, AppState, JWTClaims, LoginDetails},
+    utils::{get_random_hex, hash_password},
+};
+use chrono::Utc;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::{
+    bson::{doc, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use serde_json::{json, Value};
+
+// Admin users are looked up by name, two users with the same name would share logins
+pub async fn init_admin_users(db: &Database, logger: &Logger) {
+    let index = IndexModel::builder()
+        .keys(doc! { "user": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = db
+        .collection::<Document>("login_details")
+        .create_index(index, None)
+        .await
+    {
+        logger.warning(format!("unable to create index on login_details: {}", e));
+    }
+}
+
+pub fn get_access_token(
+    state: &AppState,
+    login: &LoginDetails,
+    session_id: &str,
+) -> Result<String, String> {
+    let exp = Utc::now().timestamp() + state.conf.auth.expiry_duration;
+    let user_claims = JWTClaims {
+        sub: login.user.clone(),
+        exp: exp as usize,
+        jti: session_id.to_string(),
+        roles: login.get_roles(),
+    };
+    encode(
+        &Header::default(),
+        &user_claims,
+        &EncodingKey::from_secret(state.conf.auth.secret_key.as_ref()),
+    )
+    .map_err(|e| e.to_string())
+}
+
+// refresh tokens are "<session id>.<secret>", only a hash of the secret is stored
+pub fn parse_refresh_token(refresh_token: &str) -> Option<(&str, &str)> {
+    refresh_token.split_once('.')
+}
+
+pub async fn create_admin_session(state: &AppState, login: &LoginDetails) -> Result<Value, String> {
+    let session_id = get_random_hex(16);
+    let secret = get_random_hex(32);
+    let refresh_token_hash = hash_password(&secret).map_err(|e| e.to_string())?;
+    let now = Utc::now().timestamp();
+
+    let session = AdminSessionDocument {
+        id: session_id.clone(),
+        user: login.user.clone(),
+        refresh_token_hash,
+        previous_refresh_token_hash: None,
+        created_at: now,
+        last_used_at: now,
+        expiry: now + state.conf.auth.refresh_expiry_duration,
+        revoked: false,
+    };
+    state
+        .db
+        .collection::<AdminSessionDocument>("admin_sessions")
+        .insert_one(session, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let token = get_access_token(state, login, &session_id)?;
+    Ok(json!({
+        "token": token,
+        "refresh_token": format!("{}.{}", session_id, secret),
+    }))
+}
+
+pub async fn revoke_admin_sessions(
+    state: &AppState,
+    filter: mongodb::bson::Document,
+) -> Result<u64, mongodb::error::Error> {
+    let result = state
+        .db
+        .collection::<AdminSessionDocument>("admin_sessions")
+        .update_many(filter, doc! { "$set": { "revoked": true } }, None)
+        .await?;
+    Ok(result.modified_count)
+}
@@ -0,0 +1,386 @@
+use crate::{
+    logger::Logger,
+    models::{AppState, BoostDraw, BoostEligibleDocument},
+    utils::{bson_as_i64, to_hex},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_bson, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+use sha2::{Digest, Sha256};
+use starknet::{
+    core::types::{BlockId, FieldElement, MaybePendingBlockWithTxHashes},
+    providers::Provider,
+};
+use std::{str::FromStr, sync::Arc};
+use tokio::time::{sleep, Duration};
+
+// Bumped whenever the way winners are drawn changes, so that past draws stay verifiable
+pub const DRAW_ALGORITHM_VERSION: i32 = 1;
+
+// Eligible addresses sorted by value without duplicates, the list every draw starts from
+pub fn sort_eligible(mut eligible: Vec<FieldElement>) -> Vec<FieldElement> {
+    eligible.sort_by_key(|addr| addr.to_bytes_be());
+    eligible.dedup();
+    eligible
+}
+
+// sha256 of the sorted eligible addresses, as 0x prefixed 64 digits hex, one per line
+pub fn get_eligible_hash(eligible: &[FieldElement]) -> String {
+    let addresses: Vec<String> = eligible.iter().map(|addr| to_hex(*addr)).collect();
+    format!("{:x}", Sha256::digest(addresses.join("\n").as_bytes()))
+}
+
+// Version 1: Fisher-Yates shuffle of the sorted eligible list, going from the last
+// position i down to 1 and swapping it with j = the first 8 bytes of
+// sha256("{seed}:{i}") read as a big endian u64, modulo i + 1. The seed is written like
+// the addresses and the winners are the first num_of_winners addresses of the result.
+pub fn draw_winners(
+    eligible: &[FieldElement],
+    seed: FieldElement,
+    num_of_winners: usize,
+) -> Vec<FieldElement> {
+    let seed = to_hex(seed);
+    let mut shuffled = eligible.to_vec();
+    for i in (1..shuffled.len()).rev() {
+        let digest = Sha256::digest(format!("{}:{}", seed, i).as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        let j = (u64::from_be_bytes(bytes) % (i as u64 + 1)) as usize;
+        shuffled.swap(i, j);
+    }
+    shuffled.truncate(num_of_winners);
+    shuffled
+}
+
+async fn get_block(state: &AppState, number: u64) -> Result<(u64, FieldElement), String> {
+    match state
+        .provider
+        .get_block_with_tx_hashes(BlockId::Number(number))
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        MaybePendingBlockWithTxHashes::Block(block) => Ok((block.timestamp, block.block_hash)),
+        MaybePendingBlockWithTxHashes::PendingBlock(_) => {
+            Err(format!("block {} is still pending", number))
+        }
+    }
+}
+
+// Number and hash of the first block produced after expiry (milliseconds), None until
+// that block exists. Block timestamps never decrease so the block is found by bisection.
+pub async fn get_seed_block(
+    state: &AppState,
+    expiry: i64,
+) -> Result<Option<(u64, FieldElement)>, String> {
+    let expiry = (expiry / 1000).max(0) as u64;
+    let latest = state
+        .provider
+        .block_number()
+        .await
+        .map_err(|e| e.to_string())?;
+    let (latest_timestamp, _) = get_block(state, latest).await?;
+    if latest_timestamp <= expiry {
+        return Ok(None);
+    }
+    let (mut low, mut high) = (0, latest);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match get_block(state, middle).await?.0 > expiry {
+            true => high = middle,
+            false => low = middle + 1,
+        }
+    }
+    let (_, hash) = get_block(state, low).await?;
+    Ok(Some((low, hash)))
+}
+
+// Whether seed is the hash of seed_block and seed_block the first block produced after
+// expiry, which only needs that block and the one before it
+pub async fn verify_seed_block(
+    state: &AppState,
+    expiry: i64,
+    seed_block: u64,
+    seed: FieldElement,
+) -> Result<bool, String> {
+    let expiry = (expiry / 1000).max(0) as u64;
+    let (timestamp, hash) = get_block(state, seed_block).await?;
+    if hash != seed || timestamp <= expiry {
+        return Ok(false);
+    }
+    match seed_block.checked_sub(1) {
+        Some(previous) => Ok(get_block(state, previous).await?.0 <= expiry),
+        None => Ok(true),
+    }
+}
+
+// Addresses which completed every task of one of the quests before expiry, tasks
+// completed before completion timestamps were saved count as completed in time
+pub async fn get_eligible(
+    state: &AppState,
+    quests: &[i64],
+    expiry: i64,
+) -> Result<Vec<FieldElement>, String> {
+    let mut eligible = vec![];
+    for quest_id in quests {
+        let task_ids: Vec<i64> = state
+            .db
+            .collection::<Document>("tasks")
+            .find(doc! { "quest_id": quest_id }, None)
+            .await
+            .map_err(|e| e.to_string())?
+            .try_collect::<Vec<Document>>()
+            .await
+            .map_err(|e| e.to_string())?
+            .iter()
+            .filter_map(|task| task.get("id").and_then(bson_as_i64))
+            .collect();
+        if task_ids.is_empty() {
+            continue;
+        }
+        let task_count = task_ids.len() as i64;
+        let pipeline = vec![
+            doc! {
+                "$match": {
+                    "task_id": { "$in": &task_ids },
+                    "$or": [
+                        { "timestamp": { "$lte": expiry } },
+                        { "timestamp": { "$exists": false } },
+                    ],
+                }
+            },
+            doc! {
+                "$group": {
+                    "_id": "$address",
+                    "done": { "$addToSet": "$task_id" },
+                }
+            },
+            doc! {
+                "$match": {
+                    "$expr": { "$eq": [{ "$size": "$done" }, task_count] }
+                }
+            },
+        ];
+        let completers: Vec<Document> = state
+            .db
+            .collection::<Document>("completed_tasks")
+            .aggregate(pipeline, None)
+            .await
+            .map_err(|e| e.to_string())?
+            .try_collect()
+            .await
+            .map_err(|e| e.to_string())?;
+        eligible.extend(
+            completers
+                .iter()
+                .filter_map(|completer| completer.get_str("_id").ok())
+                .filter_map(|addr| FieldElement::from_str(addr).ok()),
+        );
+    }
+    Ok(sort_eligible(eligible))
+}
+
+pub async fn init_boost_draws(db: &Database, logger: &Logger) {
+    let index = IndexModel::builder()
+        .keys(doc! { "boost_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = db
+        .collection::<Document>("boost_eligible")
+        .create_index(index, None)
+        .await
+    {
+        logger.warning(format!("unable to create index on boost_eligible: {}", e));
+    }
+}
+
+// Eligible list saved when the boost was drawn, None for boosts drawn before it was kept
+pub async fn get_saved_eligible(
+    state: &AppState,
+    boost_id: i64,
+) -> Result<Option<Vec<FieldElement>>, String> {
+    let saved = state
+        .db
+        .collection::<BoostEligibleDocument>("boost_eligible")
+        .find_one(doc! { "boost_id": boost_id }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(saved) = saved else {
+        return Ok(None);
+    };
+    saved
+        .eligible
+        .iter()
+        .map(|addr| FieldElement::from_hex_be(addr).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+// Saves the eligible list of a boost once, a draw started again after a crash uses the
+// list saved first so that the winners always match the saved list
+async fn save_eligible(
+    state: &AppState,
+    boost_id: i64,
+    eligible: &[FieldElement],
+) -> Result<Vec<FieldElement>, String> {
+    let addresses: Vec<String> = eligible.iter().map(|addr| to_hex(*addr)).collect();
+    state
+        .db
+        .collection::<BoostEligibleDocument>("boost_eligible")
+        .update_one(
+            doc! { "boost_id": boost_id },
+            doc! { "$setOnInsert": { "eligible": addresses } },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    get_saved_eligible(state, boost_id)
+        .await?
+        .ok_or_else(|| format!("eligible list of boost {} not saved", boost_id))
+}
+
+pub fn get_boost_quests(boost: &Document) -> Vec<i64> {
+    boost
+        .get_array("quests")
+        .map(|quests| quests.iter().filter_map(bson_as_i64).collect())
+        .unwrap_or_default()
+}
+
+// Draws the winners of an expired boost, it is left for the next iteration while its
+// seed block doesn't exist
+async fn draw_boost(state: &AppState, boost: &Document) -> Result<(), String> {
+    let (Some(id), Some(expiry)) = (
+        boost.get("id").and_then(bson_as_i64),
+        boost.get("expiry").and_then(bson_as_i64),
+    ) else {
+        return Err("boost without id or expiry".to_string());
+    };
+    let num_of_winners = boost
+        .get("num_of_winners")
+        .and_then(bson_as_i64)
+        .unwrap_or(0)
+        .max(0) as usize;
+    let Some((seed_block, seed)) = get_seed_block(state, expiry).await? else {
+        return Ok(());
+    };
+    let eligible = match get_saved_eligible(state, id).await? {
+        Some(eligible) => eligible,
+        None => {
+            let eligible = get_eligible(state, &get_boost_quests(boost), expiry).await?;
+            save_eligible(state, id, &eligible).await?
+        }
+    };
+    // boosts nobody completed in time are closed with no winner
+    let winners: Vec<String> = draw_winners(&eligible, seed, num_of_winners)
+        .into_iter()
+        .map(to_hex)
+        .collect();
+    let draw = BoostDraw {
+        algorithm_version: DRAW_ALGORITHM_VERSION,
+        seed: to_hex(seed),
+        seed_block,
+        eligible_hash: get_eligible_hash(&eligible),
+        eligible_count: eligible.len() as u64,
+        drawn_at: Utc::now().timestamp_millis(),
+    };
+    let draw = to_bson(&draw).map_err(|e| e.to_string())?;
+    state
+        .db
+        .collection::<Document>("boosts")
+        .update_one(
+            doc! { "id": id, "winner": null },
+            doc! { "$set": { "winner": winners, "draw": draw } },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn draw_expired_boosts(state: Arc<AppState>) {
+    loop {
+        let filter = doc! {
+            "expiry": { "$lt": Utc::now().timestamp_millis() },
+            "winner": null,
+        };
+        let boosts = match state
+            .db
+            .collection::<Document>("boosts")
+            .find(filter, None)
+            .await
+        {
+            Ok(cursor) => cursor.try_collect::<Vec<Document>>().await,
+            Err(e) => Err(e),
+        };
+        match boosts {
+            Ok(boosts) => {
+                for boost in boosts {
+                    if let Err(e) = draw_boost(&state, &boost).await {
+                        state.logger.warning(format!("boost raffle: {}", e));
+                    }
+                }
+            }
+            Err(e) => state.logger.warning(format!("boost raffle: {}", e)),
+        }
+
+        // read on every iteration so that a config reload applies to the next raffle
+        let interval = state.get_reloadable_conf().boost_update_interval;
+        sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+pub fn run_boosts_raffle(state: &Arc<AppState>) {
+    tokio::spawn(draw_expired_boosts(state.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(values: &[u64]) -> Vec<FieldElement> {
+        values
+            .iter()
+            .map(|&value| FieldElement::from(value))
+            .collect()
+    }
+
+    #[test]
+    fn eligible_list_is_canonical() {
+        let eligible = sort_eligible(addresses(&[3, 1, 2, 3, 1]));
+        assert_eq!(eligible, addresses(&[1, 2, 3]));
+        assert_eq!(
+            get_eligible_hash(&eligible),
+            get_eligible_hash(&sort_eligible(addresses(&[2, 3, 1])))
+        );
+        assert_ne!(
+            get_eligible_hash(&eligible),
+            get_eligible_hash(&addresses(&[1, 2]))
+        );
+    }
+
+    #[test]
+    fn draw_is_reproducible() {
+        let eligible = addresses(&(1..=50).collect::<Vec<u64>>());
+        let seed = FieldElement::from(1234_u64);
+        let winners = draw_winners(&eligible, seed, 5);
+        assert_eq!(winners.len(), 5);
+        assert_eq!(winners, draw_winners(&eligible, seed, 5));
+        assert_ne!(
+            winners,
+            draw_winners(&eligible, FieldElement::from(1235_u64), 5)
+        );
+        assert_eq!(sort_eligible(winners).len(), 5);
+    }
+
+    #[test]
+    fn small_lists_are_all_winners() {
+        let eligible = addresses(&[1, 2]);
+        let mut winners = draw_winners(&eligible, FieldElement::ONE, 5);
+        winners.sort_by_key(|addr| addr.to_bytes_be());
+        assert_eq!(winners, eligible);
+        assert!(draw_winners(&[], FieldElement::ONE, 5).is_empty());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::{
+    common::quest_rewards::get_quest_rewards,
+    logger::Logger,
+    models::{
+        AppState, NFTUri, QuestDocument, QuestReward, Reward, RewardClaimDocument,
+        RewardClaimStatus,
+    },
+    utils::{bson_as_i64, get_token_id, is_duplicate_key_error, sign_nft, to_hex},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOptions, IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+use starknet::core::types::FieldElement;
+use std::collections::HashSet;
+
+// seconds during which a reward signature can be claimed
+pub const DEFAULT_SIGNATURE_TTL: i64 = 30 * 86400;
+
+// The NFT contract doesn't know about the deadline, a claim past it is only refused by
+// the server and a signature obtained before can still be minted
+pub fn is_claim_expired(claim: &RewardClaimDocument, now: i64) -> bool {
+    claim.status == RewardClaimStatus::Issued && claim.expiry <= now
+}
+
+pub async fn init_reward_claims(db: &Database, logger: &Logger) {
+    let unique = || IndexOptions::builder().unique(true).build();
+    let indexes = [
+        IndexModel::builder()
+            .keys(doc! { "address": 1, "quest_id": 1, "level": 1 })
+            .options(unique())
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "token_id": 1 })
+            .options(unique())
+            .build(),
+        IndexModel::builder()
+            .keys(doc! { "quest_id": 1, "status": 1 })
+            .build(),
+    ];
+    for index in indexes {
+        if let Err(e) = db
+            .collection::<Document>("reward_claims")
+            .create_index(index, None)
+            .await
+        {
+            logger.warning(format!("unable to create index on reward_claims: {}", e));
+        }
+    }
+}
+
+// Reward levels of the quests which had their own claimable module, as (quest id,
+// required tasks, level)
+const LEGACY_REWARD_LEVELS: [(i64, &[i64], i64); 7] = [
+    (1, &[5, 6, 7, 56], 4),
+    (9, &[37, 38, 39], 14),
+    (13, &[51, 57, 58], 19),
+    (14, &[52, 59, 60], 20),
+    (15, &[61, 62, 63], 21),
+    (20, &[79, 80, 81], 27),
+    (27, &[132, 133, 134], 39),
+];
+
+// Creates the nft_uri of the legacy reward levels from their quest when missing and sets
+// their tasks when they have none, so running it again changes nothing. Levels whose id
+// belongs to another quest are left alone.
+pub async fn init_legacy_reward_levels(db: &Database, logger: &Logger) {
+    let nft_uri_collection = db.collection::<Document>("nft_uri");
+    for (quest_id, task_ids, level) in LEGACY_REWARD_LEVELS {
+        let quest = match db
+            .collection::<QuestDocument>("quests")
+            .find_one(doc! { "id": quest_id }, None)
+            .await
+        {
+            Ok(Some(quest)) => quest,
+            Ok(None) => continue,
+            Err(e) => {
+                logger.warning(format!("unable to migrate reward level {}: {}", level, e));
+                continue;
+            }
+        };
+        // the id may have been given to a level of another quest since
+        let owner = nft_uri_collection
+            .find_one(doc! { "id": level }, None)
+            .await
+            .map(|uri| uri.and_then(|uri| uri.get("quest_id").and_then(bson_as_i64)));
+        match owner {
+            Ok(Some(owner)) if owner != quest_id => {
+                logger.warning(format!(
+                    "reward level {} of quest {} is used by quest {}, skipping it",
+                    level, quest_id, owner
+                ));
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                logger.warning(format!("unable to migrate reward level {}: {}", level, e));
+                continue;
+            }
+        }
+        let image = quest
+            .rewards_nfts
+            .iter()
+            .find(|nft| i64::from(nft.level) == level)
+            .map(|nft| nft.img.clone())
+            .unwrap_or_else(|| quest.img_card.clone());
+        let created = nft_uri_collection
+            .update_one(
+                doc! { "id": level, "quest_id": quest_id },
+                doc! { "$setOnInsert": {
+                    "id": level,
+                    "name": &quest.name,
+                    "description": &quest.desc,
+                    "image": image,
+                    "quest_id": quest_id,
+                    "attributes": null,
+                    "task_ids": task_ids,
+                } },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await;
+        let updated = match created {
+            Ok(_) => {
+                nft_uri_collection
+                    .update_one(
+                        doc! { "id": level, "quest_id": quest_id, "task_ids": null },
+                        doc! { "$set": { "task_ids": task_ids } },
+                        None,
+                    )
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match updated {
+            Ok(_) => {}
+            Err(e) if is_duplicate_key_error(&e) => logger.warning(format!(
+                "reward level {} of quest {} is used by another quest, skipping it",
+                level, quest_id
+            )),
+            Err(e) => logger.warning(format!("unable to migrate reward level {}: {}", level, e)),
+        }
+    }
+}
+
+// Signature of a reward level for addr, None once the level was minted. The same
+// token id is signed again when the task of the level changes, so that a level can
+// only be minted once whatever the number of signatures issued, or when it was made with
+// another key than public_key. The deadline is set when the claim is first issued and
+// kept when it is signed again, an expired claim is returned as is and never renewed.
+async fn get_reward_claim(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+    level: i64,
+    task_id: u32,
+    public_key: &str,
+) -> Result<Option<RewardClaimDocument>, String> {
+    let collection = state.db.collection::<RewardClaimDocument>("reward_claims");
+    let filter = doc! { "address": addr.to_string(), "quest_id": quest_id, "level": level };
+    let existing = collection
+        .find_one(filter.clone(), None)
+        .await
+        .map_err(|_| "Error querying claims".to_string())?;
+
+    let now = Utc::now().timestamp_millis();
+    let token_id = match &existing {
+        Some(claim) if claim.status == RewardClaimStatus::Minted => return Ok(None),
+        Some(claim) if is_claim_expired(claim, now) => return Ok(Some(claim.clone())),
+        Some(claim)
+            if claim.task_id == task_id
+                && claim.signer_public_key.as_deref() == Some(public_key) =>
+        {
+            return Ok(Some(claim.clone()))
+        }
+        Some(claim) => claim
+            .token_id
+            .parse::<u64>()
+            .map_err(|_| "Invalid token id".to_string())?,
+        None => get_token_id(level as u32),
+    };
+
+    let Ok(sig) = sign_nft(quest_id, task_id, addr, token_id, state.nft_signer.as_ref()).await
+    else {
+        return Err("Signature failed".to_string());
+    };
+    let ttl = state
+        .conf
+        .nft_contract
+        .signature_ttl
+        .unwrap_or(DEFAULT_SIGNATURE_TTL);
+    let claim = RewardClaimDocument {
+        address: addr.to_string(),
+        quest_id,
+        level,
+        task_id,
+        token_id: token_id.to_string(),
+        sig: (to_hex(sig.r), to_hex(sig.s)),
+        signer_public_key: Some(public_key.to_string()),
+        status: RewardClaimStatus::Issued,
+        expiry: existing
+            .as_ref()
+            .map_or(now + ttl * 1000, |existing| existing.expiry),
+        created_at: now,
+        minted_at: None,
+        mint_transaction: None,
+    };
+
+    let saved = match existing {
+        Some(_) => collection
+            .update_one(
+                doc! {
+                    "address": addr.to_string(),
+                    "quest_id": quest_id,
+                    "level": level,
+                    "status": "issued",
+                    "expiry": { "$gt": now },
+                },
+                doc! { "$set": {
+                    "task_id": task_id,
+                    "sig": [&claim.sig.0, &claim.sig.1],
+                    "signer_public_key": public_key,
+                    "created_at": now,
+                } },
+                None,
+            )
+            .await
+            .map(|result| result.matched_count > 0),
+        None => collection.insert_one(&claim, None).await.map(|_| true),
+    };
+    let saved_claim = async {
+        collection
+            .find_one(filter, None)
+            .await
+            .map_err(|_| "Error querying claims".to_string())
+            .map(|claim| claim.filter(|claim| claim.status == RewardClaimStatus::Issued))
+    };
+    match saved {
+        Ok(true) => Ok(Some(claim)),
+        // the claim was minted or expired in the meantime, serve what is saved
+        Ok(false) => saved_claim.await,
+        // another request issued the claim first, serve that one
+        Err(e) if is_duplicate_key_error(&e) => saved_claim.await,
+        Err(_) => Err("Error saving claim".to_string()),
+    }
+}
+
+// A reward level can be claimed once every task of its subset is completed,
+// the subset defaults to all the tasks of the quest
+struct RewardLevel {
+    level: i64,
+    task_ids: Option<Vec<i64>>,
+}
+
+// Levels whose tasks are all completed, with the last task of their subset which the
+// contract expects the signature to reference
+fn get_claimable_levels(
+    levels: &[RewardLevel],
+    quest_task_ids: &[i64],
+    completed: &HashSet<i64>,
+) -> Vec<(i64, u32)> {
+    levels
+        .iter()
+        .filter_map(|level| {
+            let required = level.task_ids.as_deref().unwrap_or(quest_task_ids);
+            // a subset referencing tasks outside of the quest can never be claimed
+            let claimable = !required.is_empty()
+                && required
+                    .iter()
+                    .all(|id| quest_task_ids.contains(id) && completed.contains(id));
+            match claimable {
+                true => Some((level.level, *required.iter().max()? as u32)),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+async fn get_reward_levels(
+    state: &AppState,
+    quest: &QuestDocument,
+) -> Result<Vec<RewardLevel>, String> {
+    let options = FindOptions::builder().sort(doc! { "id": 1 }).build();
+    let nft_uris: Vec<NFTUri> = state
+        .db
+        .collection::<NFTUri>("nft_uri")
+        .find(doc! { "quest_id": quest.id }, options)
+        .await
+        .map_err(|e| e.to_string())?
+        .try_collect()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !nft_uris.is_empty() {
+        return Ok(nft_uris
+            .into_iter()
+            .map(|uri| RewardLevel {
+                level: uri.id,
+                task_ids: uri.task_ids,
+            })
+            .collect());
+    }
+
+    // legacy quests predate nft_uri, their levels only live on the quest itself
+    Ok(quest
+        .rewards_nfts
+        .iter()
+        .map(|nft| RewardLevel {
+            level: nft.level.into(),
+            task_ids: None,
+        })
+        .collect())
+}
+
+pub async fn get_claimable_rewards(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Result<Vec<Reward>, String> {
+    let quest = state
+        .db
+        .collection::<QuestDocument>("quests")
+        .find_one(doc! { "id": quest_id }, None)
+        .await
+        .map_err(|_| "Error querying quest".to_string())?
+        .ok_or_else(|| "Quest not found".to_string())?;
+    if !get_quest_rewards(&quest).contains(&QuestReward::Nft) {
+        return Err("No rewards found for this quest".to_string());
+    }
+
+    let quest_task_ids: Vec<i64> = state
+        .db
+        .collection::<Document>("tasks")
+        .find(doc! { "quest_id": quest_id }, None)
+        .await
+        .map_err(|_| "Error querying tasks".to_string())?
+        .try_collect::<Vec<Document>>()
+        .await
+        .map_err(|_| "Error querying tasks".to_string())?
+        .iter()
+        .filter_map(|task| task.get("id").and_then(bson_as_i64))
+        .collect();
+    if quest_task_ids.is_empty() {
+        return Err("Quest has no tasks".to_string());
+    }
+
+    let completed: HashSet<i64> = state
+        .db
+        .collection::<Document>("completed_tasks")
+        .find(
+            doc! {
+                "address": addr.to_string(),
+                "task_id": { "$in": &quest_task_ids },
+            },
+            None,
+        )
+        .await
+        .map_err(|_| "Error querying status".to_string())?
+        .try_collect::<Vec<Document>>()
+        .await
+        .map_err(|_| "Error querying status".to_string())?
+        .iter()
+        .filter_map(|task| task.get("task_id").and_then(bson_as_i64))
+        .collect();
+
+    let levels = get_reward_levels(state, &quest).await?;
+    if levels.is_empty() {
+        return Err("No rewards found for this quest".to_string());
+    }
+
+    let public_key = match state.nft_signer.public_key().await {
+        Ok(public_key) => to_hex(public_key),
+        Err(_) => return Err("Signature failed".to_string()),
+    };
+    let mut rewards = vec![];
+    let mut minted = 0;
+    let mut expired = 0;
+    let now = Utc::now().timestamp_millis();
+    for (level, last_task) in get_claimable_levels(&levels, &quest_task_ids, &completed) {
+        let claim = get_reward_claim(state, addr, quest_id, level, last_task, &public_key).await?;
+        let Some(claim) = claim else {
+            minted += 1;
+            continue;
+        };
+        if is_claim_expired(&claim, now) {
+            expired += 1;
+            continue;
+        }
+        let sig = match (
+            FieldElement::from_hex_be(&claim.sig.0),
+            FieldElement::from_hex_be(&claim.sig.1),
+        ) {
+            (Ok(r), Ok(s)) => (r, s),
+            _ => return Err("Invalid signature".to_string()),
+        };
+        rewards.push(Reward {
+            task_id: claim.task_id,
+            nft_contract: state.conf.nft_contract.address.clone(),
+            token_id: claim.token_id,
+            sig,
+            expiry: claim.expiry,
+        });
+    }
+
+    if rewards.is_empty() && minted > 0 {
+        return Err("Rewards already claimed".to_string());
+    }
+    if rewards.is_empty() && expired > 0 {
+        return Err("Reward claim expired".to_string());
+    }
+    if rewards.is_empty() {
+        return Err("User hasn't completed all tasks".to_string());
+    }
+    Ok(rewards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels() -> Vec<RewardLevel> {
+        LEGACY_REWARD_LEVELS
+            .iter()
+            .filter(|(quest_id, _, _)| *quest_id == 1)
+            .map(|(_, task_ids, level)| RewardLevel {
+                level: *level,
+                task_ids: Some(task_ids.to_vec()),
+            })
+            .chain([RewardLevel {
+                level: 99,
+                task_ids: None,
+            }])
+            .collect()
+    }
+
+    #[test]
+    fn levels_require_their_tasks() {
+        let quest_tasks = [5, 6, 7, 8, 56];
+        let completed = HashSet::from([5, 6, 7]);
+        assert!(get_claimable_levels(&levels(), &quest_tasks, &completed).is_empty());
+
+        // the level is signed for the last task of its subset
+        let completed = HashSet::from([5, 6, 7, 56]);
+        assert_eq!(
+            get_claimable_levels(&levels(), &quest_tasks, &completed),
+            vec![(4, 56)]
+        );
+
+        // levels without tasks require every task of the quest
+        let completed = HashSet::from(quest_tasks);
+        assert_eq!(
+            get_claimable_levels(&levels(), &quest_tasks, &completed),
+            vec![(4, 56), (99, 56)]
+        );
+    }
+
+    #[test]
+    fn levels_with_tasks_outside_the_quest_are_never_claimable() {
+        let levels = [RewardLevel {
+            level: 2,
+            task_ids: Some(vec![1, 100]),
+        }];
+        let completed = HashSet::from([1, 100]);
+        assert!(get_claimable_levels(&levels, &[1, 2], &completed).is_empty());
+    }
+
+    #[test]
+    fn legacy_levels_are_unique() {
+        let levels: HashSet<i64> = LEGACY_REWARD_LEVELS.iter().map(|row| row.2).collect();
+        assert_eq!(levels.len(), LEGACY_REWARD_LEVELS.len());
+        assert!(LEGACY_REWARD_LEVELS
+            .iter()
+            .all(|(_, task_ids, _)| !task_ids.is_empty()));
+    }
+}
@@ -0,0 +1,385 @@
+use crate::common::linked_accounts::{
+    get_refresh_token, get_valid_access_token, link_account, update_tokens, OAuthTokens,
+};
+use crate::common::oauth_state::{consume_oauth_state, issue_oauth_state, OAuthStatePayload};
+use crate::models::{AppState, LinkedAccountDocument, QuestTaskDocument};
+use crate::utils::{get_error_redirect, success_redirect, CompletedTasksTrait};
+use axum::{http::StatusCode, response::Response};
+use chrono::Utc;
+use mongodb::bson::doc;
+use reqwest::{header::AUTHORIZATION, Url};
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const SCOPES: &str = "identify guilds guilds.members.read";
+// first second of 2015, the epoch of discord snowflakes, in milliseconds
+const DISCORD_EPOCH: i64 = 1420070400000;
+const DAY_MS: i64 = 24 * 3600 * 1000;
+// no discord account is older than its epoch, larger minimums can never be met
+pub const MAX_MIN_ACCOUNT_AGE: i64 = 36500;
+
+pub_struct!(Deserialize; DiscordOAuthCallbackQuery {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+});
+
+#[derive(Deserialize, Debug)]
+struct DiscordUser {
+    id: String,
+    username: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GuildMember {
+    user: Option<DiscordUser>,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+// What a member must satisfy to complete a discord task
+struct DiscordRequirements {
+    guild_id: String,
+    role_ids: Vec<String>,
+    min_account_age: Option<i64>,
+}
+
+// Task of a callback route registered before discord tasks were stored in the db
+pub_struct!(Clone, Copy; LegacyDiscordTask {
+    quest_id: i64,
+    task_id: u32,
+    guild_id: &'static str,
+});
+
+// callback routes registered on the discord app
+pub const CALLBACKS: &[&str] = &[
+    "/quests/discord_fw_callback",
+    "/quests/ekubo/discord_fw_callback",
+    "/quests/focustree/discord_fw_callback",
+    "/quests/nostra/discord_fw_callback",
+];
+
+// Minimum account age (in days) of a discord task, as set by admins
+pub fn validate_min_account_age(days: i64) -> Result<(), String> {
+    match (0..=MAX_MIN_ACCOUNT_AGE).contains(&days) {
+        true => Ok(()),
+        false => Err(format!(
+            "Minimum account age must be between 0 and {} days",
+            MAX_MIN_ACCOUNT_AGE
+        )),
+    }
+}
+
+// Starts an authorization for the wallet, optionally to verify a task once linked
+pub async fn get_authorization_url(
+    state: &AppState,
+    addr: FieldElement,
+    quest_id: Option<i64>,
+    task_id: Option<u32>,
+    callback: &str,
+) -> Result<(String, String), String> {
+    if !CALLBACKS.contains(&callback) {
+        return Err("Invalid callback".to_string());
+    }
+    let oauth_state = issue_oauth_state(state, "discord", addr, quest_id, task_id, None).await?;
+    let url = Url::parse_with_params(
+        AUTHORIZE_URL,
+        &[
+            ("response_type", "code"),
+            ("client_id", state.conf.discord.oauth2_clientid.as_str()),
+            (
+                "redirect_uri",
+                format!("{}{}", state.conf.variables.api_link, callback).as_str(),
+            ),
+            ("scope", SCOPES),
+            ("state", oauth_state.as_str()),
+        ],
+    )
+    .map_err(|_| "Error creating authorization url".to_string())?;
+    Ok((oauth_state, url.to_string()))
+}
+
+fn get_redirect(
+    state: &AppState,
+    oauth_state: Option<&OAuthStatePayload>,
+    legacy_task: Option<LegacyDiscordTask>,
+    res: bool,
+) -> String {
+    let task = oauth_state
+        .and_then(|s| Some((s.quest_id?, s.task_id?)))
+        .or(legacy_task.map(|task| (task.quest_id, task.task_id)));
+    match task {
+        Some((quest_id, task_id)) => format!(
+            "{}/quest/{}?task_id={}&res={}",
+            state.conf.variables.app_link, quest_id, task_id, res
+        ),
+        None => format!(
+            "{}/?platform=discord&res={}",
+            state.conf.variables.app_link, res
+        ),
+    }
+}
+
+async fn get_requirements(
+    state: &AppState,
+    quest_id: i64,
+    task_id: u32,
+    legacy_task: Option<LegacyDiscordTask>,
+) -> Result<DiscordRequirements, String> {
+    if let Some(task) = legacy_task {
+        if task.quest_id != quest_id || task.task_id != task_id {
+            return Err("Invalid authorization for this task".to_string());
+        }
+        return Ok(DiscordRequirements {
+            guild_id: task.guild_id.to_string(),
+            role_ids: vec![],
+            min_account_age: None,
+        });
+    }
+
+    let task = state
+        .db
+        .collection::<QuestTaskDocument>("tasks")
+        .find_one(
+            doc! { "id": task_id, "quest_id": quest_id, "task_type": "discord" },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Task not found".to_string())?;
+    Ok(DiscordRequirements {
+        guild_id: task
+            .discord_guild_id
+            .ok_or_else(|| "Task not found".to_string())?,
+        role_ids: task.discord_role_ids.unwrap_or_default(),
+        min_account_age: task.discord_min_account_age,
+    })
+}
+
+// discord ids are snowflakes, their first 42 bits are the creation time
+fn get_account_creation(user_id: &str) -> Option<i64> {
+    let id = user_id.parse::<u64>().ok()?;
+    Some((id >> 22) as i64 + DISCORD_EPOCH)
+}
+
+async fn check_requirements(
+    access_token: &str,
+    requirements: &DiscordRequirements,
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!(
+            "https://discord.com/api/users/@me/guilds/{}/member",
+            requirements.guild_id
+        ))
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to get user info: {}", e))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err("You're not part of the Discord server".to_string());
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to get guild member info: {}",
+            response.status()
+        ));
+    }
+    let member: GuildMember = response.json().await.map_err(|e| {
+        format!(
+            "Failed to get JSON response while fetching user info: {}",
+            e
+        )
+    })?;
+
+    if let Some(role_id) = requirements
+        .role_ids
+        .iter()
+        .find(|role_id| !member.roles.contains(role_id))
+    {
+        return Err(format!(
+            "You don't have the required role ({}) in the Discord server",
+            role_id
+        ));
+    }
+
+    if let Some(days) = requirements.min_account_age {
+        let created_at = member
+            .user
+            .as_ref()
+            .and_then(|user| get_account_creation(&user.id))
+            .ok_or_else(|| "Failed to get your Discord account age".to_string())?;
+        let min_age = days.checked_mul(DAY_MS).unwrap_or(i64::MAX);
+        if Utc::now().timestamp_millis() - created_at < min_age {
+            return Err(format!(
+                "Your Discord account must be at least {} days old",
+                days
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn request_token(params: &[(&str, &str)]) -> Result<OAuthTokens, String> {
+    let client = reqwest::Client::new();
+    let res = client
+        .post("https://discord.com/api/oauth2/token")
+        .form(params)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to request Discord token: {}", e))?;
+    let json: serde_json::Value = res
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Discord token: {}", e))?;
+    match json["access_token"].as_str() {
+        Some(access_token) => Ok(OAuthTokens {
+            access_token: access_token.to_string(),
+            refresh_token: json["refresh_token"].as_str().map(|s| s.to_string()),
+            expiry: Utc::now().timestamp() + json["expires_in"].as_i64().unwrap_or(0),
+        }),
+        None => Err(format!(
+            "Failed to get 'access_token' from JSON response : {:?}",
+            json
+        )),
+    }
+}
+
+async fn get_discord_user(access_token: &str) -> Result<DiscordUser, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://discord.com/api/users/@me")
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to get user info: {}", e))?;
+    response.json().await.map_err(|e| {
+        format!(
+            "Failed to get JSON response while fetching user info: {}",
+            e
+        )
+    })
+}
+
+// returns a usable access token, refreshing and saving it when it has expired
+async fn get_access_token(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+) -> Result<String, String> {
+    if let Some(access_token) = get_valid_access_token(state, account)? {
+        return Ok(access_token);
+    }
+    let refresh_token = get_refresh_token(state, account)?;
+    let tokens = request_token(&[
+        ("client_id", state.conf.discord.oauth2_clientid.as_str()),
+        ("client_secret", state.conf.discord.oauth2_secret.as_str()),
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+    ])
+    .await?;
+    update_tokens(state, account, &tokens).await?;
+    Ok(tokens.access_token)
+}
+
+// checks a discord task with the account linked to the wallet, without a new authorization
+pub async fn verify_discord_task(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+    quest_id: i64,
+    task_id: u32,
+) -> Result<(), String> {
+    let requirements = get_requirements(state, quest_id, task_id, None).await?;
+    let access_token = get_access_token(state, account).await?;
+    check_requirements(&access_token, &requirements).await
+}
+
+// Shared by every discord callback route, callback_path is the redirect url the
+// authorization was started with. The account is linked to the wallet and, when
+// the authorization was started from a task, the task is verified
+pub async fn handle_discord_callback(
+    state: &AppState,
+    query: DiscordOAuthCallbackQuery,
+    callback_path: &str,
+    legacy_task: Option<LegacyDiscordTask>,
+) -> Response {
+    let oauth_state = match consume_oauth_state(state, "discord", &query.state).await {
+        Ok((oauth_state, _)) => oauth_state,
+        Err(e) => return get_error_redirect(get_redirect(state, None, legacy_task, false), e),
+    };
+    let error_redirect_uri = get_redirect(state, Some(&oauth_state), legacy_task, false);
+
+    let Ok(addr) = FieldElement::from_dec_str(&oauth_state.address) else {
+        return get_error_redirect(error_redirect_uri, "Invalid address".to_string());
+    };
+    let task = match (oauth_state.quest_id, oauth_state.task_id) {
+        (Some(quest_id), Some(task_id)) => {
+            match get_requirements(state, quest_id, task_id, legacy_task).await {
+                Ok(requirements) => Some((task_id, requirements)),
+                Err(e) => return get_error_redirect(error_redirect_uri, e),
+            }
+        }
+        _ if legacy_task.is_some() => {
+            return get_error_redirect(error_redirect_uri, "Missing task".to_string())
+        }
+        _ => None,
+    };
+    let Some(code) = &query.code else {
+        return get_error_redirect(
+            error_redirect_uri,
+            format!(
+                "Discord authorization failed: {}",
+                query.error.as_deref().unwrap_or("unknown error")
+            ),
+        );
+    };
+
+    // Exchange the authorization code for an access token
+    let redirect_uri = format!("{}{}", state.conf.variables.api_link, callback_path);
+    let tokens = match request_token(&[
+        ("client_id", state.conf.discord.oauth2_clientid.as_str()),
+        ("client_secret", state.conf.discord.oauth2_secret.as_str()),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("grant_type", "authorization_code"),
+    ])
+    .await
+    {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return get_error_redirect(
+                error_redirect_uri,
+                format!("Failed to exchange authorization code: {}", e),
+            );
+        }
+    };
+
+    let user = match get_discord_user(&tokens.access_token).await {
+        Ok(user) => user,
+        Err(e) => return get_error_redirect(error_redirect_uri, e),
+    };
+    if let Err(e) = link_account(
+        state,
+        &oauth_state.address,
+        "discord",
+        &user.id,
+        user.username.as_deref().unwrap_or_default(),
+        &tokens,
+    )
+    .await
+    {
+        return get_error_redirect(error_redirect_uri, e);
+    }
+
+    let Some((task_id, requirements)) = task else {
+        return success_redirect(get_redirect(state, Some(&oauth_state), legacy_task, true));
+    };
+    if let Err(e) = check_requirements(&tokens.access_token, &requirements).await {
+        return get_error_redirect(error_redirect_uri, e);
+    }
+
+    match state.upsert_completed_task(addr, task_id).await {
+        Ok(_) => success_redirect(get_redirect(state, Some(&oauth_state), legacy_task, true)),
+        Err(e) => get_error_redirect(error_redirect_uri, format!("{}", e)),
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    logger::Logger,
+    models::{AppState, LinkedAccountDocument},
+    utils::is_duplicate_key_error,
+};
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument, UpdateOptions},
+    Database, IndexModel,
+};
+use sha2::{Digest, Sha256};
+
+pub const PLATFORMS: &[&str] = &["discord", "twitter"];
+
+pub_struct!(Debug, Clone; OAuthTokens {
+    access_token: String,
+    refresh_token: Option<String>,
+    expiry: i64,
+});
+
+// One account per platform and wallet, and a social account can only be linked
+// to a single wallet
+pub async fn init_linked_accounts(db: &Database, logger: &Logger) {
+    let collection = db.collection::<LinkedAccountDocument>("linked_accounts");
+    for keys in [
+        doc! { "address": 1, "platform": 1 },
+        doc! { "platform": 1, "account_id": 1 },
+    ] {
+        let index = IndexModel::builder()
+            .keys(keys)
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = collection.create_index(index, None).await {
+            logger.warning(format!("unable to create linked_accounts index: {}", e));
+        }
+    }
+
+    let index = IndexModel::builder()
+        .keys(doc! { "platform": 1, "account_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = db
+        .collection::<Document>("linked_account_bindings")
+        .create_index(index, None)
+        .await
+    {
+        logger.warning(format!(
+            "unable to create linked_account_bindings index: {}",
+            e
+        ));
+        return;
+    }
+    // accounts linked before bindings existed are bound to their current wallet
+    let pipeline = vec![
+        doc! { "$project": {
+            "_id": 0,
+            "platform": 1,
+            "account_id": 1,
+            "address": 1,
+            "bound_at": "$linked_at",
+        } },
+        doc! { "$merge": {
+            "into": "linked_account_bindings",
+            "on": ["platform", "account_id"],
+            "whenMatched": "keepExisting",
+            "whenNotMatched": "insert",
+        } },
+    ];
+    if let Err(e) = collection.aggregate(pipeline, None).await {
+        logger.warning(format!("unable to bind linked accounts: {}", e));
+    }
+}
+
+// Wallet the social account was first linked to, binding it to address if it never
+// was. The binding survives unlinking so that an account can't complete the same
+// tasks again from another wallet.
+async fn bind_account(
+    state: &AppState,
+    address: &str,
+    platform: &str,
+    account_id: &str,
+) -> Result<String, String> {
+    let collection = state.db.collection::<Document>("linked_account_bindings");
+    let filter = doc! { "platform": platform, "account_id": account_id };
+    let options = FindOneAndUpdateOptions::builder()
+        .upsert(true)
+        .return_document(ReturnDocument::After)
+        .build();
+    let binding = match collection
+        .find_one_and_update(
+            filter.clone(),
+            doc! { "$setOnInsert": { "address": address, "bound_at": Utc::now().timestamp() } },
+            options,
+        )
+        .await
+    {
+        Ok(binding) => binding,
+        // a concurrent link bound the account first
+        Err(e) if is_duplicate_key_error(&e) => collection
+            .find_one(filter, None)
+            .await
+            .map_err(|e| e.to_string())?,
+        Err(e) => return Err(e.to_string()),
+    };
+    binding
+        .and_then(|binding| binding.get_str("address").ok().map(str::to_string))
+        .ok_or_else(|| "Error binding account".to_string())
+}
+
+fn get_cipher(state: &AppState) -> Aes256Gcm {
+    let key = Sha256::digest(state.conf.session.token_encryption_secret.as_bytes());
+    Aes256Gcm::new(&key)
+}
+
+// tokens are stored as base64(nonce || AES-256-GCM ciphertext)
+pub fn encrypt_token(state: &AppState, token: &str) -> Result<String, String> {
+    let nonce = rand::random::<[u8; 12]>();
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend(
+        get_cipher(state)
+            .encrypt(Nonce::from_slice(&nonce), token.as_bytes())
+            .map_err(|_| "Error encrypting token".to_string())?,
+    );
+    Ok(STANDARD.encode(encrypted))
+}
+
+pub fn decrypt_token(state: &AppState, encrypted: &str) -> Result<String, String> {
+    let invalid = || "Invalid stored token, please link your account again".to_string();
+    let encrypted = STANDARD.decode(encrypted).map_err(|_| invalid())?;
+    if encrypted.len() < 12 {
+        return Err(invalid());
+    }
+    let (nonce, ciphertext) = encrypted.split_at(12);
+    let token = get_cipher(state)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| invalid())?;
+    String::from_utf8(token).map_err(|_| invalid())
+}
+
+pub async fn get_linked_account(
+    state: &AppState,
+    address: &str,
+    platform: &str,
+) -> Result<LinkedAccountDocument, String> {
+    state
+        .db
+        .collection::<LinkedAccountDocument>("linked_accounts")
+        .find_one(doc! { "address": address, "platform": platform }, None)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Please link your {} account first", platform))
+}
+
+// Links (or relinks) the social account to the wallet, the tokens are encrypted
+// before being stored
+pub async fn link_account(
+    state: &AppState,
+    address: &str,
+    platform: &str,
+    account_id: &str,
+    username: &str,
+    tokens: &OAuthTokens,
+) -> Result<LinkedAccountDocument, String> {
+    let collection = state
+        .db
+        .collection::<LinkedAccountDocument>("linked_accounts");
+    let already_linked = || {
+        format!(
+            "This {} account is already linked to another wallet",
+            platform
+        )
+    };
+
+    let owner = collection
+        .find_one(
+            doc! { "platform": platform, "account_id": account_id },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if owner.is_some_and(|owner| owner.address != address) {
+        return Err(already_linked());
+    }
+    if bind_account(state, address, platform, account_id).await? != address {
+        return Err(format!(
+            "This {} account was already used with another wallet",
+            platform
+        ));
+    }
+
+    let account = LinkedAccountDocument {
+        address: address.to_string(),
+        platform: platform.to_string(),
+        account_id: account_id.to_string(),
+        username: username.to_string(),
+        access_token: Some(encrypt_token(state, &tokens.access_token)?),
+        refresh_token: match &tokens.refresh_token {
+            Some(token) => Some(encrypt_token(state, token)?),
+            None => None,
+        },
+        token_expiry: Some(tokens.expiry),
+        linked_at: Utc::now().timestamp(),
+    };
+    let account_doc = mongodb::bson::to_document(&account).map_err(|e| e.to_string())?;
+    match collection
+        .update_one(
+            doc! { "address": address, "platform": platform },
+            doc! { "$set": account_doc },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await
+    {
+        Ok(_) => Ok(account),
+        // the unique index catches a concurrent link from another wallet
+        Err(e) if is_duplicate_key_error(&e) => Err(already_linked()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Saves refreshed tokens of a linked account
+pub async fn update_tokens(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+    tokens: &OAuthTokens,
+) -> Result<(), String> {
+    let refresh_token = match &tokens.refresh_token {
+        Some(token) => Some(encrypt_token(state, token)?),
+        None => account.refresh_token.clone(),
+    };
+    state
+        .db
+        .collection::<LinkedAccountDocument>("linked_accounts")
+        .update_one(
+            doc! { "address": &account.address, "platform": &account.platform },
+            doc! { "$set": {
+                "access_token": encrypt_token(state, &tokens.access_token)?,
+                "refresh_token": refresh_token,
+                "token_expiry": tokens.expiry,
+            }},
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Returns the decrypted access token, or None once it has expired
+pub fn get_valid_access_token(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+) -> Result<Option<String>, String> {
+    let Some(access_token) = &account.access_token else {
+        return Err(format!(
+            "Please link your {} account again",
+            account.platform
+        ));
+    };
+    if account.token_expiry.unwrap_or(0) > Utc::now().timestamp() + 30 {
+        Ok(Some(decrypt_token(state, access_token)?))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn get_refresh_token(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+) -> Result<String, String> {
+    match &account.refresh_token {
+        Some(token) => decrypt_token(state, token),
+        None => Err(format!(
+            "{} session expired, please link your account again",
+            account.platform
+        )),
+    }
+}
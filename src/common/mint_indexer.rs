@@ -0,0 +1,207 @@
+use crate::models::{AppState, RewardClaimDocument};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+};
+use starknet::{
+    core::types::{BlockId, EventFilter, FieldElement},
+    macros::selector,
+    providers::Provider,
+};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+const INDEXER_NAME: &str = "nft_mints";
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// blocks read per iteration, events are fetched by pages of EVENTS_CHUNK_SIZE
+const BLOCKS_PER_ITERATION: u64 = 1000;
+const EVENTS_CHUNK_SIZE: u64 = 500;
+
+// (to, token id) of a Transfer event from the zero address. Cairo 0 contracts put
+// from, to and the u256 token id in the data, Cairo 1 contracts index them as keys.
+fn parse_mint(
+    keys: &[FieldElement],
+    data: &[FieldElement],
+) -> Option<(FieldElement, FieldElement)> {
+    let values: Vec<FieldElement> = keys.iter().skip(1).chain(data.iter()).copied().collect();
+    match values.as_slice() {
+        [from, to, token_id_low, token_id_high, ..] if *from == FieldElement::ZERO => {
+            // token ids are issued as u64 so the high part is always zero
+            match *token_id_high == FieldElement::ZERO {
+                true => Some((*to, *token_id_low)),
+                false => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+async fn get_start_block(state: &AppState) -> Result<u64, String> {
+    let cursor = state
+        .db
+        .collection::<Document>("indexer_state")
+        .find_one(doc! { "name": INDEXER_NAME }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(block) = cursor.and_then(|cursor| cursor.get_i64("next_block").ok()) {
+        return Ok(block as u64);
+    }
+    match state.conf.nft_contract.indexer_start_block {
+        Some(block) => Ok(block),
+        None => state
+            .provider
+            .block_number()
+            .await
+            .map_err(|e| e.to_string()),
+    }
+}
+
+async fn mark_minted(
+    state: &AppState,
+    to: FieldElement,
+    token_id: FieldElement,
+    transaction_hash: FieldElement,
+) -> Result<(), String> {
+    let token_id: u64 = match token_id.try_into() {
+        Ok(token_id) => token_id,
+        Err(_) => return Ok(()),
+    };
+    state
+        .db
+        .collection::<RewardClaimDocument>("reward_claims")
+        .update_one(
+            doc! { "token_id": token_id.to_string(), "address": to.to_string() },
+            doc! { "$set": {
+                "status": "minted",
+                "minted_at": Utc::now().timestamp_millis(),
+                "mint_transaction": format!("{:#x}", transaction_hash),
+            } },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Reads the mint events of the blocks [from_block, to_block] and marks their claims
+async fn index_blocks(
+    state: &AppState,
+    contract: FieldElement,
+    from_block: u64,
+    to_block: u64,
+) -> Result<(), String> {
+    let filter = EventFilter {
+        from_block: Some(BlockId::Number(from_block)),
+        to_block: Some(BlockId::Number(to_block)),
+        address: Some(contract),
+        keys: Some(vec![vec![selector!("Transfer")]]),
+    };
+    let mut continuation_token = None;
+    loop {
+        let page = state
+            .provider
+            .get_events(filter.clone(), continuation_token, EVENTS_CHUNK_SIZE)
+            .await
+            .map_err(|e| e.to_string())?;
+        for event in page.events {
+            if let Some((to, token_id)) = parse_mint(&event.keys, &event.data) {
+                mark_minted(state, to, token_id, event.transaction_hash).await?;
+            }
+        }
+        match page.continuation_token {
+            Some(token) => continuation_token = Some(token),
+            None => return Ok(()),
+        }
+    }
+}
+
+async fn index_mints(state: Arc<AppState>) {
+    let Ok(contract) = FieldElement::from_hex_be(&state.conf.nft_contract.address) else {
+        state
+            .logger
+            .warning("mint indexer: invalid nft_contract.address, indexer disabled");
+        return;
+    };
+    loop {
+        let result = async {
+            let from_block = get_start_block(&state).await?;
+            let latest = state
+                .provider
+                .block_number()
+                .await
+                .map_err(|e| e.to_string())?;
+            if from_block > latest {
+                return Ok(true);
+            }
+            let to_block = latest.min(from_block + BLOCKS_PER_ITERATION - 1);
+            index_blocks(&state, contract, from_block, to_block).await?;
+            // the cursor only moves once every event of the range was handled
+            state
+                .db
+                .collection::<Document>("indexer_state")
+                .update_one(
+                    doc! { "name": INDEXER_NAME },
+                    doc! { "$set": { "next_block": (to_block + 1) as i64 } },
+                    UpdateOptions::builder().upsert(true).build(),
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok::<bool, String>(to_block == latest)
+        }
+        .await;
+        match result {
+            // keep catching up without waiting
+            Ok(false) => continue,
+            Ok(true) => {}
+            Err(e) => state.logger.warning(format!("mint indexer: {}", e)),
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+pub fn run_mint_indexer(state: &Arc<AppState>) {
+    tokio::spawn(index_mints(state.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felts(values: &[u64]) -> Vec<FieldElement> {
+        values
+            .iter()
+            .map(|&value| FieldElement::from(value))
+            .collect()
+    }
+
+    #[test]
+    fn parses_cairo_0_mints() {
+        let keys = vec![selector!("Transfer")];
+        assert_eq!(
+            parse_mint(&keys, &felts(&[0, 42, 1234, 0])),
+            Some((FieldElement::from(42_u64), FieldElement::from(1234_u64)))
+        );
+    }
+
+    #[test]
+    fn parses_cairo_1_mints() {
+        let mut keys = vec![selector!("Transfer")];
+        keys.extend(felts(&[0, 42, 1234, 0]));
+        assert_eq!(
+            parse_mint(&keys, &[]),
+            Some((FieldElement::from(42_u64), FieldElement::from(1234_u64)))
+        );
+    }
+
+    #[test]
+    fn ignores_other_events() {
+        let keys = vec![selector!("Transfer")];
+        // a transfer between two holders
+        assert_eq!(parse_mint(&keys, &felts(&[7, 42, 1234, 0])), None);
+        // a token id that wasn't issued by the server
+        assert_eq!(parse_mint(&keys, &felts(&[0, 42, 1234, 1])), None);
+        // an event without a token id
+        assert_eq!(parse_mint(&keys, &felts(&[0, 42])), None);
+    }
+}
@@ -1,6 +1,25 @@
+pub mod admin_audit;
+pub mod admin_session;
+pub mod boost_raffle;
+pub mod claim_rewards;
+pub mod discord;
 pub mod get_achievement;
 pub mod has_deployed_time;
+pub mod linked_accounts;
+pub mod mint_indexer;
+pub mod oauth_state;
+pub mod partner_webhook;
+pub mod quest_rewards;
+pub mod quiz_attempts;
+pub mod quiz_file;
+pub mod quiz_grading;
+pub mod quiz_order;
+pub mod reward_signer;
+pub mod twitter;
+pub mod verify_custom_api;
 pub mod verify_has_nft;
 pub mod verify_has_root_domain;
 pub mod verify_has_root_or_braavos_domain;
 pub mod verify_quiz;
+pub mod verify_signature;
+pub mod verify_task;
@@ -0,0 +1,114 @@
+use crate::{
+    models::{AppState, OAuthStateDocument},
+    utils::get_random_hex,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use mongodb::bson::doc;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use starknet::core::types::FieldElement;
+
+// lifetime of an OAuth state in seconds
+const STATE_EXPIRY: i64 = 600;
+
+// What the provider sends back to the callback, signed so it can't be forged
+// to credit another address
+pub_struct!(Debug, Clone, Serialize, Deserialize; OAuthStatePayload {
+    nonce: String,
+    platform: String,
+    address: String,
+    quest_id: Option<i64>,
+    task_id: Option<u32>,
+    expiry: i64,
+});
+
+fn get_mac(state: &AppState) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(state.conf.session.oauth_state_secret.as_bytes())
+        .expect("HMAC can take a key of any size")
+}
+
+// Issues a state for the wallet of the current session, its nonce is stored so
+// that the state can only be used once
+pub async fn issue_oauth_state(
+    state: &AppState,
+    platform: &str,
+    addr: FieldElement,
+    quest_id: Option<i64>,
+    task_id: Option<u32>,
+    code_verifier: Option<String>,
+) -> Result<String, String> {
+    let collection = state.db.collection::<OAuthStateDocument>("oauth_states");
+    let now = Utc::now().timestamp();
+
+    // clean up states that were never used
+    collection
+        .delete_many(doc! { "expiry": { "$lt": now } }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let payload = OAuthStatePayload {
+        nonce: get_random_hex(16),
+        platform: platform.to_string(),
+        address: addr.to_string(),
+        quest_id,
+        task_id,
+        expiry: now + STATE_EXPIRY,
+    };
+    let document = OAuthStateDocument {
+        state: payload.nonce.clone(),
+        platform: payload.platform.clone(),
+        address: payload.address.clone(),
+        code_verifier,
+        expiry: payload.expiry,
+    };
+    collection
+        .insert_one(document, None)
+        .await
+        .map_err(|_| "Error creating authorization".to_string())?;
+
+    let encoded = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload).map_err(|e| e.to_string())?);
+    let mut mac = get_mac(state);
+    mac.update(encoded.as_bytes());
+    let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    Ok(format!("{}.{}", encoded, signature))
+}
+
+// Only checks the signature, use consume_oauth_state to accept a callback
+pub fn decode_oauth_state(state: &AppState, token: &str) -> Option<OAuthStatePayload> {
+    let (encoded, signature) = token.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac = get_mac(state);
+    mac.update(encoded.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(encoded).ok()?).ok()
+}
+
+pub async fn consume_oauth_state(
+    state: &AppState,
+    platform: &str,
+    token: &str,
+) -> Result<(OAuthStatePayload, OAuthStateDocument), String> {
+    let invalid = || "Invalid or expired authorization, please try again".to_string();
+    let payload = decode_oauth_state(state, token).ok_or_else(invalid)?;
+    if payload.platform != platform || payload.expiry < Utc::now().timestamp() {
+        return Err(invalid());
+    }
+
+    let document = state
+        .db
+        .collection::<OAuthStateDocument>("oauth_states")
+        .find_one_and_delete(
+            doc! {
+                "state": &payload.nonce,
+                "platform": platform,
+                "address": &payload.address,
+            },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(invalid)?;
+    Ok((payload, document))
+}
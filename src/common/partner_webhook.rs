@@ -0,0 +1,275 @@
+use crate::{
+    common::linked_accounts::decrypt_token,
+    logger::Logger,
+    models::{AppState, PartnerDocument, WebhookDeliveryDocument},
+    utils::{is_duplicate_key_error, CompletedTasksTrait},
+};
+use axum::http::{HeaderMap, StatusCode};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use mongodb::{
+    bson::{doc, DateTime, Document},
+    options::IndexOptions,
+    Database, IndexModel,
+};
+use serde::Deserialize;
+use sha2::Sha256;
+use starknet::core::types::FieldElement;
+use std::time::Duration;
+
+// how far the timestamp of a delivery can be from the server time, in seconds
+pub const MAX_CLOCK_SKEW: i64 = 300;
+
+pub_struct!(Deserialize; WebhookBody {
+    address: FieldElement,
+    task_id: u32,
+});
+
+// Nonces only need to be remembered while their timestamp is accepted
+pub async fn init_partner_webhooks(db: &Database, logger: &Logger) {
+    let indexes = [
+        (
+            "partners",
+            IndexModel::builder()
+                .keys(doc! { "name": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        ),
+        (
+            "webhook_nonces",
+            IndexModel::builder()
+                .keys(doc! { "partner": 1, "nonce": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        ),
+        (
+            "webhook_nonces",
+            IndexModel::builder()
+                .keys(doc! { "created_at": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .expire_after(Duration::from_secs(2 * MAX_CLOCK_SKEW as u64))
+                        .build(),
+                )
+                .build(),
+        ),
+    ];
+    for (collection, index) in indexes {
+        if let Err(e) = db
+            .collection::<Document>(collection)
+            .create_index(index, None)
+            .await
+        {
+            logger.warning(format!("unable to create index on {}: {}", collection, e));
+        }
+    }
+}
+
+// Deliveries are refused with a 401, failures of the server are reported as a 500 so
+// that partners retry them
+type WebhookError = (StatusCode, String);
+
+fn rejected(message: impl ToString) -> WebhookError {
+    (StatusCode::UNAUTHORIZED, message.to_string())
+}
+
+fn internal(error: impl ToString) -> WebhookError {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
+
+fn get_header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, WebhookError> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| rejected(format!("Missing {} header", name)))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// The signature is hex(HMAC-SHA256(secret, "<timestamp>.<nonce>.<raw body>"))
+fn verify_signature(
+    secret: &str,
+    timestamp: &str,
+    nonce: &str,
+    body: &str,
+    signature: &str,
+) -> bool {
+    let Some(signature) = decode_hex(signature.trim_start_matches("sha256=")) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(format!("{}.{}.{}", timestamp, nonce, body).as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+async fn log_delivery(state: &AppState, delivery: WebhookDeliveryDocument) {
+    if let Err(e) = state
+        .db
+        .collection::<WebhookDeliveryDocument>("webhook_deliveries")
+        .insert_one(delivery, None)
+        .await
+    {
+        state
+            .logger
+            .warning(format!("unable to log webhook delivery: {}", e));
+    }
+}
+
+async fn process_webhook(
+    state: &AppState,
+    partner: &PartnerDocument,
+    headers: &HeaderMap,
+    body: &str,
+    delivery: &mut WebhookDeliveryDocument,
+) -> Result<(), WebhookError> {
+    let timestamp = get_header(headers, "x-timestamp")?;
+    let nonce = get_header(headers, "x-nonce")?;
+    let signature = get_header(headers, "x-signature")?;
+    delivery.nonce = Some(nonce.to_string());
+
+    let Some(secret) = &partner.webhook_secret else {
+        return Err(rejected("Webhooks are not enabled for this partner"));
+    };
+    let secret = decrypt_token(state, secret).map_err(internal)?;
+    if !verify_signature(&secret, timestamp, nonce, body, signature) {
+        return Err(rejected("Invalid signature"));
+    }
+    let timestamp = timestamp
+        .parse::<i64>()
+        .map_err(|_| rejected("Invalid timestamp"))?;
+    if (Utc::now().timestamp() - timestamp).abs() > MAX_CLOCK_SKEW {
+        return Err(rejected("Timestamp too far from the server time"));
+    }
+
+    // a nonce can only be used once, the unique index rejects replays
+    let nonces = state.db.collection::<Document>("webhook_nonces");
+    let nonce_filter = doc! { "partner": &partner.name, "nonce": nonce };
+    if let Err(e) = nonces
+        .insert_one(
+            doc! { "partner": &partner.name, "nonce": nonce, "created_at": DateTime::now() },
+            None,
+        )
+        .await
+    {
+        return Err(match is_duplicate_key_error(&e) {
+            true => rejected("Nonce already used"),
+            false => internal(e),
+        });
+    }
+
+    let body: WebhookBody =
+        serde_json::from_str(body).map_err(|e| rejected(format!("Invalid body: {}", e)))?;
+    delivery.address = Some(body.address.to_string());
+    delivery.task_id = Some(body.task_id);
+    let allowed = partner
+        .webhook_task_ids
+        .as_ref()
+        .is_some_and(|task_ids| task_ids.contains(&body.task_id.into()));
+    if !allowed {
+        return Err(rejected("Task not allowed for this partner"));
+    }
+
+    // partners retry failed deliveries with the same nonce, it is released when the
+    // completion couldn't be saved so that the retry goes through
+    if let Err(e) = state
+        .upsert_completed_task(body.address, body.task_id)
+        .await
+    {
+        if let Err(e) = nonces.delete_one(nonce_filter, None).await {
+            state
+                .logger
+                .warning(format!("unable to release webhook nonce {}: {}", nonce, e));
+        }
+        return Err(internal(e));
+    }
+    Ok(())
+}
+
+// Authenticates and applies a delivery of the partner named in the X-Partner
+// header, every delivery of a known partner is logged
+pub async fn handle_webhook(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<(), WebhookError> {
+    let name = get_header(headers, "x-partner")?;
+    let partner = state
+        .db
+        .collection::<PartnerDocument>("partners")
+        .find_one(doc! { "name": name }, None)
+        .await
+        .map_err(internal)?
+        .ok_or_else(|| rejected("Unknown partner"))?;
+
+    let mut delivery = WebhookDeliveryDocument {
+        partner: partner.name.clone(),
+        address: None,
+        task_id: None,
+        nonce: None,
+        accepted: false,
+        error: None,
+        timestamp: Utc::now().timestamp_millis(),
+    };
+    let res = process_webhook(state, &partner, headers, body, &mut delivery).await;
+    delivery.accepted = res.is_ok();
+    delivery.error = res.as_ref().err().map(|(_, e)| e.clone());
+    log_delivery(state, delivery).await;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, message: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(message.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn decodes_hex() {
+        assert_eq!(decode_hex("00ff10"), Some(vec![0, 255, 16]));
+        assert_eq!(decode_hex("ABcd"), Some(vec![171, 205]));
+        assert_eq!(decode_hex(""), Some(vec![]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(decode_hex("é0"), None);
+    }
+
+    #[test]
+    fn verifies_signatures() {
+        let check = |secret, timestamp, body, signature: &str| {
+            verify_signature(secret, timestamp, "nonce", body, signature)
+        };
+        let body = r#"{"address":"0x1","task_id":1}"#;
+        let signature = sign("secret", &format!("1700000000.nonce.{}", body));
+        assert!(check("secret", "1700000000", body, &signature));
+        assert!(check(
+            "secret",
+            "1700000000",
+            body,
+            &format!("sha256={}", signature)
+        ));
+
+        let tampered = r#"{"address":"0x2","task_id":1}"#;
+        assert!(!check("secret", "1700000000", tampered, &signature));
+        assert!(!check("secret", "1700000001", body, &signature));
+        assert!(!check("other", "1700000000", body, &signature));
+        assert!(!check("secret", "1700000000", body, "not hex"));
+    }
+}
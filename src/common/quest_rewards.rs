@@ -0,0 +1,433 @@
+use crate::{
+    logger::Logger,
+    models::{AppState, QuestDocument, QuestReward, TokenRewardClaimDocument},
+    utils::{bson_as_i64, get_boost_claim_hash, is_duplicate_key_error, to_hex},
+};
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{IndexOptions, UpdateOptions},
+    Database, IndexModel,
+};
+use starknet::core::types::FieldElement;
+use std::collections::HashSet;
+
+pub async fn init_quest_rewards(db: &Database, logger: &Logger) {
+    let unique = || IndexOptions::builder().unique(true).build();
+    let indexes = [
+        (
+            "token_reward_claims",
+            IndexModel::builder()
+                .keys(doc! { "address": 1, "claim_id": 1 })
+                .options(unique())
+                .build(),
+        ),
+        (
+            "token_reward_claims",
+            IndexModel::builder().keys(doc! { "quest_id": 1 }).build(),
+        ),
+        (
+            "reward_budgets",
+            IndexModel::builder()
+                .keys(doc! { "claim_id": 1 })
+                .options(unique())
+                .build(),
+        ),
+    ];
+    for (collection, index) in indexes {
+        if let Err(e) = db
+            .collection::<Document>(collection)
+            .create_index(index, None)
+            .await
+        {
+            logger.warning(format!("unable to create index on {}: {}", collection, e));
+        }
+    }
+}
+
+// Rewards of a quest, the ones of quests created before reward definitions are
+// their NFT levels and experience
+pub fn get_quest_rewards(quest: &QuestDocument) -> Vec<QuestReward> {
+    match &quest.rewards {
+        Some(rewards) => rewards.clone(),
+        None => vec![
+            QuestReward::Nft,
+            QuestReward::Xp {
+                amount: quest.experience,
+            },
+        ],
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<u128, String> {
+    match amount.parse::<u128>() {
+        Ok(amount) if amount > 0 => Ok(amount),
+        _ => Err(format!("Invalid token amount {}", amount)),
+    }
+}
+
+// Number of completers a token reward can pay, None when it is not capped
+pub fn get_recipients_cap(
+    amount: &str,
+    max_recipients: Option<u32>,
+    budget: Option<&str>,
+) -> Result<Option<u64>, String> {
+    let amount = parse_amount(amount)?;
+    let budget_cap = match budget {
+        Some(budget) => Some((parse_amount(budget)? / amount).min(u64::MAX as u128) as u64),
+        None => None,
+    };
+    let cap = match (max_recipients.map(u64::from), budget_cap) {
+        (Some(max_recipients), Some(budget_cap)) => Some(max_recipients.min(budget_cap)),
+        (max_recipients, budget_cap) => max_recipients.or(budget_cap),
+    };
+    match cap {
+        Some(0) => Err("The reward budget can't pay a single completer".to_string()),
+        cap => Ok(cap),
+    }
+}
+
+pub fn validate_rewards(rewards: &[QuestReward]) -> Result<(), String> {
+    let mut nft = false;
+    let mut xp = false;
+    let mut claim_ids = HashSet::new();
+    for reward in rewards {
+        match reward {
+            QuestReward::Nft if nft => return Err("Only one NFT reward is allowed".to_string()),
+            QuestReward::Nft => nft = true,
+            QuestReward::Xp { .. } if xp => return Err("Only one XP reward is allowed".to_string()),
+            QuestReward::Xp { amount } if *amount < 0 => {
+                return Err("XP rewards can't be negative".to_string())
+            }
+            QuestReward::Xp { .. } => xp = true,
+            QuestReward::Token {
+                claim_id,
+                token,
+                amount,
+                max_recipients,
+                budget,
+            } => {
+                if !claim_ids.insert(*claim_id) {
+                    return Err(format!("Duplicate claim id {}", claim_id));
+                }
+                FieldElement::from_hex_be(token)
+                    .map_err(|_| format!("Invalid token address {}", token))?;
+                get_recipients_cap(amount, *max_recipients, budget.as_deref())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Ids of the quests whose tasks were all completed by the address
+pub async fn get_completed_quest_ids(
+    db: &Database,
+    addr: &FieldElement,
+) -> Result<Vec<u32>, String> {
+    let pipeline = vec![
+        doc! {
+            "$match": doc! {
+                "address": addr.to_string()
+            }
+        },
+        doc! {
+            "$lookup": doc! {
+                "from": "tasks",
+                "localField": "task_id",
+                "foreignField": "id",
+                "as": "associatedTask"
+            }
+        },
+        doc! {
+            "$unwind": "$associatedTask"
+        },
+        doc! {
+            "$group": doc! {
+                "_id": "$associatedTask.quest_id",
+                "done": doc! {
+                    "$sum": 1
+                }
+            }
+        },
+        doc! {
+            "$lookup": doc! {
+                "from": "tasks",
+                "localField": "_id",
+                "foreignField": "quest_id",
+                "as": "tasks"
+            }
+        },
+        doc! {
+            "$match": doc! {
+                "$expr": doc! {
+                    "$eq": [
+                        "$done",
+                        doc! {
+                            "$size": "$tasks"
+                        }
+                    ]
+                }
+            }
+        },
+        doc! {
+            "$project": doc! {
+                "quest_id": "$_id",
+                "_id": 0
+            }
+        },
+    ];
+    let quests: Vec<Document> = db
+        .collection::<Document>("completed_tasks")
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|e| e.to_string())?
+        .try_collect()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(quests
+        .iter()
+        .filter_map(|quest| quest.get("quest_id").and_then(bson_as_i64))
+        .map(|quest_id| quest_id as u32)
+        .collect())
+}
+
+async fn has_completed_quest(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+) -> Result<bool, String> {
+    let task_ids: Vec<i64> = state
+        .db
+        .collection::<Document>("tasks")
+        .find(doc! { "quest_id": quest_id }, None)
+        .await
+        .map_err(|_| "Error querying tasks".to_string())?
+        .try_collect::<Vec<Document>>()
+        .await
+        .map_err(|_| "Error querying tasks".to_string())?
+        .iter()
+        .filter_map(|task| task.get("id").and_then(bson_as_i64))
+        .collect();
+    if task_ids.is_empty() {
+        return Ok(false);
+    }
+    let completed = state
+        .db
+        .collection::<Document>("completed_tasks")
+        .count_documents(
+            doc! { "address": addr.to_string(), "task_id": { "$in": &task_ids } },
+            None,
+        )
+        .await
+        .map_err(|_| "Error querying status".to_string())?;
+    Ok(completed as usize >= task_ids.len())
+}
+
+// Completers paid so far by a token reward
+pub async fn get_allocated_rewards(state: &AppState, claim_id: u32) -> Result<i64, String> {
+    let budget = state
+        .db
+        .collection::<Document>("reward_budgets")
+        .find_one(doc! { "claim_id": claim_id }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(budget
+        .and_then(|budget| budget.get("allocated").and_then(bson_as_i64))
+        .unwrap_or(0))
+}
+
+// Takes one of the cap places of a token reward. The conditional $inc can't go over
+// the cap, once the counter is full the upsert conflicts on the unique claim_id. The
+// first claims of a reward can also race to insert its counter, so a conflict is
+// retried once against the counter that now exists before reporting exhaustion.
+async fn reserve_reward(state: &AppState, claim_id: u32, cap: Option<u64>) -> Result<(), String> {
+    let filter = match cap {
+        Some(cap) => doc! { "claim_id": claim_id, "allocated": { "$lt": cap as i64 } },
+        None => doc! { "claim_id": claim_id },
+    };
+    for _ in 0..2 {
+        match state
+            .db
+            .collection::<Document>("reward_budgets")
+            .update_one(
+                filter.clone(),
+                doc! { "$inc": { "allocated": 1_i64 } },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await
+        {
+            Ok(_) => return Ok(()),
+            Err(e) if is_duplicate_key_error(&e) => continue,
+            Err(_) => return Err("Error reserving reward".to_string()),
+        }
+    }
+    Err("Reward budget exhausted".to_string())
+}
+
+async fn release_reward(state: &AppState, claim_id: u32) {
+    let _ = state
+        .db
+        .collection::<Document>("reward_budgets")
+        .update_one(
+            doc! { "claim_id": claim_id },
+            doc! { "$inc": { "allocated": -1_i64 } },
+            None,
+        )
+        .await;
+}
+
+// Claim parameters of a token reward for addr. The first request of a completer takes
+// a place in the budget of the reward, the next ones return the same claim.
+pub async fn get_token_reward_claim(
+    state: &AppState,
+    addr: &FieldElement,
+    quest_id: u32,
+    claim_id: u32,
+) -> Result<TokenRewardClaimDocument, String> {
+    let quest = state
+        .db
+        .collection::<QuestDocument>("quests")
+        .find_one(doc! { "id": quest_id }, None)
+        .await
+        .map_err(|_| "Error querying quest".to_string())?
+        .ok_or_else(|| "Quest not found".to_string())?;
+    let reward = get_quest_rewards(&quest).into_iter().find(
+        |reward| matches!(reward, QuestReward::Token { claim_id: id, .. } if *id == claim_id),
+    );
+    let Some(QuestReward::Token {
+        token,
+        amount,
+        max_recipients,
+        budget,
+        ..
+    }) = reward
+    else {
+        return Err("Reward not found".to_string());
+    };
+
+    let collection = state
+        .db
+        .collection::<TokenRewardClaimDocument>("token_reward_claims");
+    let filter = doc! { "address": addr.to_string(), "claim_id": claim_id };
+    let public_key = match state.boost_signer.public_key().await {
+        Ok(public_key) => to_hex(public_key),
+        Err(_) => return Err("Signature failed".to_string()),
+    };
+    if let Some(mut claim) = collection
+        .find_one(filter.clone(), None)
+        .await
+        .map_err(|_| "Error querying claims".to_string())?
+    {
+        if claim.signer_public_key.as_deref() == Some(public_key.as_str()) {
+            return Ok(claim);
+        }
+        // signed with a key the boost contract doesn't accept anymore
+        let (Ok(token_address), Ok(amount_value)) = (
+            FieldElement::from_hex_be(&claim.token),
+            parse_amount(&claim.amount),
+        ) else {
+            return Err("Invalid reward".to_string());
+        };
+        let hashed = get_boost_claim_hash(claim_id, amount_value, token_address, *addr);
+        let Ok(sig) = state.boost_signer.sign(&hashed).await else {
+            return Err("Signature failed".to_string());
+        };
+        claim.sig = (to_hex(sig.r), to_hex(sig.s));
+        claim.signer_public_key = Some(public_key);
+        collection
+            .update_one(
+                filter,
+                doc! { "$set": {
+                    "sig": [&claim.sig.0, &claim.sig.1],
+                    "signer_public_key": &claim.signer_public_key,
+                } },
+                None,
+            )
+            .await
+            .map_err(|_| "Error saving claim".to_string())?;
+        return Ok(claim);
+    }
+    if !has_completed_quest(state, addr, quest_id).await? {
+        return Err("User hasn't completed all tasks".to_string());
+    }
+
+    let cap = get_recipients_cap(&amount, max_recipients, budget.as_deref())?;
+    let (Ok(token_address), Ok(amount_value)) =
+        (FieldElement::from_hex_be(&token), parse_amount(&amount))
+    else {
+        return Err("Invalid reward".to_string());
+    };
+    reserve_reward(state, claim_id, cap).await?;
+
+    let hashed = get_boost_claim_hash(claim_id, amount_value, token_address, *addr);
+    let sig = match state.boost_signer.sign(&hashed).await {
+        Ok(sig) => sig,
+        Err(_) => {
+            release_reward(state, claim_id).await;
+            return Err("Signature failed".to_string());
+        }
+    };
+    let claim = TokenRewardClaimDocument {
+        address: addr.to_string(),
+        quest_id,
+        claim_id,
+        token,
+        amount,
+        sig: (to_hex(sig.r), to_hex(sig.s)),
+        signer_public_key: Some(public_key),
+        created_at: Utc::now().timestamp_millis(),
+    };
+    match collection.insert_one(&claim, None).await {
+        Ok(_) => Ok(claim),
+        // a concurrent request of the same completer saved its claim first
+        Err(e) if is_duplicate_key_error(&e) => {
+            release_reward(state, claim_id).await;
+            collection
+                .find_one(filter, None)
+                .await
+                .map_err(|_| "Error querying claims".to_string())?
+                .ok_or_else(|| "Error querying claims".to_string())
+        }
+        Err(_) => {
+            release_reward(state, claim_id).await;
+            Err("Error saving claim".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_is_the_lowest_limit() {
+        assert_eq!(get_recipients_cap("10", None, None), Ok(None));
+        assert_eq!(get_recipients_cap("10", Some(5), None), Ok(Some(5)));
+        assert_eq!(get_recipients_cap("10", None, Some("35")), Ok(Some(3)));
+        assert_eq!(get_recipients_cap("10", Some(2), Some("35")), Ok(Some(2)));
+    }
+
+    #[test]
+    fn invalid_caps_are_rejected() {
+        assert!(get_recipients_cap("0", None, None).is_err());
+        assert!(get_recipients_cap("-1", None, None).is_err());
+        assert!(get_recipients_cap("10", Some(0), None).is_err());
+        assert!(get_recipients_cap("10", None, Some("5")).is_err());
+    }
+
+    #[test]
+    fn validates_rewards() {
+        let token = |claim_id| QuestReward::Token {
+            claim_id,
+            token: "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7".to_string(),
+            amount: "1000".to_string(),
+            max_recipients: Some(100),
+            budget: None,
+        };
+        assert!(validate_rewards(&[QuestReward::Nft, token(1), token(2)]).is_ok());
+        assert!(validate_rewards(&[token(1), token(1)]).is_err());
+        assert!(validate_rewards(&[QuestReward::Nft, QuestReward::Nft]).is_err());
+        assert!(validate_rewards(&[QuestReward::Xp { amount: -1 }]).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    logger::Logger,
+    models::{
+        AppState, QuizAttemptCounterDocument, QuizAttemptDocument, QuizInsertDocument,
+        QuizQuestionResult,
+    },
+    utils::is_duplicate_key_error,
+};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Database, IndexModel,
+};
+use starknet::core::types::FieldElement;
+
+pub const DEFAULT_PASSING_SCORE: u32 = 100;
+
+pub enum QuizAttemptStatus {
+    // the quiz was already passed, it doesn't need to be graded again
+    Passed,
+    Allowed(QuizAttemptReservation),
+    Denied(String),
+}
+
+// Attempt taken by reserve_attempt, given back with release_attempt when the answers
+// can't be graded
+pub struct QuizAttemptReservation {
+    reserved_at: i64,
+    previous_attempt: i64,
+}
+
+// Each address has a counter per quiz, reserving an attempt updates it atomically so
+// that concurrent submissions can't go over max_attempts or skip the cooldown
+pub async fn init_quiz_attempts(db: &Database, logger: &Logger) {
+    let indexes = [
+        (
+            "quiz_attempts",
+            IndexModel::builder()
+                .keys(doc! { "quiz_id": 1, "address": 1, "timestamp": -1 })
+                .build(),
+        ),
+        (
+            "quiz_attempt_counters",
+            IndexModel::builder()
+                .keys(doc! { "quiz_id": 1, "address": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        ),
+    ];
+    for (collection, index) in indexes {
+        if let Err(e) = db
+            .collection::<Document>(collection)
+            .create_index(index, None)
+            .await
+        {
+            logger.warning(format!("unable to create index on {}: {}", collection, e));
+        }
+    }
+
+    // counters start from the attempts recorded before they existed
+    let pipeline = vec![
+        doc! {
+            "$group": {
+                "_id": { "quiz_id": "$quiz_id", "address": "$address" },
+                "attempts": { "$sum": 1 },
+                "last_attempt": { "$max": "$timestamp" },
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "quiz_id": "$_id.quiz_id",
+                "address": "$_id.address",
+                "attempts": 1,
+                "last_attempt": 1,
+            }
+        },
+        doc! {
+            "$merge": {
+                "into": "quiz_attempt_counters",
+                "on": ["quiz_id", "address"],
+                "whenMatched": "keepExisting",
+                "whenNotMatched": "insert",
+            }
+        },
+    ];
+    if let Err(e) = db
+        .collection::<Document>("quiz_attempts")
+        .aggregate(pipeline, None)
+        .await
+    {
+        logger.warning(format!("unable to backfill quiz_attempt_counters: {}", e));
+    }
+}
+
+pub fn validate_quiz_limits(
+    passing_score: Option<u32>,
+    max_attempts: Option<u32>,
+    cooldown: Option<i64>,
+) -> Result<(), String> {
+    if passing_score.map_or(false, |score| score > 100) {
+        return Err("passing_score must be a percentage between 0 and 100".to_string());
+    }
+    if max_attempts == Some(0) {
+        return Err("max_attempts must be at least 1".to_string());
+    }
+    if cooldown.map_or(false, |cooldown| cooldown < 0) {
+        return Err("cooldown can't be negative".to_string());
+    }
+    Ok(())
+}
+
+// Takes one of the attempts of addr if the limits of the quiz allow it, before the
+// answers are graded. The counter is only updated when it is under max_attempts and out
+// of the cooldown, otherwise the upsert hits the unique index and the attempt is denied.
+pub async fn reserve_attempt(
+    state: &AppState,
+    quiz: &QuizInsertDocument,
+    addr: FieldElement,
+) -> Result<QuizAttemptStatus, String> {
+    let address = addr.to_string();
+    let passed = state
+        .db
+        .collection::<QuizAttemptDocument>("quiz_attempts")
+        .find_one(
+            doc! { "quiz_id": quiz.id, "address": &address, "passed": true },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if passed.is_some() {
+        return Ok(QuizAttemptStatus::Passed);
+    }
+
+    let now = Utc::now().timestamp_millis();
+    let mut filter = doc! { "quiz_id": quiz.id, "address": &address };
+    if let Some(max_attempts) = quiz.max_attempts {
+        filter.insert("attempts", doc! { "$lt": max_attempts as i64 });
+    }
+    if let Some(cooldown) = quiz.cooldown {
+        filter.insert("last_attempt", doc! { "$lte": now - cooldown * 1000 });
+    }
+    let collection = state
+        .db
+        .collection::<QuizAttemptCounterDocument>("quiz_attempt_counters");
+    // the first attempts of an address can race to insert its counter, the loser
+    // retries once against the counter that now exists
+    for _ in 0..2 {
+        match collection
+            .find_one_and_update(
+                filter.clone(),
+                doc! { "$inc": { "attempts": 1 }, "$set": { "last_attempt": now } },
+                FindOneAndUpdateOptions::builder()
+                    .upsert(true)
+                    .return_document(ReturnDocument::Before)
+                    .build(),
+            )
+            .await
+        {
+            Ok(previous) => {
+                return Ok(QuizAttemptStatus::Allowed(QuizAttemptReservation {
+                    reserved_at: now,
+                    previous_attempt: previous.map_or(0, |counter| counter.last_attempt),
+                }))
+            }
+            Err(e) if is_duplicate_key_error(&e) => continue,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let counter = collection
+        .find_one(doc! { "quiz_id": quiz.id, "address": &address }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(counter) = counter else {
+        return Err("Error reserving quiz attempt".to_string());
+    };
+    if quiz
+        .max_attempts
+        .is_some_and(|max_attempts| counter.attempts >= max_attempts as i64)
+    {
+        return Ok(QuizAttemptStatus::Denied(
+            "No attempts left for this quiz".to_string(),
+        ));
+    }
+    let wait = counter.last_attempt + quiz.cooldown.unwrap_or(0) * 1000 - now;
+    Ok(QuizAttemptStatus::Denied(format!(
+        "Please wait {} seconds before trying again",
+        (wait.max(1) + 999) / 1000
+    )))
+}
+
+// Gives back an attempt which couldn't be graded, along with the cooldown it started,
+// unless another attempt was reserved since
+pub async fn release_attempt(
+    state: &AppState,
+    quiz: &QuizInsertDocument,
+    addr: FieldElement,
+    reservation: QuizAttemptReservation,
+) {
+    if let Err(e) = state
+        .db
+        .collection::<QuizAttemptCounterDocument>("quiz_attempt_counters")
+        .update_one(
+            doc! {
+                "quiz_id": quiz.id,
+                "address": addr.to_string(),
+                "last_attempt": reservation.reserved_at,
+            },
+            doc! {
+                "$inc": { "attempts": -1 },
+                "$set": { "last_attempt": reservation.previous_attempt },
+            },
+            None,
+        )
+        .await
+    {
+        state.logger.warning(format!(
+            "unable to release attempt of quiz {} for {}: {}",
+            quiz.id, addr, e
+        ));
+    }
+}
+
+pub async fn record_attempt(
+    state: &AppState,
+    quiz: &QuizInsertDocument,
+    addr: FieldElement,
+    results: Vec<QuizQuestionResult>,
+) -> Result<QuizAttemptDocument, String> {
+    let correct = results.iter().filter(|result| result.correct).count();
+    let score = match results.len() {
+        0 => 100,
+        total => (correct * 100 / total) as u32,
+    };
+    let attempt = QuizAttemptDocument {
+        quiz_id: quiz.id as i64,
+        address: addr.to_string(),
+        results,
+        score,
+        passed: score >= quiz.passing_score.unwrap_or(DEFAULT_PASSING_SCORE),
+        timestamp: Utc::now().timestamp_millis(),
+    };
+    state
+        .db
+        .collection::<QuizAttemptDocument>("quiz_attempts")
+        .insert_one(&attempt, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(attempt)
+}
@@ -0,0 +1,271 @@
+use crate::{
+    common::{quiz_attempts::validate_quiz_limits, quiz_grading::validate_question},
+    config::{Quiz, QuizQuestion},
+    models::{AppState, QuestTaskDocument, QuizInsertDocument, QuizQuestionDocument},
+    utils::{get_next_id, run_transaction},
+};
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::FindOptions};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuizFileFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+// Ids of the documents created by an import
+pub_struct!(Debug; ImportedQuiz {
+    quiz_id: u32,
+    task_id: i32,
+    question_ids: Vec<i64>,
+});
+
+pub fn parse_quiz(content: &str, format: QuizFileFormat) -> Result<Quiz, String> {
+    match format {
+        QuizFileFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        QuizFileFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn serialize_quiz(quiz: &Quiz, format: QuizFileFormat) -> Result<String, String> {
+    match format {
+        QuizFileFormat::Json => serde_json::to_string_pretty(quiz).map_err(|e| e.to_string()),
+        QuizFileFormat::Toml => toml::to_string(quiz).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn validate_quiz(quiz: &Quiz) -> Result<(), String> {
+    if quiz.name.trim().is_empty() {
+        return Err("The quiz needs a name".to_string());
+    }
+    if quiz.questions.is_empty() {
+        return Err("The quiz needs at least one question".to_string());
+    }
+    validate_quiz_limits(quiz.passing_score, quiz.max_attempts, quiz.cooldown)?;
+    for (i, question) in quiz.questions.iter().enumerate() {
+        if question.question.trim().is_empty() {
+            return Err(format!("Question {} has no text", i + 1));
+        }
+        validate_question(question).map_err(|e| format!("Question {}: {}", i + 1, e))?;
+    }
+    Ok(())
+}
+
+// Quiz with its task fields and its questions in canonical order
+pub async fn export_quiz(state: &AppState, quiz_id: i64) -> Result<Option<Quiz>, String> {
+    let Some(quiz) = state
+        .db
+        .collection::<QuizInsertDocument>("quizzes")
+        .find_one(doc! { "id": quiz_id }, None)
+        .await
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let task = state
+        .db
+        .collection::<QuestTaskDocument>("tasks")
+        .find_one(doc! { "quiz_name": quiz_id }, None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let options = FindOptions::builder().sort(doc! { "id": 1 }).build();
+    let questions: Vec<QuizQuestion> = state
+        .db
+        .collection::<QuizQuestion>("quiz_questions")
+        .find(doc! { "quiz_id": quiz_id }, options)
+        .await
+        .map_err(|e| e.to_string())?
+        .try_collect()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(Quiz {
+        name: quiz.name,
+        desc: quiz.desc,
+        intro: Some(quiz.intro),
+        help_link: task.as_ref().map(|task| task.href.clone()),
+        cta: task.as_ref().map(|task| task.cta.clone()),
+        passing_score: quiz.passing_score,
+        max_attempts: quiz.max_attempts,
+        cooldown: quiz.cooldown,
+        questions,
+    }))
+}
+
+// Creates the quiz, its task and its questions in a single transaction
+pub async fn import_quiz(
+    state: &AppState,
+    quest_id: i64,
+    quiz: &Quiz,
+) -> Result<ImportedQuiz, String> {
+    validate_quiz(quiz)?;
+
+    let quiz_id = get_next_id(&state.db, "quizzes").await? as u32;
+    let task_id = get_next_id(&state.db, "tasks").await? as i32;
+    let mut question_ids = Vec::with_capacity(quiz.questions.len());
+    for _ in &quiz.questions {
+        question_ids.push(get_next_id(&state.db, "quiz_questions").await?);
+    }
+
+    let quiz_document = QuizInsertDocument {
+        id: quiz_id,
+        name: quiz.name.clone(),
+        desc: quiz.desc.clone(),
+        intro: quiz.intro.clone().unwrap_or_default(),
+        passing_score: quiz.passing_score,
+        max_attempts: quiz.max_attempts,
+        cooldown: quiz.cooldown,
+    };
+    let task_document = QuestTaskDocument {
+        name: quiz.name.clone(),
+        desc: quiz.desc.clone(),
+        href: quiz.help_link.clone().unwrap_or_default(),
+        total_amount: None,
+        cta: quiz.cta.clone().unwrap_or_default(),
+        quest_id,
+        id: task_id,
+        verify_endpoint: "/quests/verify_quiz".to_string(),
+        verify_endpoint_type: "quiz".to_string(),
+        quiz_name: Some(quiz_id as i64),
+        task_type: Some("quiz".to_string()),
+        discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
+        verify_redirect: None,
+        contracts: None,
+        api_url: None,
+        regex: None,
+        verifier: None,
+        custom_api: None,
+    };
+    let question_documents: Vec<QuizQuestionDocument> = quiz
+        .questions
+        .iter()
+        .zip(&question_ids)
+        .map(|(question, id)| QuizQuestionDocument {
+            id: *id,
+            quiz_id: quiz_id as i64,
+            question: question.question.clone(),
+            options: question.options.clone(),
+            correct_answers: question
+                .correct_answers
+                .iter()
+                .flatten()
+                .map(|&answer| answer as i64)
+                .collect(),
+            kind: question.kind.as_str().to_string(),
+            layout: question.layout.clone(),
+            correct_order: question.correct_order.clone(),
+            image_for_layout: question.image_for_layout.clone(),
+            correct_value: question.correct_value,
+            tolerance: question.tolerance,
+            accepted_answers: question.accepted_answers.clone(),
+            answer_regex: question.answer_regex.clone(),
+        })
+        .collect();
+
+    let db = state.db.clone();
+    run_transaction(&state.client, move |session| {
+        let (db, quiz_document, task_document, question_documents) = (
+            db.clone(),
+            quiz_document.clone(),
+            task_document.clone(),
+            question_documents.clone(),
+        );
+        Box::pin(async move {
+            db.collection::<QuizInsertDocument>("quizzes")
+                .insert_one_with_session(quiz_document, None, session)
+                .await?;
+            db.collection::<QuestTaskDocument>("tasks")
+                .insert_one_with_session(task_document, None, session)
+                .await?;
+            db.collection::<QuizQuestionDocument>("quiz_questions")
+                .insert_many_with_session(question_documents, None, session)
+                .await?;
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(ImportedQuiz {
+        quiz_id,
+        task_id,
+        question_ids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QuizQuestionType;
+
+    fn get_question(kind: QuizQuestionType) -> QuizQuestion {
+        QuizQuestion {
+            id: None,
+            kind,
+            layout: "default".to_string(),
+            question: "Which layer is Starknet?".to_string(),
+            options: vec![],
+            correct_answers: None,
+            correct_order: None,
+            image_for_layout: None,
+            correct_value: None,
+            tolerance: None,
+            accepted_answers: None,
+            answer_regex: None,
+        }
+    }
+
+    fn get_quiz() -> Quiz {
+        let mut choice = get_question(QuizQuestionType::TextChoice);
+        choice.options = vec!["L1".to_string(), "L2".to_string(), "L3".to_string()];
+        choice.correct_answers = Some(vec![1]);
+        let mut numeric = get_question(QuizQuestionType::Numeric);
+        numeric.correct_value = Some(2.5);
+        numeric.tolerance = Some(0.1);
+        let mut short_text = get_question(QuizQuestionType::ShortText);
+        short_text.accepted_answers = Some(vec!["Cairo".to_string()]);
+        short_text.answer_regex = Some("^cairo( 1)?$".to_string());
+        Quiz {
+            name: "Starknet basics".to_string(),
+            desc: "A \"quiz\" about Starknet".to_string(),
+            intro: Some("Let's go".to_string()),
+            help_link: Some("https://starknet.io".to_string()),
+            cta: None,
+            passing_score: Some(80),
+            max_attempts: Some(3),
+            cooldown: Some(3600),
+            questions: vec![choice, numeric, short_text],
+        }
+    }
+
+    #[test]
+    fn quiz_files_round_trip() {
+        let quiz = get_quiz();
+        for format in [QuizFileFormat::Json, QuizFileFormat::Toml] {
+            let content = serialize_quiz(&quiz, format).unwrap();
+            let parsed = parse_quiz(&content, format).unwrap();
+            assert_eq!(
+                serde_json::to_value(&parsed).unwrap(),
+                serde_json::to_value(&quiz).unwrap(),
+                "{:?}",
+                format
+            );
+            assert_eq!(serialize_quiz(&parsed, format).unwrap(), content);
+            assert!(validate_quiz(&parsed).is_ok());
+        }
+    }
+
+    #[test]
+    fn formats_are_not_mixed_up() {
+        let json = serialize_quiz(&get_quiz(), QuizFileFormat::Json).unwrap();
+        assert!(parse_quiz(&json, QuizFileFormat::Toml).is_err());
+        let toml = serialize_quiz(&get_quiz(), QuizFileFormat::Toml).unwrap();
+        assert!(parse_quiz(&toml, QuizFileFormat::Json).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use crate::config::{QuizQuestion, QuizQuestionType};
+use crate::models::QuizAnswer;
+use mongodb::bson::{from_document, Document};
+use regex::RegexBuilder;
+
+// Expected order of the options of an ordering question, as option indices. It is read
+// from correct_answers, or from correct_order which lists the options themselves.
+fn get_correct_order(question: &QuizQuestion) -> Option<Vec<usize>> {
+    match &question.correct_answers {
+        Some(correct_answers) if !correct_answers.is_empty() => {
+            return Some(correct_answers.clone())
+        }
+        _ => {}
+    }
+    question
+        .correct_order
+        .as_ref()?
+        .iter()
+        .map(|option| question.options.iter().position(|o| o == option))
+        .collect()
+}
+
+fn grade_choices(question: &QuizQuestion, answers: &[usize]) -> bool {
+    let Some(correct_answers) = &question.correct_answers else {
+        return false;
+    };
+    let mut correct_answers = correct_answers.clone();
+    let mut answers = answers.to_vec();
+    correct_answers.sort_unstable();
+    answers.sort_unstable();
+    correct_answers == answers
+}
+
+fn grade_ordering(question: &QuizQuestion, answers: &[usize]) -> bool {
+    match get_correct_order(question) {
+        Some(correct_order) => correct_order == answers,
+        None => false,
+    }
+}
+
+fn grade_numeric(question: &QuizQuestion, answer: &QuizAnswer) -> bool {
+    let value = match answer {
+        QuizAnswer::Number(value) => *value,
+        QuizAnswer::Text(text) => match text.trim().parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => return false,
+        },
+        QuizAnswer::Choices(_) => return false,
+    };
+    let Some(correct_value) = question.correct_value else {
+        return false;
+    };
+    let tolerance = question.tolerance.unwrap_or(0.0).abs();
+    value.is_finite() && (value - correct_value).abs() <= tolerance
+}
+
+fn grade_short_text(question: &QuizQuestion, answer: &QuizAnswer) -> bool {
+    let QuizAnswer::Text(text) = answer else {
+        return false;
+    };
+    let text = text.trim();
+    let accepted = question
+        .accepted_answers
+        .as_ref()
+        .map_or(false, |accepted| {
+            accepted
+                .iter()
+                .any(|accepted| accepted.trim().to_lowercase() == text.to_lowercase())
+        });
+    accepted
+        || question.answer_regex.as_ref().map_or(false, |regex| {
+            RegexBuilder::new(regex)
+                .case_insensitive(true)
+                .build()
+                .map_or(false, |regex| regex.is_match(text))
+        })
+}
+
+pub fn grade_question(question: &QuizQuestion, answer: &QuizAnswer) -> bool {
+    match (&question.kind, answer) {
+        (
+            QuizQuestionType::TextChoice | QuizQuestionType::ImageChoice,
+            QuizAnswer::Choices(answers),
+        ) => grade_choices(question, answers),
+        (QuizQuestionType::Ordering, QuizAnswer::Choices(answers)) => {
+            grade_ordering(question, answers)
+        }
+        (QuizQuestionType::Numeric, answer) => grade_numeric(question, answer),
+        (QuizQuestionType::ShortText, answer) => grade_short_text(question, answer),
+        _ => false,
+    }
+}
+
+// Grades answers given in the canonical order of the questions, a missing answer is wrong
+pub fn grade_quiz(questions: &[QuizQuestion], answers: &[QuizAnswer]) -> Vec<bool> {
+    questions
+        .iter()
+        .enumerate()
+        .map(|(i, question)| {
+            answers
+                .get(i)
+                .map_or(false, |answer| grade_question(question, answer))
+        })
+        .collect()
+}
+
+// Checks that a question can be graded before it is saved
+pub fn validate_question(question: &QuizQuestion) -> Result<(), String> {
+    let in_options = |indices: &Vec<usize>| indices.iter().all(|&i| i < question.options.len());
+    match question.kind {
+        QuizQuestionType::TextChoice | QuizQuestionType::ImageChoice => {
+            match &question.correct_answers {
+                Some(answers) if !answers.is_empty() && in_options(answers) => Ok(()),
+                _ => Err("correct_answers must list valid option indices".to_string()),
+            }
+        }
+        QuizQuestionType::Ordering => match get_correct_order(question) {
+            Some(order) if order.len() == question.options.len() && in_options(&order) => Ok(()),
+            _ => Err("correct_answers or correct_order must order all the options".to_string()),
+        },
+        QuizQuestionType::Numeric => {
+            let valid_tolerance = question
+                .tolerance
+                .map_or(true, |tolerance| tolerance.is_finite() && tolerance >= 0.0);
+            match question.correct_value {
+                Some(value) if value.is_finite() && valid_tolerance => Ok(()),
+                _ => Err("numeric questions need a correct_value and a positive tolerance"
+                    .to_string()),
+            }
+        }
+        QuizQuestionType::ShortText => {
+            if let Some(regex) = &question.answer_regex {
+                RegexBuilder::new(regex)
+                    .build()
+                    .map_err(|e| format!("Invalid answer_regex: {}", e))?;
+            }
+            let has_answers = question
+                .accepted_answers
+                .as_ref()
+                .map_or(false, |answers| !answers.is_empty());
+            if !has_answers && question.answer_regex.is_none() {
+                return Err(
+                    "short text questions need accepted_answers or an answer_regex".to_string(),
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+// Same as validate_question for a quiz_questions document
+pub fn validate_question_document(document: Document) -> Result<(), String> {
+    let question: QuizQuestion = from_document(document).map_err(|e| e.to_string())?;
+    validate_question(&question)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(kind: QuizQuestionType) -> QuizQuestion {
+        QuizQuestion {
+            id: Some(1),
+            kind,
+            layout: "default".to_string(),
+            question: "question".to_string(),
+            options: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            correct_answers: None,
+            correct_order: None,
+            image_for_layout: None,
+            correct_value: None,
+            tolerance: None,
+            accepted_answers: None,
+            answer_regex: None,
+        }
+    }
+
+    #[test]
+    fn text_choice_ignores_answers_order() {
+        let mut q = question(QuizQuestionType::TextChoice);
+        q.correct_answers = Some(vec![0, 2]);
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![2, 0])));
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![0, 2])));
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0])));
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0, 1, 2])));
+    }
+
+    #[test]
+    fn image_choice_ignores_answers_order() {
+        let mut q = question(QuizQuestionType::ImageChoice);
+        q.correct_answers = Some(vec![1, 0]);
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![0, 1])));
+    }
+
+    #[test]
+    fn ordering_uses_correct_order_without_correct_answers() {
+        let mut q = question(QuizQuestionType::Ordering);
+        q.correct_order = Some(vec!["c".to_string(), "a".to_string(), "b".to_string()]);
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![2, 0, 1])));
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0, 1, 2])));
+    }
+
+    #[test]
+    fn ordering_ignores_empty_correct_answers() {
+        let mut q = question(QuizQuestionType::Ordering);
+        q.correct_answers = Some(vec![]);
+        q.correct_order = Some(vec!["b".to_string(), "c".to_string(), "a".to_string()]);
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![1, 2, 0])));
+    }
+
+    #[test]
+    fn ordering_keeps_answers_order() {
+        let mut q = question(QuizQuestionType::Ordering);
+        q.correct_answers = Some(vec![1, 2, 0]);
+        assert!(grade_question(&q, &QuizAnswer::Choices(vec![1, 2, 0])));
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0, 1, 2])));
+    }
+
+    #[test]
+    fn misconfigured_questions_are_wrong() {
+        let q = question(QuizQuestionType::Ordering);
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0, 1, 2])));
+        let q = question(QuizQuestionType::TextChoice);
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0])));
+        let q = question(QuizQuestionType::Numeric);
+        assert!(!grade_question(&q, &QuizAnswer::Number(1.0)));
+    }
+
+    #[test]
+    fn numeric_accepts_tolerance() {
+        let mut q = question(QuizQuestionType::Numeric);
+        q.correct_value = Some(2.5);
+        q.tolerance = Some(0.01);
+        assert!(grade_question(&q, &QuizAnswer::Number(2.505)));
+        assert!(grade_question(&q, &QuizAnswer::Text(" 2.49 ".to_string())));
+        assert!(!grade_question(&q, &QuizAnswer::Number(2.6)));
+        assert!(!grade_question(&q, &QuizAnswer::Number(f64::NAN)));
+        assert!(!grade_question(&q, &QuizAnswer::Text("pi".to_string())));
+    }
+
+    #[test]
+    fn short_text_is_case_insensitive() {
+        let mut q = question(QuizQuestionType::ShortText);
+        q.accepted_answers = Some(vec!["Starknet".to_string()]);
+        assert!(grade_question(&q, &QuizAnswer::Text("  STARKNET ".to_string())));
+        assert!(!grade_question(&q, &QuizAnswer::Text("ethereum".to_string())));
+        assert!(!grade_question(&q, &QuizAnswer::Choices(vec![0])));
+    }
+
+    #[test]
+    fn short_text_matches_regex() {
+        let mut q = question(QuizQuestionType::ShortText);
+        q.answer_regex = Some("^cairo( 1)?$".to_string());
+        assert!(grade_question(&q, &QuizAnswer::Text("Cairo 1".to_string())));
+        assert!(!grade_question(&q, &QuizAnswer::Text("solidity".to_string())));
+    }
+
+    #[test]
+    fn missing_answers_are_wrong() {
+        let mut q = question(QuizQuestionType::TextChoice);
+        q.correct_answers = Some(vec![0]);
+        let results = grade_quiz(&[q.clone(), q], &[QuizAnswer::Choices(vec![0])]);
+        assert_eq!(results, vec![true, false]);
+    }
+
+    #[test]
+    fn validates_questions() {
+        let mut q = question(QuizQuestionType::TextChoice);
+        q.correct_answers = Some(vec![3]);
+        assert!(validate_question(&q).is_err());
+        q.correct_answers = Some(vec![2]);
+        assert!(validate_question(&q).is_ok());
+
+        let mut q = question(QuizQuestionType::ShortText);
+        q.answer_regex = Some("(".to_string());
+        assert!(validate_question(&q).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{models::QuizAnswer, utils::to_hex};
+use hmac::{Hmac, Mac};
+use mongodb::bson::{Bson, Document};
+use sha2::Sha256;
+use starknet::core::types::FieldElement;
+
+// Order in which a given address sees the items of a quiz: permutation[displayed] = canonical.
+// Deterministic so that the answers sent back to /quests/verify_quiz can be mapped back,
+// but different for each address so that answer keys can't be shared between users. Keyed
+// with a server secret, otherwise anyone could compute the order of any address.
+pub fn get_permutation(
+    secret: &str,
+    addr: FieldElement,
+    quiz_id: i64,
+    scope: &str,
+    len: usize,
+) -> Vec<usize> {
+    let addr = to_hex(addr);
+    let mut indexed: Vec<(Vec<u8>, usize)> = (0..len)
+        .map(|i| {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .expect("HMAC can take a key of any size");
+            mac.update(format!("{}:{}:{}:{}", addr, quiz_id, scope, i).as_bytes());
+            (mac.finalize().into_bytes().to_vec(), i)
+        })
+        .collect();
+    indexed.sort();
+    indexed.into_iter().map(|(_, i)| i).collect()
+}
+
+fn get_options_scope(question_index: usize) -> String {
+    format!("question_{}", question_index)
+}
+
+fn permute<T: Clone>(items: &[T], permutation: &[usize]) -> Vec<T> {
+    permutation.iter().map(|&i| items[i].clone()).collect()
+}
+
+// Reorders the questions of a quiz document (sorted by id) and the options of each question
+pub fn shuffle_quiz(quiz: &mut Document, secret: &str, addr: FieldElement, quiz_id: i64) {
+    let Ok(questions) = quiz.get_array_mut("questions") else {
+        return;
+    };
+
+    for (i, question) in questions.iter_mut().enumerate() {
+        if let Bson::Document(question) = question {
+            if let Ok(options) = question.get_array_mut("options") {
+                let permutation =
+                    get_permutation(secret, addr, quiz_id, &get_options_scope(i), options.len());
+                *options = permute(options, &permutation);
+            }
+        }
+    }
+
+    let permutation = get_permutation(secret, addr, quiz_id, "questions", questions.len());
+    *questions = permute(questions, &permutation);
+}
+
+// Maps the answers given in the order shown to addr back to canonical question and
+// option indices. options_count holds the number of options of each canonical question.
+// Returns None if the answers don't fit the quiz.
+pub fn get_canonical_answers(
+    secret: &str,
+    addr: FieldElement,
+    quiz_id: i64,
+    options_count: &[usize],
+    user_answers_list: &[QuizAnswer],
+) -> Option<Vec<QuizAnswer>> {
+    if user_answers_list.len() != options_count.len() {
+        return None;
+    }
+
+    let questions_permutation =
+        get_permutation(secret, addr, quiz_id, "questions", options_count.len());
+    let mut canonical_answers = vec![QuizAnswer::Choices(Vec::new()); options_count.len()];
+    for (displayed, user_answers) in user_answers_list.iter().enumerate() {
+        let question_index = questions_permutation[displayed];
+        canonical_answers[question_index] = match user_answers {
+            QuizAnswer::Choices(choices) => {
+                let options_permutation = get_permutation(
+                    secret,
+                    addr,
+                    quiz_id,
+                    &get_options_scope(question_index),
+                    options_count[question_index],
+                );
+                QuizAnswer::Choices(
+                    choices
+                        .iter()
+                        .map(|&answer| options_permutation.get(answer).copied())
+                        .collect::<Option<Vec<usize>>>()?,
+                )
+            }
+            // numeric and short text answers don't refer to options
+            answer => answer.clone(),
+        };
+    }
+    Some(canonical_answers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::doc;
+
+    // question q has the options "q:0", "q:1"... in the canonical order
+    fn get_quiz(options_count: &[usize]) -> Document {
+        let questions: Vec<Document> = options_count
+            .iter()
+            .enumerate()
+            .map(|(q, &count)| {
+                let options: Vec<String> = (0..count).map(|o| format!("{}:{}", q, o)).collect();
+                doc! { "id": q as i64, "options": options }
+            })
+            .collect();
+        doc! { "questions": questions }
+    }
+
+    #[test]
+    fn shuffled_answers_map_back_to_canonical_indices() {
+        let options_count = [4, 3, 5, 2, 4];
+        let addr = FieldElement::from(42_u64);
+        let mut quiz = get_quiz(&options_count);
+        shuffle_quiz(&mut quiz, "secret", addr, 7);
+
+        // the user picks the option o = q % count of each question, where it is displayed
+        let displayed: Vec<QuizAnswer> = quiz
+            .get_array("questions")
+            .unwrap()
+            .iter()
+            .map(|question| {
+                let question = question.as_document().unwrap();
+                let q = question.get_i64("id").unwrap() as usize;
+                let expected = format!("{}:{}", q, q % options_count[q]);
+                let position = question
+                    .get_array("options")
+                    .unwrap()
+                    .iter()
+                    .position(|option| option.as_str() == Some(expected.as_str()))
+                    .unwrap();
+                QuizAnswer::Choices(vec![position])
+            })
+            .collect();
+
+        let canonical =
+            get_canonical_answers("secret", addr, 7, &options_count, &displayed).unwrap();
+        let expected: Vec<QuizAnswer> = options_count
+            .iter()
+            .enumerate()
+            .map(|(q, count)| QuizAnswer::Choices(vec![q % count]))
+            .collect();
+        assert_eq!(canonical, expected);
+    }
+
+    #[test]
+    fn order_depends_on_the_secret() {
+        let addr = FieldElement::from(42_u64);
+        let permutation = get_permutation("secret", addr, 7, "questions", 12);
+        assert_eq!(
+            permutation,
+            get_permutation("secret", addr, 7, "questions", 12)
+        );
+        assert_ne!(
+            permutation,
+            get_permutation("other", addr, 7, "questions", 12)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{config::SignerConfig, utils::to_hex};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use starknet::{
+    core::{crypto::Signature, types::FieldElement},
+    signers::SigningKey,
+};
+use std::{env, sync::Arc, time::Duration};
+
+// Signs the reward hashes checked by the NFT and boost contracts
+#[async_trait]
+pub trait RewardSigner: Send + Sync {
+    async fn sign(&self, hash: &FieldElement) -> Result<Signature, String>;
+
+    // key the contracts must be configured with, it changes when the key is rotated
+    async fn public_key(&self) -> Result<FieldElement, String>;
+}
+
+// Key held in memory, read from the config or from an encrypted keystore file
+pub struct LocalKeySigner {
+    key: SigningKey,
+}
+
+impl LocalKeySigner {
+    pub fn new(private_key: FieldElement) -> Self {
+        LocalKeySigner {
+            key: SigningKey::from_secret_scalar(private_key),
+        }
+    }
+
+    pub fn from_keystore(path: &str, password: &str) -> Result<Self, String> {
+        let key = SigningKey::from_keystore(path, password)
+            .map_err(|e| format!("unable to read keystore {}: {}", path, e))?;
+        Ok(LocalKeySigner { key })
+    }
+}
+
+#[async_trait]
+impl RewardSigner for LocalKeySigner {
+    async fn sign(&self, hash: &FieldElement) -> Result<Signature, String> {
+        self.key.sign(hash).map_err(|e| e.to_string())
+    }
+
+    async fn public_key(&self) -> Result<FieldElement, String> {
+        Ok(self.key.verifying_key().scalar())
+    }
+}
+
+#[derive(Deserialize)]
+struct RemoteSignature {
+    r: FieldElement,
+    s: FieldElement,
+}
+
+#[derive(Deserialize)]
+struct RemotePublicKey {
+    public_key: FieldElement,
+}
+
+// Signing service keeping the key out of the server: `POST {url}/sign` with
+// {"key_id", "hash"} returns {"r", "s"} and `GET {url}/public_key?key_id=...`
+// returns {"public_key"}, the api key is sent as a bearer token
+pub struct RemoteSigner {
+    url: String,
+    key_id: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, key_id: &str, api_key: Option<String>) -> Self {
+        RemoteSigner {
+            url: url.trim_end_matches('/').to_string(),
+            key_id: key_id.to_string(),
+            api_key,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl RewardSigner for RemoteSigner {
+    async fn sign(&self, hash: &FieldElement) -> Result<Signature, String> {
+        let request = self
+            .client
+            .post(format!("{}/sign", self.url))
+            .json(&json!({ "key_id": self.key_id, "hash": to_hex(*hash) }));
+        let response = self
+            .authorize(request)
+            .send()
+            .await
+            .map_err(|e| format!("remote signer unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("remote signer returned {}", response.status()));
+        }
+        let signature: RemoteSignature = response
+            .json()
+            .await
+            .map_err(|e| format!("invalid remote signature: {}", e))?;
+        Ok(Signature {
+            r: signature.r,
+            s: signature.s,
+        })
+    }
+
+    async fn public_key(&self) -> Result<FieldElement, String> {
+        let request = self
+            .client
+            .get(format!("{}/public_key", self.url))
+            .query(&[("key_id", &self.key_id)]);
+        let response = self
+            .authorize(request)
+            .send()
+            .await
+            .map_err(|e| format!("remote signer unreachable: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("remote signer returned {}", response.status()));
+        }
+        let public_key: RemotePublicKey = response
+            .json()
+            .await
+            .map_err(|e| format!("invalid remote public key: {}", e))?;
+        Ok(public_key.public_key)
+    }
+}
+
+// The signer section takes precedence over the legacy private_key field
+pub fn get_reward_signer(
+    private_key: Option<FieldElement>,
+    signer: &Option<SignerConfig>,
+) -> Result<Arc<dyn RewardSigner>, String> {
+    match (signer, private_key.as_ref()) {
+        (Some(SignerConfig::Local { private_key }), _) | (None, Some(private_key)) => {
+            Ok(Arc::new(LocalKeySigner::new(*private_key)))
+        }
+        (Some(SignerConfig::Keystore { path, password_env }), _) => {
+            let password = env::var(password_env)
+                .map_err(|_| format!("{} is not set", password_env))?;
+            Ok(Arc::new(LocalKeySigner::from_keystore(path, &password)?))
+        }
+        (Some(SignerConfig::Remote { url, key_id, api_key }), _) => {
+            Ok(Arc::new(RemoteSigner::new(url, key_id, api_key.clone())))
+        }
+        (None, None) => Err("either private_key or signer must be set".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::Query,
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::Value;
+    use starknet::core::crypto::ecdsa_verify;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    const PRIVATE_KEY: &str = "0x1234";
+
+    // Signing service answering like the real one with PRIVATE_KEY
+    fn start_stub() -> String {
+        let private_key = FieldElement::from_hex_be(PRIVATE_KEY).unwrap();
+        let public_key = SigningKey::from_secret_scalar(private_key)
+            .verifying_key()
+            .scalar();
+        let app = Router::new()
+            .route(
+                "/sign",
+                post(move |Json(body): Json<Value>| async move {
+                    let hash = FieldElement::from_hex_be(body["hash"].as_str().unwrap()).unwrap();
+                    let signature = SigningKey::from_secret_scalar(private_key)
+                        .sign(&hash)
+                        .unwrap();
+                    Json(json!({ "r": to_hex(signature.r), "s": to_hex(signature.s) }))
+                }),
+            )
+            .route(
+                "/public_key",
+                get(move |Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query.get("key_id").map(String::as_str), Some("nft"));
+                    Json(json!({ "public_key": to_hex(public_key) }))
+                }),
+            );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        url
+    }
+
+    #[tokio::test]
+    async fn local_signature_is_valid() {
+        let signer = LocalKeySigner::new(FieldElement::from_hex_be(PRIVATE_KEY).unwrap());
+        let hash = FieldElement::from(42_u32);
+        let signature = signer.sign(&hash).await.unwrap();
+        let public_key = signer.public_key().await.unwrap();
+        assert!(ecdsa_verify(&public_key, &hash, &signature).unwrap());
+    }
+
+    #[tokio::test]
+    async fn remote_signature_is_valid() {
+        let signer = RemoteSigner::new(&start_stub(), "nft", None);
+        let hash = FieldElement::from(42_u32);
+        let signature = signer.sign(&hash).await.unwrap();
+        let public_key = signer.public_key().await.unwrap();
+        let local = LocalKeySigner::new(FieldElement::from_hex_be(PRIVATE_KEY).unwrap());
+        assert_eq!(public_key, local.public_key().await.unwrap());
+        assert!(ecdsa_verify(&public_key, &hash, &signature).unwrap());
+    }
+
+    #[tokio::test]
+    async fn unreachable_remote_signer_fails() {
+        let signer = RemoteSigner::new("http://127.0.0.1:1", "nft", None);
+        assert!(signer.sign(&FieldElement::ONE).await.is_err());
+    }
+}
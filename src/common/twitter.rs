@@ -0,0 +1,506 @@
+use crate::{
+    common::{
+        linked_accounts::{get_refresh_token, get_valid_access_token, update_tokens, OAuthTokens},
+        oauth_state::issue_oauth_state,
+    },
+    config::Twitter,
+    logger::Logger,
+    models::{AppState, LinkedAccountDocument, QuestTaskDocument},
+    utils::get_random_hex,
+};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use starknet::core::types::FieldElement;
+
+const DEFAULT_API_URL: &str = "https://api.twitter.com";
+const AUTHORIZE_URL: &str = "https://twitter.com/i/oauth2/authorize";
+const SCOPES: &str = "tweet.read users.read follows.read offline.access";
+// the following list is read 1000 accounts per page, accounts rarely follow more than 50k
+const MAX_FOLLOWING_PAGES: usize = 50;
+// the API only returns the 3200 latest posts of a timeline, 100 per page
+const MAX_TIMELINE_PAGES: usize = 32;
+
+pub_struct!(Debug, Clone, Deserialize; TwitterUser {
+    id: String,
+    username: String,
+});
+
+// Everything the quests need from the Twitter API, the server uses TwitterHttpApi
+// and tests can plug a local mock instead
+#[async_trait]
+pub trait TwitterApi: Send + Sync {
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<OAuthTokens, String>;
+
+    async fn refresh(&self, refresh_token: &str) -> Result<OAuthTokens, String>;
+
+    async fn get_me(&self, access_token: &str) -> Result<TwitterUser, String>;
+
+    async fn is_following(
+        &self,
+        access_token: &str,
+        user_id: &str,
+        target_username: &str,
+    ) -> Result<bool, String>;
+
+    async fn has_reposted(
+        &self,
+        access_token: &str,
+        user_id: &str,
+        tweet_id: &str,
+    ) -> Result<bool, String>;
+}
+
+pub struct TwitterHttpApi {
+    api_url: String,
+    client_id: String,
+    client_secret: String,
+    client: reqwest::Client,
+    logger: Logger,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct DataResponse<T> {
+    data: Option<T>,
+    meta: Option<PageMeta>,
+}
+
+#[derive(Deserialize)]
+struct PageMeta {
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Tweet {
+    referenced_tweets: Option<Vec<ReferencedTweet>>,
+}
+
+#[derive(Deserialize)]
+struct ReferencedTweet {
+    r#type: String,
+    id: String,
+}
+
+impl TwitterHttpApi {
+    pub fn new(conf: &Twitter, logger: Logger) -> Self {
+        TwitterHttpApi {
+            api_url: conf
+                .api_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            client_id: conf.oauth2_clientid.clone(),
+            client_secret: conf.oauth2_secret.clone(),
+            client: reqwest::Client::new(),
+            logger,
+        }
+    }
+
+    async fn request_token(&self, params: &[(&str, &str)]) -> Result<OAuthTokens, String> {
+        let res = self
+            .client
+            .post(format!("{}/2/oauth2/token", self.api_url))
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(params)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to request Twitter token: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("Twitter token request failed: {}", res.status()));
+        }
+        let token: TokenResponse = res
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Twitter token: {}", e))?;
+        Ok(OAuthTokens {
+            access_token: token.access_token,
+            refresh_token: token.refresh_token,
+            expiry: Utc::now().timestamp() + token.expires_in,
+        })
+    }
+
+    async fn get_page<T: serde::de::DeserializeOwned>(
+        &self,
+        access_token: &str,
+        url: &str,
+        pagination_token: Option<&str>,
+    ) -> Result<DataResponse<T>, String> {
+        let mut request = self.client.get(url).bearer_auth(access_token);
+        if let Some(token) = pagination_token {
+            request = request.query(&[("pagination_token", token)]);
+        }
+        let res = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to query Twitter: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("Twitter request failed: {}", res.status()));
+        }
+        res.json()
+            .await
+            .map_err(|e| format!("Failed to parse Twitter response: {}", e))
+    }
+
+    // walks a paginated list until one of its items matches, reaching max_pages is logged
+    // since the item may be further in the list
+    async fn find_in_pages<T: serde::de::DeserializeOwned + Send>(
+        &self,
+        access_token: &str,
+        url: &str,
+        max_pages: usize,
+        matches: impl Fn(&T) -> bool + Send + Sync,
+    ) -> Result<bool, String> {
+        let mut pagination_token: Option<String> = None;
+        for _ in 0..max_pages {
+            let page: DataResponse<Vec<T>> = self
+                .get_page(access_token, url, pagination_token.as_deref())
+                .await?;
+            if page.data.unwrap_or_default().iter().any(&matches) {
+                return Ok(true);
+            }
+            pagination_token = page.meta.and_then(|meta| meta.next_token);
+            if pagination_token.is_none() {
+                return Ok(false);
+            }
+        }
+        self.logger.warning(format!(
+            "twitter: stopped reading {} after {} pages",
+            url, max_pages
+        ));
+        Ok(false)
+    }
+}
+
+#[async_trait]
+impl TwitterApi for TwitterHttpApi {
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<OAuthTokens, String> {
+        self.request_token(&[
+            ("grant_type", "authorization_code"),
+            ("client_id", &self.client_id),
+            ("code", code),
+            ("code_verifier", code_verifier),
+            ("redirect_uri", redirect_uri),
+        ])
+        .await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<OAuthTokens, String> {
+        self.request_token(&[
+            ("grant_type", "refresh_token"),
+            ("client_id", &self.client_id),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+    }
+
+    async fn get_me(&self, access_token: &str) -> Result<TwitterUser, String> {
+        let url = format!("{}/2/users/me", self.api_url);
+        self.get_page::<TwitterUser>(access_token, &url, None)
+            .await?
+            .data
+            .ok_or_else(|| "Twitter user not found".to_string())
+    }
+
+    async fn is_following(
+        &self,
+        access_token: &str,
+        user_id: &str,
+        target_username: &str,
+    ) -> Result<bool, String> {
+        let url = format!(
+            "{}/2/users/{}/following?max_results=1000",
+            self.api_url, user_id
+        );
+        self.find_in_pages(
+            access_token,
+            &url,
+            MAX_FOLLOWING_PAGES,
+            |user: &TwitterUser| user.username.eq_ignore_ascii_case(target_username),
+        )
+        .await
+    }
+
+    async fn has_reposted(
+        &self,
+        access_token: &str,
+        user_id: &str,
+        tweet_id: &str,
+    ) -> Result<bool, String> {
+        // the reposts of the user rather than the reposters of the post, which can be
+        // too many to read on popular posts
+        let url = format!(
+            "{}/2/users/{}/tweets?max_results=100&tweet.fields=referenced_tweets&exclude=replies",
+            self.api_url, user_id
+        );
+        self.find_in_pages(access_token, &url, MAX_TIMELINE_PAGES, |tweet: &Tweet| {
+            tweet
+                .referenced_tweets
+                .iter()
+                .flatten()
+                .any(|referenced| referenced.r#type == "retweeted" && referenced.id == tweet_id)
+        })
+        .await
+    }
+}
+
+// PKCE (S256) challenge sent to the authorize url, the verifier stays on the server
+fn get_code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+pub fn get_redirect_uri(state: &AppState) -> String {
+    format!("{}/quests/twitter_callback", state.conf.variables.api_link)
+}
+
+// Starts an authorization for the wallet, optionally to verify a task once linked
+pub async fn get_authorization_url(
+    state: &AppState,
+    addr: FieldElement,
+    quest_id: Option<i64>,
+    task_id: Option<u32>,
+) -> Result<String, String> {
+    let code_verifier = get_random_hex(48);
+    let oauth_state = issue_oauth_state(
+        state,
+        "twitter",
+        addr,
+        quest_id,
+        task_id,
+        Some(code_verifier.clone()),
+    )
+    .await?;
+
+    Url::parse_with_params(
+        AUTHORIZE_URL,
+        &[
+            ("response_type", "code"),
+            ("client_id", state.conf.twitter.oauth2_clientid.as_str()),
+            ("redirect_uri", get_redirect_uri(state).as_str()),
+            ("scope", SCOPES),
+            ("state", oauth_state.as_str()),
+            (
+                "code_challenge",
+                get_code_challenge(&code_verifier).as_str(),
+            ),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map(|url| url.to_string())
+    .map_err(|_| "Error creating authorization url".to_string())
+}
+
+// "https://twitter.com/{username}" for follow tasks
+fn get_target_username(href: &str) -> Option<&str> {
+    href.split(['?', '#'])
+        .next()?
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .map(|username| username.trim_start_matches('@'))
+        .filter(|username| !username.is_empty())
+}
+
+// "https://twitter.com/{username}/status/{id}" for repost tasks
+fn get_tweet_id(href: &str) -> Option<&str> {
+    let (_, rest) = href.split_once("/status/")?;
+    rest.split(['/', '?', '#'])
+        .next()
+        .filter(|id| !id.is_empty())
+}
+
+// returns a usable access token, refreshing and saving it when it has expired
+async fn get_access_token(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+) -> Result<String, String> {
+    if let Some(access_token) = get_valid_access_token(state, account)? {
+        return Ok(access_token);
+    }
+    let tokens = state
+        .twitter
+        .refresh(&get_refresh_token(state, account)?)
+        .await?;
+    update_tokens(state, account, &tokens).await?;
+    Ok(tokens.access_token)
+}
+
+// checks a twitter_fw or twitter_rw task against the API with the linked account
+pub async fn verify_twitter_task(
+    state: &AppState,
+    account: &LinkedAccountDocument,
+    task: &QuestTaskDocument,
+) -> Result<(), String> {
+    let access_token = get_access_token(state, account).await?;
+    check_twitter_task(
+        state.twitter.as_ref(),
+        &access_token,
+        &account.account_id,
+        task.task_type.as_deref(),
+        &task.href,
+    )
+    .await
+}
+
+async fn check_twitter_task(
+    twitter: &dyn TwitterApi,
+    access_token: &str,
+    user_id: &str,
+    task_type: Option<&str>,
+    href: &str,
+) -> Result<(), String> {
+    match task_type {
+        Some("twitter_fw") => {
+            let username = get_target_username(href)
+                .ok_or_else(|| "Invalid Twitter account in task".to_string())?;
+            match twitter
+                .is_following(access_token, user_id, username)
+                .await?
+            {
+                true => Ok(()),
+                false => Err(format!("You're not following @{}", username)),
+            }
+        }
+        Some("twitter_rw") => {
+            let tweet_id =
+                get_tweet_id(href).ok_or_else(|| "Invalid Twitter post in task".to_string())?;
+            match twitter
+                .has_reposted(access_token, user_id, tweet_id)
+                .await?
+            {
+                true => Ok(()),
+                false => Err("You haven't reposted this post".to_string()),
+            }
+        }
+        _ => Err("Not a Twitter task".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // user 1 follows starknet_quest and reposted tweet 42
+    struct MockTwitterApi;
+
+    #[async_trait]
+    impl TwitterApi for MockTwitterApi {
+        async fn exchange_code(&self, _: &str, _: &str, _: &str) -> Result<OAuthTokens, String> {
+            Err("not mocked".to_string())
+        }
+
+        async fn refresh(&self, _: &str) -> Result<OAuthTokens, String> {
+            Err("not mocked".to_string())
+        }
+
+        async fn get_me(&self, _: &str) -> Result<TwitterUser, String> {
+            Err("not mocked".to_string())
+        }
+
+        async fn is_following(
+            &self,
+            access_token: &str,
+            user_id: &str,
+            target_username: &str,
+        ) -> Result<bool, String> {
+            match access_token {
+                "token" => {
+                    Ok(user_id == "1" && target_username.eq_ignore_ascii_case("starknet_quest"))
+                }
+                _ => Err("Twitter request failed: 401 Unauthorized".to_string()),
+            }
+        }
+
+        async fn has_reposted(
+            &self,
+            access_token: &str,
+            user_id: &str,
+            tweet_id: &str,
+        ) -> Result<bool, String> {
+            match access_token {
+                "token" => Ok(user_id == "1" && tweet_id == "42"),
+                _ => Err("Twitter request failed: 401 Unauthorized".to_string()),
+            }
+        }
+    }
+
+    async fn check(user_id: &str, task_type: &str, href: &str) -> Result<(), String> {
+        check_twitter_task(&MockTwitterApi, "token", user_id, Some(task_type), href).await
+    }
+
+    #[test]
+    fn usernames_and_tweet_ids_are_read_from_links() {
+        for href in [
+            "https://twitter.com/starknet_quest",
+            "https://x.com/@starknet_quest/",
+            "https://twitter.com/starknet_quest?ref_src=twsrc",
+            "https://twitter.com/starknet_quest#top",
+        ] {
+            assert_eq!(get_target_username(href), Some("starknet_quest"));
+        }
+        assert_eq!(
+            get_tweet_id("https://twitter.com/starknet_quest/status/42?s=20#m"),
+            Some("42")
+        );
+        assert_eq!(get_tweet_id("https://twitter.com/starknet_quest"), None);
+    }
+
+    #[tokio::test]
+    async fn verify_twitter_fw() {
+        let href = "https://twitter.com/Starknet_Quest?s=20";
+        assert_eq!(check("1", "twitter_fw", href).await, Ok(()));
+        assert_eq!(
+            check("2", "twitter_fw", href).await,
+            Err("You're not following @Starknet_Quest".to_string())
+        );
+        assert!(check("1", "twitter_fw", "").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn verify_twitter_rw() {
+        let href = "https://twitter.com/starknet_quest/status/42";
+        assert_eq!(check("1", "twitter_rw", href).await, Ok(()));
+        assert_eq!(
+            check("2", "twitter_rw", href).await,
+            Err("You haven't reposted this post".to_string())
+        );
+        assert_eq!(
+            check("1", "twitter_rw", "https://twitter.com/starknet_quest").await,
+            Err("Invalid Twitter post in task".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn api_errors_and_other_tasks_fail() {
+        let href = "https://twitter.com/starknet_quest";
+        assert!(
+            check_twitter_task(&MockTwitterApi, "expired", "1", Some("twitter_fw"), href)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            check("1", "discord", href).await,
+            Err("Not a Twitter task".to_string())
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    common::verify_task::{fill_template, matches_predicate},
+    models::{AppState, CustomApiMethod, CustomApiOptions, PartnerDocument, QuestTaskDocument},
+};
+use mongodb::bson::doc;
+use regex::Regex;
+use reqwest::{header::CONTENT_TYPE, redirect::Policy};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use starknet::core::types::FieldElement;
+use std::time::Duration;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const MAX_TIMEOUT_MS: u64 = 30000;
+
+fn fill_custom_template(template: &str, addr: FieldElement, quest_id: i64) -> String {
+    fill_template(&template.replace("{quest_id}", &quest_id.to_string()), addr)
+}
+
+// Checks a custom_api task when it is created or updated, so that a broken regex
+// or url is reported to the admin instead of failing for every user
+pub async fn validate_custom_api(
+    state: &AppState,
+    api_url: &str,
+    regex: Option<&str>,
+    options: &CustomApiOptions,
+) -> Result<(), String> {
+    let url = fill_custom_template(api_url, FieldElement::ZERO, 0);
+    reqwest::Url::parse(&url).map_err(|_| format!("Invalid url: {}", api_url))?;
+    if let Some(regex) = regex {
+        Regex::new(regex).map_err(|e| format!("Invalid regex: {}", e))?;
+    }
+    if let Some(path) = &options.path {
+        JsonPath::parse(path).map_err(|e| format!("Invalid JSONPath: {}", e))?;
+    }
+    if regex.is_none() && options.path.is_none() {
+        return Err("A regex or a JSONPath is required".to_string());
+    }
+    if options.predicate.is_some() && options.path.is_none() {
+        return Err("A predicate requires a JSONPath".to_string());
+    }
+    if options.body.is_some() && options.method != CustomApiMethod::Post {
+        return Err("Only POST requests can have a body".to_string());
+    }
+    if options
+        .timeout_ms
+        .is_some_and(|timeout| timeout == 0 || timeout > MAX_TIMEOUT_MS)
+    {
+        return Err(format!(
+            "timeout_ms must be between 1 and {}",
+            MAX_TIMEOUT_MS
+        ));
+    }
+    if let Some(partner) = &options.partner {
+        check_partner_host(&get_partner(state, partner).await?, &url)?;
+    }
+    Ok(())
+}
+
+// The headers of a partner hold its api keys, they are only sent to the hosts it allows
+fn check_partner_host(partner: &PartnerDocument, url: &str) -> Result<(), String> {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()));
+    let allowed = partner.allowed_hosts.as_deref().unwrap_or_default();
+    match host {
+        Some(host) if allowed.contains(&host) => Ok(()),
+        _ => Err(format!(
+            "The url of a task using partner {} must be on one of its allowed hosts",
+            partner.name
+        )),
+    }
+}
+
+async fn get_partner(state: &AppState, name: &str) -> Result<PartnerDocument, String> {
+    state
+        .db
+        .collection::<PartnerDocument>("partners")
+        .find_one(doc! { "name": name }, None)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Unknown partner: {}", name))
+}
+
+// Calls the api of a custom_api task for this user and checks its response
+pub async fn execute_custom_api(
+    state: &AppState,
+    addr: FieldElement,
+    task: &QuestTaskDocument,
+) -> Result<bool, String> {
+    let api_url = task
+        .api_url
+        .as_ref()
+        .ok_or_else(|| "API URL not found.".to_string())?;
+    let options = task.custom_api.clone().unwrap_or_default();
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(
+            options.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+        ))
+        // a redirection to another host would receive the partner headers
+        .redirect(Policy::custom(|attempt| {
+            if attempt.previous().len() > 5 {
+                attempt.error("too many redirects")
+            } else if attempt.url().host_str() != attempt.previous()[0].host_str() {
+                attempt.stop()
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = fill_custom_template(api_url, addr, task.quest_id);
+    let partner = match &options.partner {
+        Some(partner) => {
+            let partner = get_partner(state, partner).await?;
+            check_partner_host(&partner, &url)?;
+            Some(partner)
+        }
+        None => None,
+    };
+    let mut request = match options.method {
+        CustomApiMethod::Get => client.get(url),
+        CustomApiMethod::Post => client.post(url).header(CONTENT_TYPE, "application/json"),
+    };
+    if let Some(body) = &options.body {
+        request = request.body(fill_custom_template(body, addr, task.quest_id));
+    }
+    if let Some(partner) = partner {
+        for (name, value) in partner.api_headers {
+            request = request.header(name, value);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch API: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("API returned an error: {}", response.status()));
+    }
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read API response: {}", e))?;
+
+    if let Some(regex) = &task.regex {
+        let regex = Regex::new(regex).map_err(|e| format!("Invalid regex: {}", e))?;
+        if !regex.is_match(&text) {
+            return Ok(false);
+        }
+    }
+    if let Some(path) = &options.path {
+        let path = JsonPath::parse(path).map_err(|e| format!("Invalid JSONPath: {}", e))?;
+        let json: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to get JSON response: {}", e))?;
+        let values = path.query(&json).all();
+        let matched = match &options.predicate {
+            Some(predicate) => values
+                .into_iter()
+                .any(|value| matches_predicate(value, predicate, addr)),
+            None => values.into_iter().any(|value| value == &Value::Bool(true)),
+        };
+        if !matched {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partner(allowed_hosts: Option<Vec<String>>) -> PartnerDocument {
+        PartnerDocument {
+            name: "partner".to_string(),
+            api_headers: Default::default(),
+            allowed_hosts,
+            webhook_secret: None,
+            webhook_task_ids: None,
+        }
+    }
+
+    #[test]
+    fn partner_headers_only_go_to_allowed_hosts() {
+        let partner = partner(Some(vec!["api.partner.xyz".to_string()]));
+        assert!(check_partner_host(&partner, "https://API.partner.xyz/users/0x1").is_ok());
+        assert!(check_partner_host(&partner, "https://attacker.xyz/api.partner.xyz").is_err());
+        assert!(check_partner_host(&partner, "https://api.partner.xyz.attacker.xyz/").is_err());
+        assert!(check_partner_host(&partner, "not a url").is_err());
+    }
+
+    #[test]
+    fn partners_without_allowed_hosts_send_no_headers() {
+        assert!(check_partner_host(&partner(None), "https://api.partner.xyz/").is_err());
+        assert!(check_partner_host(&partner(Some(vec![])), "https://api.partner.xyz/").is_err());
+    }
+}
@@ -1,30 +1,20 @@
-use crate::config::{Quiz, QuizQuestionType};
-use crate::models::QuizInsertDocument;
+use crate::common::{quiz_grading::grade_quiz, quiz_order::get_canonical_answers};
+use crate::config::Quiz;
+use crate::models::{QuizAnswer, QuizInsertDocument, QuizQuestionResult};
 use futures::StreamExt;
 use mongodb::bson::{doc, from_document};
 use mongodb::Database;
 use starknet::core::types::FieldElement;
 
-fn match_vectors(vector1: &Vec<usize>, vector2: &Vec<usize>) -> bool {
-    // Check if vectors have the same length
-    if vector1.len() != vector2.len() {
-        return false;
-    }
-
-    // Check if vectors are equal element-wise
-    let equal = vector1 == vector2;
-    equal
-}
-
-// addr is currently unused, this could become the case if we generate
-// a deterministic permutation of answers in the future. Seems non necessary for now
-#[allow(dead_code)]
+// user_answers_list follows the order in which /get_quiz served the quiz to addr,
+// the results are returned in the canonical order of the questions
 pub async fn verify_quiz(
     config: &Database,
-    _addr: FieldElement,
+    secret: &str,
+    addr: FieldElement,
     quiz_name: &i64,
-    user_answers_list: &Vec<Vec<usize>>,
-) -> bool {
+    user_answers_list: &[QuizAnswer],
+) -> Result<Vec<QuizQuestionResult>, String> {
     let collection = config.collection::<QuizInsertDocument>("quizzes");
     let pipeline = vec![
         doc! {
@@ -44,6 +34,11 @@ pub async fn verify_quiz(
                             "quiz_id": &quiz_name
                         }
                     },
+                    doc! {
+                        "$sort": doc! {
+                            "id": 1
+                        }
+                    },
                     doc! {
                         "$project": doc! {
                             "quiz_id": 0,
@@ -62,44 +57,40 @@ pub async fn verify_quiz(
         },
     ];
 
-    let mut quiz_document = collection.aggregate(pipeline, None).await.unwrap();
+    let mut quiz_document = collection
+        .aggregate(pipeline, None)
+        .await
+        .map_err(|e| e.to_string())?;
 
     while let Some(result) = quiz_document.next().await {
         match result {
             Ok(document) => {
-                let quiz: Quiz = from_document(document).unwrap();
-                let mut correct_answers_count = 0;
-                for (i, user_answers) in user_answers_list.iter().enumerate() {
-                    let question = &quiz.questions[i];
-                    let mut user_answers_list = user_answers.clone();
-                    let correct_answers: bool = match question.kind {
-                        QuizQuestionType::TextChoice => {
-                            let mut correct_answers = question.correct_answers.clone().unwrap();
-                            correct_answers.sort();
-                            user_answers_list.sort();
-                            match_vectors(&correct_answers, &user_answers)
-                        }
-                        QuizQuestionType::ImageChoice => {
-                            let mut correct_answers = question.correct_answers.clone().unwrap();
-                            correct_answers.sort();
-                            user_answers_list.sort();
-                            match_vectors(&correct_answers, &user_answers)
-                        }
-                        QuizQuestionType::Ordering => {
-                            let correct_answers = question.correct_answers.clone().unwrap();
-                            match_vectors(&correct_answers, &user_answers_list)
-                        }
-                    };
-                    if correct_answers {
-                        correct_answers_count += 1;
-                    }
-                }
-                return correct_answers_count == quiz.questions.len();
+                let quiz: Quiz = from_document(document).map_err(|e| e.to_string())?;
+                let options_count: Vec<usize> =
+                    quiz.questions.iter().map(|q| q.options.len()).collect();
+                let Some(user_answers_list) = get_canonical_answers(
+                    secret,
+                    addr,
+                    *quiz_name,
+                    &options_count,
+                    user_answers_list,
+                ) else {
+                    return Err("Answers don't match the quiz".to_string());
+                };
+                let results = grade_quiz(&quiz.questions, &user_answers_list)
+                    .into_iter()
+                    .zip(&quiz.questions)
+                    .map(|(correct, question)| QuizQuestionResult {
+                        question_id: question.id.unwrap_or_default(),
+                        correct,
+                    })
+                    .collect();
+                return Ok(results);
             }
-            Err(_e) => {
-                return false;
+            Err(e) => {
+                return Err(e.to_string());
             }
         }
     }
-    true
+    Err("Quiz not found".to_string())
 }
@@ -0,0 +1,112 @@
+use crate::{config::Config, utils::to_hex};
+use serde_json::{json, Value};
+use starknet::{
+    core::{
+        crypto::compute_hash_on_elements,
+        types::{BlockId, BlockTag, FieldElement, FunctionCall},
+        utils::{cairo_short_string_to_felt, starknet_keccak},
+    },
+    macros::selector,
+    providers::{jsonrpc::HttpTransport, JsonRpcClient, Provider},
+};
+
+const DOMAIN_NAME: &str = "Starknet Quest";
+const DOMAIN_VERSION: &str = "1";
+const DOMAIN_TYPE: &str = "StarkNetDomain(name:felt,version:felt,chainId:felt)";
+const CHALLENGE_TYPE: &str = "Challenge(nonce:felt,expiry:felt)";
+
+fn get_chain_id(conf: &Config) -> &'static str {
+    match conf.variables.is_testnet {
+        true => "SN_SEPOLIA",
+        false => "SN_MAIN",
+    }
+}
+
+// SNIP-12 (revision 0) typed data the wallet is asked to sign, mirrored by get_challenge_hash
+pub fn get_challenge_typed_data(conf: &Config, nonce: FieldElement, expiry: i64) -> Value {
+    json!({
+        "types": {
+            "StarkNetDomain": [
+                { "name": "name", "type": "felt" },
+                { "name": "version", "type": "felt" },
+                { "name": "chainId", "type": "felt" },
+            ],
+            "Challenge": [
+                { "name": "nonce", "type": "felt" },
+                { "name": "expiry", "type": "felt" },
+            ],
+        },
+        "primaryType": "Challenge",
+        "domain": {
+            "name": DOMAIN_NAME,
+            "version": DOMAIN_VERSION,
+            "chainId": get_chain_id(conf),
+        },
+        "message": {
+            "nonce": to_hex(nonce),
+            "expiry": expiry.to_string(),
+        },
+    })
+}
+
+pub fn get_challenge_hash(
+    conf: &Config,
+    addr: FieldElement,
+    nonce: FieldElement,
+    expiry: i64,
+) -> FieldElement {
+    let domain_hash = compute_hash_on_elements(&[
+        starknet_keccak(DOMAIN_TYPE.as_bytes()),
+        cairo_short_string_to_felt(DOMAIN_NAME).unwrap(),
+        cairo_short_string_to_felt(DOMAIN_VERSION).unwrap(),
+        cairo_short_string_to_felt(get_chain_id(conf)).unwrap(),
+    ]);
+    let message_hash = compute_hash_on_elements(&[
+        starknet_keccak(CHALLENGE_TYPE.as_bytes()),
+        nonce,
+        FieldElement::from(expiry as u64),
+    ]);
+    compute_hash_on_elements(&[
+        cairo_short_string_to_felt("StarkNet Message").unwrap(),
+        domain_hash,
+        addr,
+        message_hash,
+    ])
+}
+
+// Asks the account contract itself whether the signature is valid, so that any
+// signer scheme supported by the wallet (multisig, guardians, webauthn...) works
+pub async fn verify_signature(
+    provider: &JsonRpcClient<HttpTransport>,
+    addr: FieldElement,
+    hash: FieldElement,
+    signature: &[FieldElement],
+) -> bool {
+    let mut calldata = vec![hash, signature.len().into()];
+    calldata.extend_from_slice(signature);
+
+    for entry_point_selector in [selector!("is_valid_signature"), selector!("isValidSignature")] {
+        let call_result = provider
+            .call(
+                FunctionCall {
+                    contract_address: addr,
+                    entry_point_selector,
+                    calldata: calldata.clone(),
+                },
+                BlockId::Tag(BlockTag::Latest),
+            )
+            .await;
+
+        if let Ok(result) = call_result {
+            // cairo 1 accounts return 'VALID', older accounts return 1
+            return match result.first() {
+                Some(value) => {
+                    *value == FieldElement::ONE
+                        || *value == cairo_short_string_to_felt("VALID").unwrap()
+                }
+                None => false,
+            };
+        }
+    }
+    false
+}
@@ -0,0 +1,461 @@
+use crate::{
+    config::Quests,
+    models::{AppState, Comparison, JsonPredicate, QuestTaskDocument, TaskVerifier},
+    utils::{get_error, to_hex, CompletedTasksTrait},
+};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use mongodb::bson::doc;
+use serde_json::{json, Value};
+use serde_json_path::JsonPath;
+use starknet::{
+    core::{
+        types::{BlockId, BlockTag, FieldElement, FunctionCall},
+        utils::get_selector_from_name,
+    },
+    macros::selector,
+    providers::Provider,
+};
+use std::{cmp::Ordering, time::Duration};
+
+const DEFAULT_ERROR: &str = "You have not completed this task yet.";
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn fill_template(template: &str, addr: FieldElement) -> String {
+    template
+        .replace("{addr_hex}", &to_hex(addr))
+        .replace("{addr}", &addr.to_string())
+}
+
+// felts can be written in decimal or in hex with a 0x prefix
+fn parse_felt(value: &str) -> Result<FieldElement, String> {
+    let parsed = match value.starts_with("0x") {
+        true => FieldElement::from_hex_be(value),
+        false => FieldElement::from_dec_str(value),
+    };
+    parsed.map_err(|_| format!("Invalid felt: {}", value))
+}
+
+fn parse_felt_template(template: &str, addr: FieldElement) -> Result<FieldElement, String> {
+    parse_felt(&fill_template(template, addr))
+}
+
+fn compare<T: PartialOrd>(left: T, comparison: Comparison, right: T) -> bool {
+    match (left.partial_cmp(&right), comparison) {
+        (Some(ordering), Comparison::Eq) => ordering == Ordering::Equal,
+        (Some(ordering), Comparison::Ne) => ordering != Ordering::Equal,
+        (Some(ordering), Comparison::Gt) => ordering == Ordering::Greater,
+        (Some(ordering), Comparison::Gte) => ordering != Ordering::Less,
+        (Some(ordering), Comparison::Lt) => ordering == Ordering::Less,
+        (Some(ordering), Comparison::Lte) => ordering != Ordering::Greater,
+        (None, _) => false,
+    }
+}
+
+pub fn matches_predicate(value: &Value, predicate: &JsonPredicate, addr: FieldElement) -> bool {
+    match predicate {
+        JsonPredicate::Exists => !value.is_null(),
+        JsonPredicate::Equals { value: expected } => value == expected,
+        JsonPredicate::Compare {
+            comparison,
+            value: expected,
+        } => {
+            // numbers are sometimes returned as strings
+            let number = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::String(string) => string.parse::<f64>().ok(),
+                _ => None,
+            };
+            match number {
+                Some(number) => compare(number, *comparison, *expected),
+                None => false,
+            }
+        }
+        JsonPredicate::IsAddr => match value.as_str() {
+            Some(string) => parse_felt(string) == Ok(addr),
+            None => false,
+        },
+    }
+}
+
+impl TaskVerifier {
+    // Checks everything that can be checked without calling the chain or the api,
+    // so that admins get an error when creating the task rather than users when verifying it
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            TaskVerifier::ContractCall {
+                contract,
+                entrypoint,
+                calldata,
+                value,
+                ..
+            } => {
+                let addr = FieldElement::ZERO;
+                parse_felt_template(contract, addr)?;
+                get_selector_from_name(entrypoint)
+                    .map_err(|_| format!("Invalid entrypoint: {}", entrypoint))?;
+                for item in calldata {
+                    parse_felt_template(item, addr)?;
+                }
+                parse_felt_template(value, addr)?;
+                Ok(())
+            }
+            TaskVerifier::JsonApi { url, path, .. } => {
+                reqwest::Url::parse(&fill_template(url, FieldElement::ZERO))
+                    .map_err(|_| format!("Invalid url: {}", url))?;
+                JsonPath::parse(path).map_err(|e| format!("Invalid JSONPath: {}", e))?;
+                Ok(())
+            }
+            TaskVerifier::NftOwnership { min_count, .. } => {
+                if *min_count == 0 {
+                    return Err("min_count must be greater than 0".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get_error_msg(&self) -> String {
+        let error_msg = match self {
+            TaskVerifier::ContractCall { error_msg, .. } => error_msg,
+            TaskVerifier::JsonApi { error_msg, .. } => error_msg,
+            TaskVerifier::NftOwnership { error_msg, .. } => error_msg,
+        };
+        error_msg
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ERROR.to_string())
+    }
+}
+
+// Returns whether the user with this address fulfills the condition of the verifier
+pub async fn execute_verifier(
+    state: &AppState,
+    addr: FieldElement,
+    verifier: &TaskVerifier,
+) -> Result<bool, String> {
+    match verifier {
+        TaskVerifier::ContractCall {
+            contract,
+            entrypoint,
+            calldata,
+            result_index,
+            comparison,
+            value,
+            ..
+        } => {
+            let calldata = calldata
+                .iter()
+                .map(|item| parse_felt_template(item, addr))
+                .collect::<Result<Vec<FieldElement>, String>>()?;
+            let result = state
+                .provider
+                .call(
+                    FunctionCall {
+                        contract_address: parse_felt_template(contract, addr)?,
+                        entry_point_selector: get_selector_from_name(entrypoint)
+                            .map_err(|_| format!("Invalid entrypoint: {}", entrypoint))?,
+                        calldata,
+                    },
+                    BlockId::Tag(BlockTag::Latest),
+                )
+                .await
+                .map_err(|e| format!("Contract call failed: {}", e))?;
+            match result.get(*result_index) {
+                Some(result) => Ok(compare(
+                    *result,
+                    *comparison,
+                    parse_felt_template(value, addr)?,
+                )),
+                None => Err("Contract call returned too few values".to_string()),
+            }
+        }
+        TaskVerifier::JsonApi {
+            url,
+            path,
+            predicate,
+            ..
+        } => {
+            let path = JsonPath::parse(path).map_err(|e| format!("Invalid JSONPath: {}", e))?;
+            let client = reqwest::Client::builder()
+                .timeout(API_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())?;
+            let response = client
+                .get(fill_template(url, addr))
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch API: {}", e))?
+                .json::<Value>()
+                .await
+                .map_err(|e| format!("Failed to get JSON response: {}", e))?;
+            Ok(path
+                .query(&response)
+                .all()
+                .into_iter()
+                .any(|value| matches_predicate(value, predicate, addr)))
+        }
+        TaskVerifier::NftOwnership {
+            contract,
+            min_count,
+            ..
+        } => {
+            // older contracts only expose the camel case entrypoint
+            for entry_point_selector in [selector!("balance_of"), selector!("balanceOf")] {
+                let call_result = state
+                    .provider
+                    .call(
+                        FunctionCall {
+                            contract_address: *contract,
+                            entry_point_selector,
+                            calldata: vec![addr],
+                        },
+                        BlockId::Tag(BlockTag::Latest),
+                    )
+                    .await;
+                // the balance is a u256, its high part is ignored
+                if let Ok(result) = call_result {
+                    return match result.first() {
+                        Some(balance) => Ok(*balance >= FieldElement::from(*min_count)),
+                        None => Err("Contract call returned too few values".to_string()),
+                    };
+                }
+            }
+            Err("Unable to get the NFT balance".to_string())
+        }
+    }
+}
+
+// Verifies a task declaring a verifier for this user and marks it as completed
+pub async fn verify_task(state: &AppState, addr: FieldElement, task_id: u32) -> Response {
+    let task = match state
+        .db
+        .collection::<QuestTaskDocument>("tasks")
+        .find_one(doc! { "id": task_id }, None)
+        .await
+    {
+        Ok(Some(task)) => task,
+        Ok(None) => return get_error("Task not found".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+    let Some(verifier) = &task.verifier else {
+        return get_error("This task can't be verified with this endpoint".to_string());
+    };
+    complete_verified_task(state, addr, task_id, verifier).await
+}
+
+// Verifies a legacy protocol task with the verifier of its route
+pub async fn verify_legacy_task(
+    state: &AppState,
+    addr: FieldElement,
+    task_id: u32,
+    task: LegacyTask,
+) -> Response {
+    let verifier = task.get_verifier(&state.get_reloadable_conf().quests);
+    complete_verified_task(state, addr, task_id, &verifier).await
+}
+
+async fn complete_verified_task(
+    state: &AppState,
+    addr: FieldElement,
+    task_id: u32,
+    verifier: &TaskVerifier,
+) -> Response {
+    match execute_verifier(state, addr, verifier).await {
+        Ok(true) => match state.upsert_completed_task(addr, task_id).await {
+            Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
+            Err(e) => get_error(format!("{}", e)),
+        },
+        Ok(false) => get_error(verifier.get_error_msg()),
+        Err(e) => get_error(e),
+    }
+}
+
+fn contract_call(
+    contract: FieldElement,
+    entrypoint: &str,
+    calldata: Vec<String>,
+    comparison: Comparison,
+    value: &str,
+    error_msg: &str,
+) -> TaskVerifier {
+    TaskVerifier::ContractCall {
+        contract: to_hex(contract),
+        entrypoint: entrypoint.to_string(),
+        calldata,
+        result_index: 0,
+        comparison,
+        value: value.to_string(),
+        error_msg: Some(error_msg.to_string()),
+    }
+}
+
+// Protocol tasks created before verifiers existed, their routes used to hard code the
+// checks below. The Nostra staking and ProScore signers tasks chain several calls or
+// parse a variable layout and keep their own routes.
+#[derive(Debug, Clone, Copy)]
+pub enum LegacyTask {
+    EkuboLiquidity,
+    NostraLiquidity,
+    ProscoreBorrow,
+    CarminePriceProtect,
+}
+
+impl LegacyTask {
+    pub fn get_verifier(&self, quests: &Quests) -> TaskVerifier {
+        match self {
+            LegacyTask::EkuboLiquidity => contract_call(
+                quests.ekubo.contract,
+                "balanceOf",
+                vec!["{addr}".to_string()],
+                Comparison::Gt,
+                "0",
+                "You didn't provided any liquidity on Ekubo.",
+            ),
+            LegacyTask::NostraLiquidity => {
+                let nostra = &quests.nostra;
+                let mut calldata = vec!["{addr}".to_string(), nostra.pairs.len().to_string()];
+                calldata.extend(nostra.pairs.iter().map(|pair| to_hex(*pair)));
+                contract_call(
+                    nostra.utils_contract,
+                    "sum_balances",
+                    calldata,
+                    Comparison::Gt,
+                    "0",
+                    "You didn't deposit liquidity.",
+                )
+            }
+            LegacyTask::ProscoreBorrow => contract_call(
+                FieldElement::from_hex_be(
+                    "0x04c0a5193d58f74fbace4b74dcf65481e734ed1714121bdc571da345540efa05",
+                )
+                .unwrap(),
+                "get_user_debt_for_token",
+                vec![
+                    "{addr}".to_string(),
+                    "0x075afe6402ad5a5c20dd25e10ec3b3986acaa647b77e4ae24b0cbc9a54a27a87"
+                        .to_string(),
+                ],
+                Comparison::Gte,
+                "9000000000000000000",
+                "You must borrow 10 EKUBO tokens",
+            ),
+            LegacyTask::CarminePriceProtect => TaskVerifier::JsonApi {
+                url: "https://api.carmine.finance/api/v1/mainnet/price-protect-users".to_string(),
+                path: "$.data[*]".to_string(),
+                predicate: JsonPredicate::IsAddr,
+                error_msg: Some(
+                    "You didn't open price protect for at least 10$ on Carmine.".to_string(),
+                ),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr() -> FieldElement {
+        FieldElement::from_hex_be("0x1234").unwrap()
+    }
+
+    #[test]
+    fn compare_follows_the_comparison() {
+        assert!(compare(2, Comparison::Eq, 2));
+        assert!(compare(1, Comparison::Ne, 2));
+        assert!(compare(3, Comparison::Gt, 2));
+        assert!(!compare(2, Comparison::Gt, 2));
+        assert!(compare(2, Comparison::Gte, 2));
+        assert!(compare(1, Comparison::Lt, 2));
+        assert!(compare(2, Comparison::Lte, 2));
+        assert!(!compare(3, Comparison::Lte, 2));
+        // NaN can't be ordered, nothing matches it
+        assert!(!compare(f64::NAN, Comparison::Ne, 1.0));
+    }
+
+    #[test]
+    fn fill_template_writes_the_address() {
+        assert_eq!(
+            fill_template("/users/{addr}?hex={addr_hex}", addr()),
+            format!("/users/4660?hex={}", to_hex(addr()))
+        );
+    }
+
+    #[test]
+    fn predicates_match_json_values() {
+        assert!(matches_predicate(&json!(0), &JsonPredicate::Exists, addr()));
+        assert!(!matches_predicate(
+            &Value::Null,
+            &JsonPredicate::Exists,
+            addr()
+        ));
+        let equals = JsonPredicate::Equals {
+            value: json!("gold"),
+        };
+        assert!(matches_predicate(&json!("gold"), &equals, addr()));
+        assert!(!matches_predicate(&json!("silver"), &equals, addr()));
+        let compare = JsonPredicate::Compare {
+            comparison: Comparison::Gte,
+            value: 10.0,
+        };
+        assert!(matches_predicate(&json!(12), &compare, addr()));
+        assert!(matches_predicate(&json!("10"), &compare, addr()));
+        assert!(!matches_predicate(&json!(9.5), &compare, addr()));
+        assert!(!matches_predicate(&json!("ten"), &compare, addr()));
+        assert!(matches_predicate(
+            &json!("0x1234"),
+            &JsonPredicate::IsAddr,
+            addr()
+        ));
+        assert!(matches_predicate(
+            &json!("4660"),
+            &JsonPredicate::IsAddr,
+            addr()
+        ));
+        assert!(!matches_predicate(
+            &json!("0x1235"),
+            &JsonPredicate::IsAddr,
+            addr()
+        ));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_verifiers() {
+        let contract_call = |entrypoint: &str, value: &str| TaskVerifier::ContractCall {
+            contract: "0x1".to_string(),
+            entrypoint: entrypoint.to_string(),
+            calldata: vec!["{addr}".to_string(), "{addr_hex}".to_string()],
+            result_index: 0,
+            comparison: Comparison::Gte,
+            value: value.to_string(),
+            error_msg: None,
+        };
+        assert!(contract_call("balance_of", "10").validate().is_ok());
+        assert!(contract_call("balance_öf", "10").validate().is_err());
+        assert!(contract_call("balance_of", "ten").validate().is_err());
+
+        let json_api = |url: &str, path: &str| TaskVerifier::JsonApi {
+            url: url.to_string(),
+            path: path.to_string(),
+            predicate: JsonPredicate::IsAddr,
+            error_msg: None,
+        };
+        assert!(json_api("https://api.example.com/{addr_hex}", "$.data[*]")
+            .validate()
+            .is_ok());
+        assert!(json_api("not a url", "$.data[*]").validate().is_err());
+        assert!(json_api("https://api.example.com", "data[")
+            .validate()
+            .is_err());
+
+        let nft = |min_count| TaskVerifier::NftOwnership {
+            contract: FieldElement::ONE,
+            min_count,
+            error_msg: None,
+        };
+        assert!(nft(1).validate().is_ok());
+        assert!(nft(0).validate().is_err());
+    }
+}
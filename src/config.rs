@@ -1,4 +1,5 @@
-use serde::{self, Deserialize, Deserializer};
+use reqwest::Url;
+use serde::{self, Deserialize, Deserializer, Serialize, Serializer};
 use starknet::core::types::FieldElement;
 use std::env;
 use std::fs;
@@ -24,9 +25,34 @@ pub_struct!(Clone, Deserialize; Database {
     connection_string: String,
 });
 
+// Where a reward signing key lives, see common::reward_signer
+#[derive(Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SignerConfig {
+    Local {
+        private_key: FieldElement,
+    },
+    // the password is read from the environment variable named password_env
+    Keystore {
+        path: String,
+        password_env: String,
+    },
+    Remote {
+        url: String,
+        key_id: String,
+        api_key: Option<String>,
+    },
+}
+
 pub_struct!(Clone, Deserialize; NftContract {
     address: String,
-    private_key: FieldElement,
+    // legacy in-process key, used when signer is not set
+    private_key: Option<FieldElement>,
+    signer: Option<SignerConfig>,
+    // seconds during which a reward signature can be claimed, 30 days if not set
+    signature_ttl: Option<i64>,
+    // first block read by the mint indexer, the current block if not set
+    indexer_start_block: Option<u64>,
 });
 
 pub_struct!(Clone, Deserialize;  Variables {
@@ -105,10 +131,12 @@ pub_struct!(Clone, Deserialize;  Quests {
 pub_struct!(Clone, Deserialize;  Twitter {
     oauth2_clientid: String,
     oauth2_secret: String,
+    api_url: Option<String>,
 });
 
 pub_struct!(Clone, Deserialize;  QuestBoost{
-    private_key: FieldElement,
+    private_key: Option<FieldElement>,
+    signer: Option<SignerConfig>,
     update_interval: u64,
 });
 
@@ -122,6 +150,29 @@ pub enum QuizQuestionType {
     TextChoice,
     ImageChoice,
     Ordering,
+    Numeric,
+    ShortText,
+}
+
+impl QuizQuestionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuizQuestionType::TextChoice => "text_choice",
+            QuizQuestionType::ImageChoice => "image_choice",
+            QuizQuestionType::Ordering => "ordering",
+            QuizQuestionType::Numeric => "numeric",
+            QuizQuestionType::ShortText => "short_text",
+        }
+    }
+}
+
+impl Serialize for QuizQuestionType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
 }
 
 impl<'de> Deserialize<'de> for QuizQuestionType {
@@ -134,12 +185,16 @@ impl<'de> Deserialize<'de> for QuizQuestionType {
             "text_choice" => Ok(QuizQuestionType::TextChoice),
             "image_choice" => Ok(QuizQuestionType::ImageChoice),
             "ordering" => Ok(QuizQuestionType::Ordering),
+            "numeric" => Ok(QuizQuestionType::Numeric),
+            "short_text" => Ok(QuizQuestionType::ShortText),
             _ => Err(serde::de::Error::custom("Unexpected type")),
         }
     }
 }
 
-pub_struct!(Clone, Deserialize,Debug; QuizQuestion {
+// Also the format of quizzes exported by /admin/quiz/export, ids are ignored on import
+pub_struct!(Clone, Serialize, Deserialize, Debug; QuizQuestion {
+    id: Option<i64>,
     kind: QuizQuestionType,
     layout: String,
     question: String,
@@ -147,11 +202,22 @@ pub_struct!(Clone, Deserialize,Debug; QuizQuestion {
     correct_answers: Option<Vec<usize>>,
     correct_order: Option<Vec<String>>,
     image_for_layout: Option<String>,
+    correct_value: Option<f64>,
+    tolerance: Option<f64>,
+    accepted_answers: Option<Vec<String>>,
+    answer_regex: Option<String>,
 });
 
-pub_struct!(Clone, Deserialize,Debug; Quiz {
+// questions stay last so that the quiz can be written as TOML
+pub_struct!(Clone, Serialize, Deserialize, Debug; Quiz {
     name: String,
     desc: String,
+    intro: Option<String>,
+    help_link: Option<String>,
+    cta: Option<String>,
+    passing_score: Option<u32>,
+    max_attempts: Option<u32>,
+    cooldown: Option<i64>,
     questions: Vec<QuizQuestion>,
 });
 
@@ -185,6 +251,16 @@ pub_struct!(Clone, Deserialize;  Achievements {
 pub_struct!(Clone, Deserialize;  AuthSetup {
     secret_key: String,
     expiry_duration: i64,
+    refresh_expiry_duration: i64,
+});
+
+pub_struct!(Clone, Deserialize;  Session {
+    secret_key: String,
+    expiry_duration: i64,
+    challenge_expiry: i64,
+    oauth_state_secret: String,
+    token_encryption_secret: String,
+    quiz_order_secret: String,
 });
 
 pub_struct!(Clone, Deserialize;  ProtocolStats {
@@ -212,24 +288,95 @@ pub_struct!(Clone, Deserialize;  Config {
     rango: Api,
     pyramid: ApiEndpoint,
     auth:AuthSetup,
+    session: Session,
 });
 
-pub fn load() -> Config {
-    let args: Vec<String> = env::args().collect();
-    let config_path = if args.len() <= 1 {
-        "config.toml"
-    } else {
-        args.get(1).unwrap()
-    };
-    let file_contents = fs::read_to_string(config_path);
-    if file_contents.is_err() {
-        panic!("error: unable to read file with path \"{}\"", config_path);
+// Sections that can be changed on a running server by sending it SIGHUP,
+// everything else (secrets, database, server...) requires a restart
+pub_struct!(Clone; ReloadableConfig {
+    quests: Quests,
+    discover: ProtocolStats,
+    boost_update_interval: u64,
+});
+
+impl ReloadableConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let contracts = [
+            ("quests.utils_contract", self.quests.utils_contract),
+            ("quests.sithswap.utils_contract", self.quests.sithswap.utils_contract),
+            ("quests.zklend.contract", self.quests.zklend.contract),
+            ("quests.zklend.utils_contract", self.quests.zklend.utils_contract),
+            ("quests.jediswap.utils_contract", self.quests.jediswap.utils_contract),
+            ("quests.ekubo.contract", self.quests.ekubo.contract),
+            ("quests.myswap.contract", self.quests.myswap.contract),
+            ("quests.nostra.utils_contract", self.quests.nostra.utils_contract),
+            ("quests.nostra.staking_contract", self.quests.nostra.staking_contract),
+            ("quests.carbonable.contract", self.quests.carbonable.contract),
+            ("quests.nimbora.contract", self.quests.nimbora.contract),
+            ("quests.bountive.contract", self.quests.bountive.contract),
+        ];
+        for (name, contract) in contracts {
+            if contract == FieldElement::ZERO {
+                return Err(format!("{} must not be zero", name));
+            }
+        }
+
+        let endpoints = [
+            ("discover.pairs_api_endpoint", &self.discover.pairs_api_endpoint),
+            ("discover.lending_api_endpoint", &self.discover.lending_api_endpoint),
+            ("discover.derivates_api_endpoint", &self.discover.derivates_api_endpoint),
+            (
+                "discover.alt_protocols_api_endpoint",
+                &self.discover.alt_protocols_api_endpoint,
+            ),
+            ("quests.haiko.api_endpoint", &self.quests.haiko.api_endpoint),
+            ("quests.sithswap_2.api_endpoint", &self.quests.sithswap_2.api_endpoint),
+        ];
+        for (name, endpoint) in endpoints {
+            if Url::parse(endpoint).is_err() {
+                return Err(format!("{} is not a valid url", name));
+            }
+        }
+
+        if self.boost_update_interval == 0 {
+            return Err("quest_boost.update_interval must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn get_reloadable(&self) -> ReloadableConfig {
+        ReloadableConfig {
+            quests: self.quests.clone(),
+            discover: self.discover.clone(),
+            boost_update_interval: self.quest_boost.update_interval,
+        }
     }
+}
+
+fn get_config_path() -> String {
+    // flags such as --reconcile-leaderboard are not config paths
+    match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => "config.toml".to_string(),
+    }
+}
 
-    match toml::from_str(file_contents.unwrap().as_str()) {
+pub fn try_load() -> Result<Config, String> {
+    let config_path = get_config_path();
+    let file_contents = fs::read_to_string(&config_path)
+        .map_err(|_| format!("unable to read file with path \"{}\"", config_path))?;
+
+    toml::from_str(file_contents.as_str())
+        .map_err(|err| format!("unable to deserialize config. {}", err))
+}
+
+pub fn load() -> Config {
+    match try_load() {
         Ok(loaded) => loaded,
         Err(err) => {
-            panic!("error: unable to deserialize config. {}", err);
+            panic!("error: {}", err);
         }
     }
 }
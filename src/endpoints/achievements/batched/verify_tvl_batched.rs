@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     common::get_achievement::get_achievement,
+    middleware::session::VerifiedAddress,
     models::{AppState, VerifyAchievementBatchedQuery},
     utils::{get_error, to_hex, AchievementsTrait},
 };
@@ -18,9 +19,9 @@ use starknet::core::types::FieldElement;
 #[route(get, "/achievements/batched/verify_tvl_batched")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementBatchedQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
@@ -38,7 +39,7 @@ pub async fn handler(
                         return get_error("Your TVL is too low".to_string());
                     }
 
-                    match get_achievement(&state, &query.addr, query.category_id).await {
+                    match get_achievement(&state, &addr, query.category_id).await {
                         Ok(achievements) => {
                             let mut achieved: Vec<u32> = vec![];
                             for achievement in achievements.achievements {
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    middleware::session::VerifiedAddress,
     models::{AppState, VerifyAchievementQuery},
     utils::{get_error, AchievementsTrait},
 };
@@ -17,9 +18,9 @@ use starknet::core::types::FieldElement;
 #[route(get, "/achievements/verify_achieved_quests")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::utils::fetch_json_from_url;
 use crate::{
+    middleware::session::VerifiedAddress,
     models::{AchievedDocument, AppState, VerifyAchievementQuery},
     utils::{get_error, to_hex, AchievementsTrait},
 };
@@ -19,9 +20,9 @@ use starknet::core::types::FieldElement;
 #[route(get, "/achievements/verify_briq")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
@@ -6,6 +6,7 @@ use crate::{
     endpoints::achievements::verify_whitelisted::{
         is_argent_whitelisted, is_braavos_whitelisted, is_carbonable_whitelisted,
     },
+    middleware::session::VerifiedAddress,
     models::{AchievedDocument, AppState, Nft, VerifyAchievementQuery},
     utils::{get_error, AchievementsTrait},
 };
@@ -45,9 +46,9 @@ fn get_args(config: Config, achievement_id: u32) -> Result<(FieldElement, u32, N
 #[route(get, "/achievements/verify_default")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
@@ -1,9 +1,10 @@
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, AchievementsTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -23,9 +24,8 @@ use std::{
 #[route(get, "/achievements/verify_has_domain")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
-    let addr = &query.addr;
     let achievement_id = 9;
 
     // get starkname from address
@@ -35,7 +35,7 @@ pub async fn handler(
             FunctionCall {
                 contract_address: state.conf.starknetid_contracts.naming_contract,
                 entry_point_selector: selector!("address_to_domain"),
-                calldata: vec![*addr, FieldElement::ZERO],
+                calldata: vec![addr, FieldElement::ZERO],
             },
             BlockId::Tag(BlockTag::Latest),
         )
@@ -73,7 +73,7 @@ pub async fn handler(
                 }
 
                 match state
-                    .upsert_completed_achievement(*addr, achievement_id)
+                    .upsert_completed_achievement(addr, achievement_id)
                     .await
                 {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
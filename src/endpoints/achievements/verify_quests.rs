@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::utils::{to_hex, AchievementsTrait};
 use crate::{
+    middleware::session::VerifiedAddress,
     models::{AppState, VerifyAchievementQuery},
     utils::get_error,
 };
@@ -31,9 +32,9 @@ fn get_number_of_quests(id: u32) -> u32 {
 #[route(get, "/achievements/verify_quests")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     let hex_addr = to_hex(addr);
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::{
     common::has_deployed_time::execute_has_deployed_time,
+    middleware::session::VerifiedAddress,
     models::{AppState, VerifyAchievementQuery},
     utils::{get_error, AchievementsTrait},
 };
@@ -19,9 +20,9 @@ use starknet::core::types::FieldElement;
 #[route(get, "/achievements/verify_seniority")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyAchievementQuery>,
 ) -> impl IntoResponse {
-    let addr = query.addr;
     if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
@@ -31,7 +32,7 @@ pub async fn handler(
         return get_error("Invalid achievement id".to_string());
     }
 
-    match execute_has_deployed_time(state.clone(), &query.addr).await {
+    match execute_has_deployed_time(state.clone(), &addr).await {
         Ok(timestamp) => {
             
             let date_time = DateTime::from_timestamp(timestamp as i64, 0).unwrap();
@@ -0,0 +1,89 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::AdminAuditDocument;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct GetAuditQuery {
+    user: Option<String>,
+    quest_id: Option<i64>,
+    /*
+    time range in milliseconds, both bounds are inclusive
+     */
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    /*
+    page number starting from 0
+     */
+    page: Option<u64>,
+    page_size: Option<i64>,
+}
+
+#[route(get, "/admin/audit", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    _admin: Admin<require::ViewAuditLog>,
+    Query(query): Query<GetAuditQuery>,
+) -> impl IntoResponse {
+    let page = query.page.unwrap_or(0);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
+
+    let mut filter = Document::new();
+    if let Some(user) = &query.user {
+        filter.insert("user", user);
+    }
+    if let Some(quest_id) = query.quest_id {
+        filter.insert("quest_ids", quest_id);
+    }
+    let mut time_range = Document::new();
+    if let Some(start_time) = query.start_time {
+        time_range.insert("$gte", start_time);
+    }
+    if let Some(end_time) = query.end_time {
+        time_range.insert("$lte", end_time);
+    }
+    if !time_range.is_empty() {
+        filter.insert("timestamp", time_range);
+    }
+
+    let collection = state.db.collection::<AdminAuditDocument>("admin_audit");
+    let total = match collection.count_documents(filter.clone(), None).await {
+        Ok(total) => total,
+        Err(_e) => return get_error("Error querying audit log".to_string()),
+    };
+
+    let options = FindOptions::builder()
+        .sort(doc! { "timestamp": -1 })
+        .skip(page * page_size as u64)
+        .limit(page_size)
+        .build();
+    match collection.find(filter, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<AdminAuditDocument>>().await {
+            Ok(entries) => (
+                StatusCode::OK,
+                Json(json!({
+                    "entries": entries,
+                    "page": page,
+                    "page_size": page_size,
+                    "total": total,
+                })),
+            )
+                .into_response(),
+            Err(_e) => get_error("Error querying audit log".to_string()),
+        },
+        Err(_e) => get_error("Error querying audit log".to_string()),
+    }
+}
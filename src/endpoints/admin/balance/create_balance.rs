@@ -1,15 +1,15 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::{QuestDocument, QuestTaskDocument};
-use crate::utils::verify_quest_auth;
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json}
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use starknet::core::types::FieldElement;
@@ -28,27 +28,22 @@ pub_struct!(Deserialize; CreateBalance {
 #[route(post, "/admin/tasks/balance/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateBalance>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &(body.quest_id as i64)).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &(body.quest_id as i64)).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
 
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    let next_id = match get_next_id(&state.db, "tasks").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
 
     // Build a vector of FieldElement from the comma separated contracts string
     let parsed_contracts: Vec<FieldElement> = body
@@ -70,19 +65,36 @@ pub async fn handler(
         verify_endpoint_type: "default".to_string(),
         task_type: Some("balance".to_string()),
         discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
         quiz_name: None,
         contracts: Some(parsed_contracts),
         api_url: None,
         regex: None,
+        verifier: None,
+        custom_api: None,
     };
 
     // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task created successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/tasks/balance/create",
+            "tasks",
+            next_id.into(),
+        ),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error creating tasks".to_string()),
     };
 }
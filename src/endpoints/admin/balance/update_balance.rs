@@ -1,10 +1,11 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::QuestTaskDocument;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use crate::utils::verify_task_auth;
 
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -33,12 +34,12 @@ fn field_element_to_bson(fe: &FieldElement) -> mongodb::bson::Bson {
 #[route(post, "/admin/tasks/balance/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateBalance>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
 
-    let res = verify_task_auth(sub, &collection, &(body.id as i32)).await;
+    let res = verify_task_auth(&admin, &collection, &(body.id as i32)).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -78,12 +79,20 @@ pub async fn handler(
     };
 
     // insert document to boost collection
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task updated successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(&admin.sub, "/admin/tasks/balance/update", "tasks", body.id),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error updating tasks".to_string()),
     };
 }
@@ -1,15 +1,16 @@
-use crate::models::{QuestDocument, QuestTaskDocument};
-use crate::utils::verify_quest_auth;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::verify_custom_api::validate_custom_api;
+use crate::models::{CustomApiOptions, QuestDocument, QuestTaskDocument};
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -20,33 +21,48 @@ pub_struct!(Deserialize; CreateCustom {
     desc: String,
     cta: String,
     href: String,
-    api: String,
+    api: Option<String>,
+    api_url: Option<String>,
+    regex: Option<String>,
+    custom_api: Option<CustomApiOptions>,
 });
 
 #[route(post, "/admin/tasks/custom/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateCustom>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &(body.quest_id as i64)).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &(body.quest_id as i64)).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
    
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    // tasks with an api_url are verified by the server, others by the partner api
+    let (task_type, verify_endpoint) = match &body.api_url {
+        Some(api_url) => {
+            let options = body.custom_api.clone().unwrap_or_default();
+            if let Err(e) =
+                validate_custom_api(&state, api_url, body.regex.as_deref(), &options).await
+            {
+                return get_error(e);
+            }
+            ("custom_api", "quests/verify_custom_api".to_string())
+        }
+        None => match &body.api {
+            Some(api) => ("custom", api.clone()),
+            None => return get_error("An api or an api_url is required".to_string()),
+        },
+    };
+
+    let next_id = match get_next_id(&state.db, "tasks").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
 
     let new_document = QuestTaskDocument {
         name: body.name.clone(),
@@ -57,23 +73,40 @@ pub async fn handler(
         total_amount: None,
         id: next_id,
         cta: body.cta.clone(),
-        verify_endpoint: body.api.clone(),
+        verify_endpoint,
         verify_endpoint_type: "default".to_string(),
-        task_type: Some("custom".to_string()),
+        task_type: Some(task_type.to_string()),
         discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
         quiz_name: None,
         contracts: None,
-        api_url: None,
-        regex: None,
+        api_url: body.api_url.clone(),
+        regex: body.regex.clone(),
+        verifier: None,
+        custom_api: body.custom_api.clone(),
     };
 
     // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task created successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/tasks/custom/create",
+            "tasks",
+            next_id.into(),
+        ),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error creating tasks".to_string()),
     };
 }
@@ -1,14 +1,16 @@
-use crate::models::QuestTaskDocument;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::verify_custom_api::validate_custom_api;
+use crate::models::{CustomApiOptions, QuestTaskDocument};
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, to_bson};
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -22,17 +24,20 @@ pub_struct!(Deserialize; CreateCustom {
     verify_endpoint_type: Option<String>,
     verify_redirect: Option<String>,
     href: Option<String>,
+    api_url: Option<String>,
+    regex: Option<String>,
+    custom_api: Option<CustomApiOptions>,
 });
 
 #[route(post, "/admin/tasks/custom/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateCustom>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
     
-    let res = verify_task_auth(sub, &collection, &(body.id as i32)).await;
+    let res = verify_task_auth(&admin, &collection, &(body.id as i32)).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -66,18 +71,56 @@ pub async fn handler(
         update_doc.insert("verify_endpoint_type", verify_endpoint_type);
     }
 
+    if body.api_url.is_some() || body.regex.is_some() || body.custom_api.is_some() {
+        let task = match collection.find_one(doc! { "id": &body.id }, None).await {
+            Ok(Some(task)) => task,
+            _ => return get_error("Error updating tasks".to_string()),
+        };
+        // the new settings are validated together with the ones that are kept
+        let Some(api_url) = body.api_url.as_ref().or(task.api_url.as_ref()) else {
+            return get_error("An api_url is required".to_string());
+        };
+        let regex = body.regex.as_deref().or(task.regex.as_deref());
+        let options = body
+            .custom_api
+            .clone()
+            .or(task.custom_api)
+            .unwrap_or_default();
+        if let Err(e) = validate_custom_api(&state, api_url, regex, &options).await {
+            return get_error(e);
+        }
+        match to_bson(&options) {
+            Ok(options) => update_doc.insert("custom_api", options),
+            Err(_e) => return get_error("Invalid custom_api".to_string()),
+        };
+        update_doc.insert("api_url", api_url);
+        if let Some(regex) = regex {
+            update_doc.insert("regex", regex);
+        }
+        update_doc.insert("task_type", "custom_api");
+        update_doc.insert("verify_endpoint", "quests/verify_custom_api");
+    }
+
     // update quest query
     let update = doc! {
         "$set": update_doc
     };
 
     // insert document to boost collection
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task updated successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(&admin.sub, "/admin/tasks/custom/update", "tasks", body.id),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error updating tasks".to_string()),
     };
 }
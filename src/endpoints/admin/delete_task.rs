@@ -1,9 +1,10 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::QuestTaskDocument;
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -20,11 +21,11 @@ pub_struct!(Deserialize; DeleteTask {
 #[route(post, "/admin/tasks/remove_task", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     body: Json<DeleteTask>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
-    let res = verify_task_auth(sub, &collection, &body.id).await;
+    let res = verify_task_auth(&admin, &collection, &body.id).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -33,12 +34,25 @@ pub async fn handler(
     let filter = doc! {
         "id": &body.id,
     };
-    return match &collection.delete_one(filter.clone(), None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "deleted successfully"})),
-        )
-            .into_response(),
+    return match &with_admin_audit(
+        &state,
+        AuditEntry::delete(
+            &admin.sub,
+            "/admin/tasks/remove_task",
+            "tasks",
+            body.id.into(),
+        ),
+        collection.delete_one(filter.clone(), None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "deleted successfully"})),
+            )
+                .into_response()
+        }
         Err(_) => {
             return get_error("Task does not exist".to_string());
         }
@@ -1,9 +1,11 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::discord::validate_min_account_age;
 use crate::models::QuestTaskDocument;
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -19,17 +21,19 @@ pub_struct!(Deserialize; CreateCustom {
     desc: Option<String>,
     invite_link: Option<String>,
     guild_id: Option<String>,
+    role_ids: Option<Vec<String>>,
+    min_account_age: Option<i64>,
 });
 
 #[route(post, "/admin/tasks/discord/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateCustom>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
     
-    let res = verify_task_auth(sub, &collection, &(body.id as i32)).await;
+    let res = verify_task_auth(&admin, &collection, &(body.id as i32)).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -40,6 +44,8 @@ pub async fn handler(
     };
 
     let mut update_doc = doc! {};
+    // requirements are removed with an empty role list or a minimum age of 0
+    let mut unset_doc = doc! {};
 
     if let Some(name) = &body.name {
         update_doc.insert("name", name);
@@ -53,19 +59,49 @@ pub async fn handler(
     if let Some(guild_id) = &body.guild_id {
         update_doc.insert("discord_guild_id", guild_id);
     }
+    match &body.role_ids {
+        Some(role_ids) if role_ids.is_empty() => {
+            unset_doc.insert("discord_role_ids", "");
+        }
+        Some(role_ids) => {
+            update_doc.insert("discord_role_ids", role_ids);
+        }
+        None => {}
+    }
+    if let Some(min_account_age) = body.min_account_age {
+        if let Err(e) = validate_min_account_age(min_account_age) {
+            return get_error(e);
+        }
+        match min_account_age {
+            0 => unset_doc.insert("discord_min_account_age", ""),
+            _ => update_doc.insert("discord_min_account_age", min_account_age),
+        };
+    }
 
     // update quest query
-    let update = doc! {
-        "$set": update_doc
-    };
+    let mut update = doc! {};
+    if !update_doc.is_empty() {
+        update.insert("$set", update_doc);
+    }
+    if !unset_doc.is_empty() {
+        update.insert("$unset", unset_doc);
+    }
 
     // insert document to boost collection
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task updated successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(&admin.sub, "/admin/tasks/discord/update", "tasks", body.id),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error updating tasks".to_string()),
     };
 }
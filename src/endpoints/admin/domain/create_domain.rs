@@ -1,15 +1,15 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::{QuestDocument, QuestTaskDocument};
-use crate::utils::verify_quest_auth;
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -23,26 +23,21 @@ pub_struct!(Deserialize; CreateTwitterFw {
 #[route(post, "/admin/tasks/domain/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateTwitterFw>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &body.quest_id).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &body.quest_id).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    let next_id = match get_next_id(&state.db, "tasks").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
 
     let new_document = QuestTaskDocument {
         name: body.name.clone(),
@@ -56,20 +51,37 @@ pub async fn handler(
         task_type: Some("domain".to_string()),
         cta: "Register a domain".to_string(),
         discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
         quiz_name: None,
         verify_redirect: None,
         contracts: None,
         api_url: None,
         regex: None,
+        verifier: None,
+        custom_api: None,
     };
 
     // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task created successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/tasks/domain/create",
+            "tasks",
+            next_id.into(),
+        ),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error creating task".to_string()),
     };
 }
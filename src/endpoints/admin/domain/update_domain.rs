@@ -1,9 +1,10 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::QuestTaskDocument;
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -22,11 +23,11 @@ pub_struct!(Deserialize; CreateTwitterFw {
 #[route(post, "/admin/tasks/domain/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateTwitterFw>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
-    let res = verify_task_auth(sub, &collection, &body.id).await;
+    let res = verify_task_auth(&admin, &collection, &body.id).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -51,12 +52,25 @@ pub async fn handler(
     };
 
     // insert document to boost collection
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task updated successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/domain/update",
+            "tasks",
+            body.id.into(),
+        ),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error updating tasks".to_string()),
     };
 }
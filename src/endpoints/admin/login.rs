@@ -1,70 +1,58 @@
-use crate::models::{JWTClaims, LoginDetails};
-use crate::utils::calculate_hash;
+use crate::common::admin_session::create_admin_session;
+use crate::models::LoginDetails;
+use crate::utils::{hash_password, verify_login};
 use crate::{models::AppState, utils::get_error};
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use chrono::Utc;
-use futures::StreamExt;
-use jsonwebtoken::{encode, EncodingKey, Header};
-use mongodb::bson::{doc, from_document};
+use mongodb::bson::doc;
 use serde::Deserialize;
-use serde_json::json;
 use std::sync::Arc;
 
 #[derive(Deserialize)]
-pub struct GetQuestsQuery {
-    code: String,
+pub struct LoginQuery {
+    user: String,
+    password: String,
 }
 
-#[route(get, "/admin/login")]
+#[route(post, "/admin/login")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<GetQuestsQuery>,
+    Json(body): Json<LoginQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<LoginDetails>("login_details");
-    let hashed_code = calculate_hash(&query.code);
-    let pipeline = [doc! {
-        "$match": {
-            "code": hashed_code.to_string(),
-        }
-    }];
+    let login = match collection.find_one(doc! { "user": &body.user }, None).await {
+        Ok(Some(login)) => login,
+        Ok(None) => return get_error("Incorrect Password".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
 
-    match collection.aggregate(pipeline, None).await {
-        Ok(mut cursor) => {
-            while let Some(result) = cursor.next().await {
-                match result {
-                    Ok(document) => {
-                        let secret_key = &state.conf.auth.secret_key;
-                        if let Ok(login) = from_document::<LoginDetails>(document) {
-                            let new_exp = (Utc::now().timestamp_millis()
-                                + &state.conf.auth.expiry_duration)
-                                as usize;
-                            let user_claims = JWTClaims {
-                                sub: login.user.parse().unwrap(),
-                                exp: new_exp,
-                            };
-                            let token = encode(
-                                &Header::default(),
-                                &user_claims,
-                                &EncodingKey::from_secret(&secret_key.as_ref()),
-                            )
-                            .unwrap();
-                            return (StatusCode::OK, Json(json!({"token":token}))).into_response();
-                        }
-                    }
-                    Err(e) => {
-                        return get_error(e.to_string());
-                    }
-                }
-            }
-            get_error("Incorrect Password".to_string())
-        }
-        Err(e) => {
+    if !verify_login(&login, &body.password) {
+        return get_error("Incorrect Password".to_string());
+    }
+
+    // upgrade accounts still using the legacy hash now that we know the password
+    if login.password_hash.is_none() {
+        let Ok(password_hash) = hash_password(&body.password) else {
+            return get_error("Error hashing password".to_string());
+        };
+        let update = doc! {
+            "$set": { "password_hash": password_hash },
+            "$unset": { "code": "" },
+        };
+        if let Err(e) = collection
+            .update_one(doc! { "user": &login.user }, update, None)
+            .await
+        {
             return get_error(e.to_string());
         }
     }
+
+    match create_admin_session(&state, &login).await {
+        Ok(tokens) => (StatusCode::OK, Json(tokens)).into_response(),
+        Err(_e) => get_error("Error creating token".to_string()),
+    }
 }
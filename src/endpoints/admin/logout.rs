@@ -0,0 +1,24 @@
+use crate::common::admin_session::revoke_admin_sessions;
+use crate::middleware::auth::auth_middleware;
+use crate::models::JWTClaims;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde_json::json;
+use std::sync::Arc;
+
+#[route(post, "/admin/logout", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JWTClaims>,
+) -> impl IntoResponse {
+    match revoke_admin_sessions(&state, doc! { "id": &claims.jti }).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"message": "Logged out successfully"}))).into_response(),
+        Err(_e) => get_error("Error revoking session".to_string()),
+    }
+}
@@ -1,12 +1,18 @@
+pub mod audit;
 pub mod balance;
 pub mod custom;
 pub mod delete_task;
 pub mod discord;
 pub mod domain;
 pub mod login;
+pub mod logout;
 pub mod nft_uri;
+pub mod partners;
 pub mod quest;
 pub mod quest_boost;
 pub mod quiz;
+pub mod refresh;
+pub mod session;
 pub mod twitter;
 pub mod user;
+pub mod verifier;
@@ -1,15 +1,15 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::{NFTUri, QuestDocument};
-use crate::utils::verify_quest_auth;
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -19,32 +19,31 @@ pub_struct!(Deserialize; CreateCustom {
     name: String,
     desc: String,
     image: String,
+    task_ids: Option<Vec<i64>>,
+    rarity: Option<String>,
+    animation_url: Option<String>,
+    external_url: Option<String>,
 });
 
 #[route(post, "/admin/nft_uri/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateCustom>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<NFTUri>("nft_uri");
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &(body.quest_id as i64)).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &(body.quest_id as i64)).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
 
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    let next_id = match get_next_id(&state.db, "nft_uri").await {
+        Ok(id) => id,
+        Err(e) => return get_error(e),
+    };
 
     let new_document = NFTUri {
         name: body.name.clone(),
@@ -53,15 +52,27 @@ pub async fn handler(
         quest_id: body.quest_id.clone() as i64,
         id: next_id,
         attributes: None,
+        task_ids: body.task_ids.clone(),
+        rarity: body.rarity.clone(),
+        animation_url: body.animation_url.clone(),
+        external_url: body.external_url.clone(),
     };
 
     // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Uri created successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(&admin.sub, "/admin/nft_uri/create", "nft_uri", next_id),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Uri created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error creating boosts".to_string()),
     };
 }
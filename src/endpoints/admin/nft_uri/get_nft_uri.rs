@@ -1,6 +1,9 @@
-use crate::models::NFTUri;
-use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::models::{NFTUri, QuestDocument};
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quest_view_auth},
+};
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -20,8 +23,13 @@ pub struct GetQuestsQuery {
 #[route(get, "/admin/nft_uri/get_nft_uri", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewQuests>,
     Query(query): Query<GetQuestsQuery>,
 ) -> impl IntoResponse {
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_view_auth(&admin, &quests_collection, &query.id).await {
+        return get_error("Error querying quest".to_string());
+    }
     let collection = state.db.collection::<NFTUri>("nft_uri");
     let pipeline = vec![
         doc! {
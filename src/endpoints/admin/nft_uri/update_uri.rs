@@ -1,6 +1,10 @@
-use crate::models::NFTUri;
-use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{NFTUri, QuestDocument};
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quest_auth},
+};
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
     extract::State,
     http::StatusCode,
@@ -17,11 +21,16 @@ pub_struct!(Deserialize; CreateCustom {
     name: Option<String>,
     desc: Option<String>,
     image: Option<String>,
+    task_ids: Option<Vec<i64>>,
+    rarity: Option<String>,
+    animation_url: Option<String>,
+    external_url: Option<String>,
 });
 
 #[route(post, "/admin/nft_uri/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateCustom>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<NFTUri>("nft_uri");
@@ -31,6 +40,17 @@ pub async fn handler(
         "id": &body.id,
     };
 
+    // the uri is editable by the issuer of its quest
+    let quest_id = match collection.find_one(filter.clone(), None).await {
+        Ok(Some(nft_uri)) => nft_uri.quest_id,
+        Ok(None) => return get_error("NFT Uri not found".to_string()),
+        Err(_e) => return get_error("Error updating tasks".to_string()),
+    };
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_auth(&admin, &quests_collection, &quest_id).await {
+        return get_error("Error updating tasks".to_string());
+    }
+
     let mut update_doc = doc! {};
 
     if let Some(name) = &body.name {
@@ -42,6 +62,18 @@ pub async fn handler(
     if let Some(image) = &body.image {
         update_doc.insert("image", image);
     }
+    if let Some(task_ids) = &body.task_ids {
+        update_doc.insert("task_ids", task_ids);
+    }
+    if let Some(rarity) = &body.rarity {
+        update_doc.insert("rarity", rarity);
+    }
+    if let Some(animation_url) = &body.animation_url {
+        update_doc.insert("animation_url", animation_url);
+    }
+    if let Some(external_url) = &body.external_url {
+        update_doc.insert("external_url", external_url);
+    }
 
     // update quest query
     let update = doc! {
@@ -49,12 +81,20 @@ pub async fn handler(
     };
 
     // insert document to boost collection
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task updated successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(&admin.sub, "/admin/nft_uri/update", "nft_uri", body.id),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error updating tasks".to_string()),
     };
 }
@@ -0,0 +1,74 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::WebhookDeliveryDocument;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct GetDeliveriesQuery {
+    name: String,
+    accepted: Option<bool>,
+    /*
+    page number starting from 0
+     */
+    page: Option<u64>,
+    page_size: Option<i64>,
+}
+
+#[route(get, "/admin/partners/get_deliveries", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    _admin: Admin<require::ManagePartners>,
+    Query(query): Query<GetDeliveriesQuery>,
+) -> impl IntoResponse {
+    let page = query.page.unwrap_or(0);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, MAX_PAGE_SIZE);
+
+    let mut filter = Document::new();
+    filter.insert("partner", &query.name);
+    if let Some(accepted) = query.accepted {
+        filter.insert("accepted", accepted);
+    }
+
+    let collection = state
+        .db
+        .collection::<WebhookDeliveryDocument>("webhook_deliveries");
+    let total = match collection.count_documents(filter.clone(), None).await {
+        Ok(total) => total,
+        Err(_e) => return get_error("Error querying deliveries".to_string()),
+    };
+
+    let options = FindOptions::builder()
+        .sort(doc! { "timestamp": -1 })
+        .skip(page * page_size as u64)
+        .limit(page_size)
+        .build();
+    match collection.find(filter, options).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<WebhookDeliveryDocument>>().await {
+            Ok(entries) => (
+                StatusCode::OK,
+                Json(json!({
+                    "entries": entries,
+                    "page": page,
+                    "page_size": page_size,
+                    "total": total,
+                })),
+            )
+                .into_response(),
+            Err(_e) => get_error("Error querying deliveries".to_string()),
+        },
+        Err(_e) => get_error("Error querying deliveries".to_string()),
+    }
+}
@@ -0,0 +1,47 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::PartnerDocument;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use serde_json::json;
+use std::sync::Arc;
+
+// Lists the partners with the names of their headers, their values are secrets
+#[route(get, "/admin/partners/get", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    _admin: Admin<require::ManagePartners>,
+) -> impl IntoResponse {
+    let cursor = match state
+        .db
+        .collection::<PartnerDocument>("partners")
+        .find(None, None)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(e) => return get_error(e.to_string()),
+    };
+    let partners: Vec<PartnerDocument> = match cursor.try_collect().await {
+        Ok(partners) => partners,
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    let partners: Vec<_> = partners
+        .into_iter()
+        .map(|partner| {
+            json!({
+                "name": partner.name,
+                "api_headers": partner.api_headers.into_keys().collect::<Vec<_>>(),
+                "allowed_hosts": partner.allowed_hosts,
+                "webhook_enabled": partner.webhook_secret.is_some(),
+                "webhook_task_ids": partner.webhook_task_ids,
+            })
+        })
+        .collect();
+    (StatusCode::OK, Json(partners)).into_response()
+}
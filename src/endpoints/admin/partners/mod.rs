@@ -0,0 +1,3 @@
+pub mod get_deliveries;
+pub mod get_partners;
+pub mod set_partner;
@@ -0,0 +1,131 @@
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::common::linked_accounts::encrypt_token;
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::PartnerDocument;
+use crate::utils::get_random_hex;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::{HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; SetPartner {
+    name: String,
+    api_headers: Option<HashMap<String, String>>,
+    allowed_hosts: Option<Vec<String>>,
+    webhook_task_ids: Option<Vec<i64>>,
+    rotate_webhook_secret: Option<bool>,
+});
+
+// Creates or updates a partner: the headers added to the requests of its custom_api
+// tasks and the tasks it can complete through /partners/webhook
+#[route(post, "/admin/partners/set", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ManagePartners>,
+    Json(body): Json<SetPartner>,
+) -> impl IntoResponse {
+    if body.name.is_empty() {
+        return get_error("Invalid partner name".to_string());
+    }
+
+    // new partners get empty headers, $setOnInsert leaves existing ones untouched
+    let mut update_doc = doc! {};
+    let mut insert_doc = doc! {};
+    match &body.api_headers {
+        Some(api_headers) => {
+            for (name, value) in api_headers {
+                if HeaderName::try_from(name.as_str()).is_err()
+                    || HeaderValue::try_from(value.as_str()).is_err()
+                {
+                    return get_error(format!("Invalid header: {}", name));
+                }
+            }
+            let api_headers: Document = api_headers
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone().into()))
+                .collect();
+            update_doc.insert("api_headers", api_headers);
+        }
+        None => {
+            insert_doc.insert("api_headers", Document::new());
+        }
+    }
+    // the headers are only sent to these hosts, whatever the url of the task
+    if let Some(allowed_hosts) = &body.allowed_hosts {
+        let mut hosts = vec![];
+        for host in allowed_hosts {
+            let host = host.trim().to_lowercase();
+            match reqwest::Url::parse(&format!("https://{}", host)) {
+                Ok(url) if url.host_str() == Some(host.as_str()) => hosts.push(host),
+                _ => return get_error(format!("Invalid host: {}", host)),
+            }
+        }
+        update_doc.insert("allowed_hosts", hosts);
+    }
+    if let Some(task_ids) = &body.webhook_task_ids {
+        update_doc.insert("webhook_task_ids", task_ids);
+    }
+
+    // the secret is only returned once, partners need a new one if they lose it
+    let webhook_secret = match body.rotate_webhook_secret {
+        Some(true) => Some(get_random_hex(32)),
+        _ => None,
+    };
+    if let Some(secret) = &webhook_secret {
+        match encrypt_token(&state, secret) {
+            Ok(encrypted) => update_doc.insert("webhook_secret", encrypted),
+            Err(e) => return get_error(e),
+        };
+    }
+
+    let mut update = doc! {};
+    if !update_doc.is_empty() {
+        update.insert("$set", update_doc);
+    }
+    if !insert_doc.is_empty() {
+        update.insert("$setOnInsert", insert_doc);
+    }
+    let entry = AuditEntry::keyed(
+        AuditAction::Update,
+        &admin.sub,
+        "/admin/partners/set",
+        "partners",
+        "name",
+        &body.name,
+    );
+    match with_admin_audit(
+        &state,
+        entry,
+        state
+            .db
+            .collection::<PartnerDocument>("partners")
+            .update_one(
+                doc! { "name": &body.name },
+                update,
+                UpdateOptions::builder().upsert(true).build(),
+            ),
+    )
+    .await
+    {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({
+                "message": "Partner saved successfully",
+                "webhook_secret": webhook_secret,
+            })),
+        )
+            .into_response(),
+        Err(_e) => get_error("Error saving partner".to_string()),
+    }
+}
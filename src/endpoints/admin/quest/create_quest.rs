@@ -1,14 +1,15 @@
-use crate::models::QuestInsertDocument;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{Permission, QuestInsertDocument};
+use crate::utils::get_next_id;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
 use mongodb::bson::{doc, from_document};
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -31,32 +32,27 @@ pub_struct!(Deserialize; CreateQuestQuery {
 #[route(post, "/admin/quest/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<CreateQuestQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestInsertDocument>("quests");
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    let next_id = match get_next_id(&state.db, "quests").await {
+        Ok(id) => id as u32,
+        Err(e) => return get_error(e),
+    };
 
     let nft_reward = doc! {
         "img": body.img_card.clone().to_string(),
         "level": 1,
     };
 
-    let issuer = match sub == "super_user" {
+    let issuer = match admin.has_permission(Permission::EditAllQuests) {
         true => {
             let result_issuer = (&body.issuer).as_ref().unwrap();
             result_issuer
         }
-        false => &sub,
+        false => &admin.sub,
     };
 
     let mut new_document = doc! {
@@ -87,12 +83,15 @@ pub async fn handler(
     };
 
     // insert document to boost collection
-    return match collection
-        .insert_one(
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(&admin.sub, "/admin/quest/create", "quests", next_id.into()),
+        collection.insert_one(
             from_document::<QuestInsertDocument>(new_document).unwrap(),
             None,
-        )
-        .await
+        ),
+    )
+    .await
     {
         Ok(_res) => {
             return (
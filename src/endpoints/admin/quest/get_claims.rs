@@ -0,0 +1,77 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::{QuestDocument, RewardClaimDocument};
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quest_view_auth},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct GetClaimsQuery {
+    quest_id: u32,
+}
+
+// Number of signatures issued, minted and expired for each reward level of a quest
+#[route(get, "/admin/quest/get_claims", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewQuests>,
+    Query(query): Query<GetClaimsQuery>,
+) -> impl IntoResponse {
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_view_auth(&admin, &quests_collection, &(query.quest_id as i64)).await {
+        return get_error("Error querying claims".to_string());
+    }
+    let now = Utc::now().timestamp_millis();
+    let pipeline = vec![
+        doc! { "$match": { "quest_id": query.quest_id } },
+        doc! {
+            "$group": {
+                "_id": "$level",
+                "issued": { "$sum": 1 },
+                "minted": {
+                    "$sum": { "$cond": [{ "$eq": ["$status", "minted"] }, 1, 0] }
+                },
+                "expired": {
+                    "$sum": {
+                        "$cond": [
+                            {
+                                "$and": [
+                                    { "$eq": ["$status", "issued"] },
+                                    { "$lte": ["$expiry", now] },
+                                ]
+                            },
+                            1,
+                            0,
+                        ]
+                    }
+                },
+            }
+        },
+        doc! { "$sort": { "_id": 1 } },
+        doc! { "$project": { "_id": 0, "level": "$_id", "issued": 1, "minted": 1, "expired": 1 } },
+    ];
+    match state
+        .db
+        .collection::<RewardClaimDocument>("reward_claims")
+        .aggregate(pipeline, None)
+        .await
+    {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(levels) => (StatusCode::OK, Json(json!({ "levels": levels }))).into_response(),
+            Err(_e) => get_error("Error querying claims".to_string()),
+        },
+        Err(_e) => get_error("Error querying claims".to_string()),
+    }
+}
@@ -1,10 +1,10 @@
 use crate::{
-    models::{AppState, QuestDocument},
+    models::{AppState, Permission, QuestDocument},
     utils::get_error,
 };
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Query, State, Extension},
+    extract::{Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -23,7 +23,7 @@ pub struct GetQuestsQuery {
 pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(query): Query<GetQuestsQuery>,
-    Extension(sub): Extension<String>
+    admin: Admin<require::ViewQuests>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestDocument>("quests");
     let mut pipeline = vec![
@@ -69,12 +69,12 @@ pub async fn handler(
         },
     ];
 
-    if sub != "super_user" {
+    if !admin.has_permission(Permission::ViewAllQuests) {
         pipeline.insert(
             1,
             doc! {
                 "$match": doc! {
-                    "issuer": sub,
+                    "issuer": &admin.sub,
                 }
             },
         );
@@ -1,10 +1,10 @@
 use crate::{
-    models::{AppState, QuestDocument},
+    models::{AppState, Permission, QuestDocument},
     utils::get_error,
 };
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -16,13 +16,13 @@ use std::sync::Arc;
 #[route(get, "/admin/quest/get_quests", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>, 
-    Extension(sub): Extension<String> 
+    admin: Admin<require::ViewQuests>, 
 ) -> impl IntoResponse {
     let mut pipeline = vec![];
-    if sub != "super_user" {
+    if !admin.has_permission(Permission::ViewAllQuests) {
         pipeline.push(doc! {
             "$match": doc! {
-                "issuer":sub
+                "issuer": &admin.sub
             }
         });
     }
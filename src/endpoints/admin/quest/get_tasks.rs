@@ -1,5 +1,9 @@
-use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::models::QuestDocument;
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quest_view_auth},
+};
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -35,8 +39,13 @@ pub struct GetTasksQuery {
 #[route(get, "/admin/quest/get_tasks", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewQuests>,
     Query(query): Query<GetTasksQuery>,
 ) -> impl IntoResponse {
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_view_auth(&admin, &quests_collection, &(query.quest_id as i64)).await {
+        return get_error("Error querying tasks".to_string());
+    }
     let pipeline = vec![
         doc! { "$match": { "quest_id": query.quest_id } },
         doc! {
@@ -1,5 +1,7 @@
 pub mod create_quest;
+pub mod get_claims;
 mod get_quest;
 pub mod get_quests;
 pub mod get_tasks;
+pub mod set_rewards;
 pub mod update_quest;
@@ -0,0 +1,157 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::quest_rewards::{get_quest_rewards, validate_rewards};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::{AppState, QuestDocument, QuestReward, TokenRewardClaimDocument};
+use crate::utils::{get_error, get_next_id, verify_quest_auth};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, to_bson};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+// Reward as sent by admins, token rewards get their claim id from the server
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RewardDefinition {
+    Nft,
+    Token {
+        token: String,
+        amount: String,
+        #[serde(default)]
+        max_recipients: Option<u32>,
+        #[serde(default)]
+        budget: Option<String>,
+    },
+    Xp {
+        amount: i64,
+    },
+}
+
+#[derive(Deserialize)]
+pub struct SetRewardsQuery {
+    quest_id: u32,
+    rewards: Vec<RewardDefinition>,
+}
+
+#[route(post, "/admin/quest/set_rewards", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::EditQuests>,
+    Json(body): Json<SetRewardsQuery>,
+) -> impl IntoResponse {
+    let collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_auth(&admin, &collection, &(body.quest_id as i64)).await {
+        return get_error("Error setting rewards".to_string());
+    }
+    let quest = match collection
+        .find_one(doc! { "id": body.quest_id }, None)
+        .await
+    {
+        Ok(Some(quest)) => quest,
+        Ok(None) => return get_error("quest does not exist".to_string()),
+        Err(_) => return get_error("Error setting rewards".to_string()),
+    };
+
+    // a token reward keeps its claim id while its token and amount don't change, since
+    // the signatures already issued for it commit to them
+    let mut previous: Vec<(u32, String, String)> = get_quest_rewards(&quest)
+        .into_iter()
+        .filter_map(|reward| match reward {
+            QuestReward::Token {
+                claim_id,
+                token,
+                amount,
+                ..
+            } => Some((claim_id, token, amount)),
+            _ => None,
+        })
+        .collect();
+    let mut rewards = vec![];
+    for reward in body.rewards {
+        rewards.push(match reward {
+            RewardDefinition::Nft => QuestReward::Nft,
+            RewardDefinition::Xp { amount } => QuestReward::Xp { amount },
+            RewardDefinition::Token {
+                token,
+                amount,
+                max_recipients,
+                budget,
+            } => {
+                let kept = previous
+                    .iter()
+                    .position(|(_, t, a)| t.eq_ignore_ascii_case(&token) && *a == amount);
+                let claim_id = match kept {
+                    Some(index) => previous.remove(index).0,
+                    // token rewards are paid by the boost contract, their claim ids are
+                    // taken from the boost ids so that they never collide
+                    None => match get_next_id(&state.db, "boosts").await {
+                        Ok(id) => id as u32,
+                        Err(e) => return get_error(e),
+                    },
+                };
+                QuestReward::Token {
+                    claim_id,
+                    token,
+                    amount,
+                    max_recipients,
+                    budget,
+                }
+            }
+        });
+    }
+    if let Err(e) = validate_rewards(&rewards) {
+        return get_error(e);
+    }
+
+    // rewards some completers were already issued a claim for can't be removed
+    for (claim_id, _, _) in previous {
+        match state
+            .db
+            .collection::<TokenRewardClaimDocument>("token_reward_claims")
+            .count_documents(doc! { "claim_id": claim_id }, None)
+            .await
+        {
+            Ok(0) => {}
+            Ok(_) => {
+                return get_error(format!(
+                    "Token reward {} was already issued and can't be changed",
+                    claim_id
+                ))
+            }
+            Err(_) => return get_error("Error setting rewards".to_string()),
+        }
+    }
+
+    let experience = rewards
+        .iter()
+        .find_map(|reward| match reward {
+            QuestReward::Xp { amount } => Some(*amount),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let Ok(rewards_bson) = to_bson(&rewards) else {
+        return get_error("Error setting rewards".to_string());
+    };
+    let update = doc! { "$set": { "rewards": rewards_bson, "experience": experience } };
+
+    match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/quest/set_rewards",
+            "quests",
+            body.quest_id.into(),
+        ),
+        collection.update_one(doc! { "id": body.quest_id }, update, None),
+    )
+    .await
+    {
+        Ok(_) => (StatusCode::OK, Json(json!({ "rewards": rewards }))).into_response(),
+        Err(_) => get_error("Error setting rewards".to_string()),
+    }
+}
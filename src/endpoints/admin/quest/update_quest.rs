@@ -1,8 +1,9 @@
-use crate::models::QuestDocument;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{Permission, QuestDocument};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -31,7 +32,7 @@ pub_struct!(Deserialize; UpdateQuestQuery {
 #[route(post, "/admin/quest/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     Json(body): Json<UpdateQuestQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestDocument>("quests");
@@ -41,9 +42,9 @@ pub async fn handler(
         "id": &body.id,
     };
 
-    // check if user is super_user
-    if sub != "super_user" {
-        filter.insert("issuer", sub);
+    // issuers can only update their own quests
+    if !admin.has_permission(Permission::EditAllQuests) {
+        filter.insert("issuer", &admin.sub);
     }
 
     let existing_quest = &collection.find_one(filter.clone(), None).await.unwrap();
@@ -100,12 +101,20 @@ pub async fn handler(
         "$set": update_doc
     };
 
-    return match collection.find_one_and_update(filter, update, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(&admin.sub, "/admin/quest/update", "quests", body.id.into()),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("error updating quest".to_string()),
     };
 }
@@ -1,15 +1,15 @@
-use crate::models::{BoostTable, QuestDocument};
-use crate::utils::verify_quest_auth;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{BoostTable, Permission, QuestDocument};
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -30,27 +30,23 @@ pub struct CreateBoostQuery {
 #[route(post, "/admin/quest_boost/create_boost", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::ManageBoosts>,
     Json(body): Json<CreateBoostQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<BoostTable>("boosts");
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &(body.quest_id as i64)).await;
+    let res = admin.has_permission(Permission::ManageAllBoosts)
+        || verify_quest_auth(&admin, &quests_collection, &(body.quest_id as i64)).await;
     if !res {
         return get_error("Error creating boost".to_string());
     };
 
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_doc = &collection.find_one(last_id_filter, options).await.unwrap();
 
-    let mut next_id = 1;
-    if let Some(doc) = last_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
+    let next_id = match get_next_id(&state.db, "boosts").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
 
     let new_document = BoostTable {
         name: body.name.clone(),
@@ -64,15 +60,29 @@ pub async fn handler(
         hidden: body.hidden.clone(),
         img_url: body.img_url.clone(),
         winner: None,
+        draw: None,
     };
 
     // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Boost created successfully"})).into_response(),
-        )
-            .into_response(),
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/quest_boost/create_boost",
+            "boosts",
+            next_id.into(),
+        ),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Boost created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("Error creating boosts".to_string()),
     };
 }
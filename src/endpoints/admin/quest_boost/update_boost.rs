@@ -1,9 +1,10 @@
-use crate::models::{BoostTable, QuestDocument};
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{BoostTable, Permission, QuestDocument};
 use crate::utils::verify_quest_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -29,7 +30,7 @@ pub_struct!(Deserialize; UpdateBoostQuery {
 #[route(post, "/admin/quest_boost/update_boost", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::ManageBoosts>,
     body: Json<UpdateBoostQuery>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<BoostTable>("boosts");
@@ -44,7 +45,8 @@ pub async fn handler(
         return get_error("boost does not exist".to_string());
     }
     let quest_id = res.as_ref().unwrap().quests[0];
-    let res = verify_quest_auth(sub, &questcollection, &(quest_id as i64)).await;
+    let res = admin.has_permission(Permission::ManageAllBoosts)
+        || verify_quest_auth(&admin, &questcollection, &(quest_id as i64)).await;
 
     if !res {
         return get_error("Error updating boost".to_string());
@@ -87,15 +89,25 @@ pub async fn handler(
         "$set": update_doc
     };
     let options = FindOneAndUpdateOptions::default();
-    return match collection
-        .find_one_and_update(filter, update, options)
-        .await
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/quest_boost/update_boost",
+            "boosts",
+            body.id.into(),
+        ),
+        collection.find_one_and_update(filter, update, options),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("error updating boost".to_string()),
     };
 }
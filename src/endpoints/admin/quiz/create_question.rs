@@ -1,17 +1,17 @@
-use crate::models::{
-    QuestDocument, QuestTaskDocument, QuizInsertDocument, QuizQuestionDocument,
-};
-use crate::utils::verify_quest_auth;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::quiz_grading::validate_question_document;
+use crate::config::QuizQuestionType;
+use crate::models::{QuestDocument, QuizInsertDocument, QuizQuestionDocument};
+use crate::utils::{get_next_id, get_quiz_quest_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
+use mongodb::bson::{doc, to_document};
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -19,14 +19,21 @@ use std::sync::Arc;
 pub_struct!(Deserialize; CreateQuizQuestion {
     quiz_id: i64,
     question: String,
-    options:Vec<String>,
-    correct_answers: Vec<i64>,
+    options: Option<Vec<String>>,
+    correct_answers: Option<Vec<i64>>,
+    // text_choice if not set
+    kind: Option<QuizQuestionType>,
+    layout: Option<String>,
+    correct_value: Option<f64>,
+    tolerance: Option<f64>,
+    accepted_answers: Option<Vec<String>>,
+    answer_regex: Option<String>,
 });
 
 #[route(post, "/admin/tasks/quiz/question/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     body: Json<CreateQuizQuestion>,
 ) -> impl IntoResponse {
     let quiz_collection = state.db.collection::<QuizInsertDocument>("quizzes");
@@ -34,20 +41,13 @@ pub async fn handler(
         .db
         .collection::<QuizQuestionDocument>("quiz_questions");
     let quests_collection = state.db.collection::<QuestDocument>("quests");
-    let tasks_collection = state.db.collection::<QuestTaskDocument>("tasks");
 
-    let pipeline = doc! {
-        "quiz_name": &body.quiz_id,
-    };
-    let res = &tasks_collection.find_one(pipeline, None).await.unwrap();
-    if res.is_none() {
+    // the quest of the quiz is the one of its task
+    let Some(quest_id) = get_quiz_quest_id(&state.db, body.quiz_id).await else {
         return get_error("quiz does not exist".to_string());
-    }
-
-    // get the quest id
-    let quest_id = res.as_ref().unwrap().id as i64;
+    };
 
-    let res = verify_quest_auth(sub, &quests_collection, &quest_id).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &quest_id).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
@@ -65,39 +65,60 @@ pub async fn handler(
         return get_error("quiz does not exist".to_string());
     }
 
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_quiz_question_doc = &quiz_questions_collection
-        .find_one(last_id_filter.clone(), options.clone())
-        .await
-        .unwrap();
-
-    let mut next_quiz_question_id = 1;
-    if let Some(doc) = last_quiz_question_doc {
-        let last_id = doc.id;
-        next_quiz_question_id = last_id + 1;
-    }
+    let next_quiz_question_id = match get_next_id(&state.db, "quiz_questions").await {
+        Ok(id) => id,
+        Err(e) => return get_error(e),
+    };
 
     let new_quiz_document = QuizQuestionDocument {
         quiz_id: body.quiz_id.clone(),
         question: body.question.clone(),
-        options: body.options.clone(),
-        correct_answers: body.correct_answers.clone(),
+        options: body.options.clone().unwrap_or_default(),
+        correct_answers: body.correct_answers.clone().unwrap_or_default(),
         id: next_quiz_question_id,
-        kind: "text_choice".to_string(),
-        layout: "default".to_string(),
+        kind: body
+            .kind
+            .as_ref()
+            .unwrap_or(&QuizQuestionType::TextChoice)
+            .as_str()
+            .to_string(),
+        layout: body
+            .layout
+            .clone()
+            .unwrap_or_else(|| "default".to_string()),
+        correct_order: None,
+        image_for_layout: None,
+        correct_value: body.correct_value,
+        tolerance: body.tolerance,
+        accepted_answers: body.accepted_answers.clone(),
+        answer_regex: body.answer_regex.clone(),
     };
+    let validated = to_document(&new_quiz_document)
+        .map_err(|e| e.to_string())
+        .and_then(validate_question_document);
+    if let Err(e) = validated {
+        return get_error(e);
+    }
 
-    return match quiz_questions_collection
-        .insert_one(new_quiz_document, None)
-        .await
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/tasks/quiz/question/create",
+            "quiz_questions",
+            next_quiz_question_id,
+        ),
+        quiz_questions_collection.insert_one(new_quiz_document, None),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "Task created successfully"})).into_response(),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task created successfully"})).into_response(),
+            )
+                .into_response()
+        }
         Err(_e) => return get_error("Error creating task".to_string()),
     };
 }
@@ -1,15 +1,15 @@
+use crate::common::admin_audit::{record_admin_audit, AuditEntry};
 use crate::models::{QuestDocument, QuestTaskDocument, QuizInsertDocument};
-use crate::utils::verify_quest_auth;
+use crate::common::quiz_attempts::validate_quiz_limits;
+use crate::utils::{get_next_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{State, Extension},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::doc;
-use mongodb::options::FindOneOptions;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -21,12 +21,15 @@ pub_struct!(Deserialize; CreateQuiz {
     cta: String,
     intro: String,
     quest_id: i64,
+    passing_score: Option<u32>,
+    max_attempts: Option<u32>,
+    cooldown: Option<i64>,
 });
 
 #[route(post, "/admin/tasks/quiz/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     body: Json<CreateQuiz>,
 ) -> impl IntoResponse {
     let tasks_collection = state.db.collection::<QuestTaskDocument>("tasks");
@@ -34,72 +37,87 @@ pub async fn handler(
 
     let quests_collection = state.db.collection::<QuestDocument>("quests");
 
-    let res = verify_quest_auth(sub, &quests_collection, &body.quest_id).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &body.quest_id).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
-
-    // Get the last id in increasing order
-    let last_id_filter = doc! {};
-    let options = FindOneOptions::builder().sort(doc! {"id": -1}).build();
-    let last_quiz_doc = &quiz_collection
-        .find_one(last_id_filter.clone(), options.clone())
-        .await
-        .unwrap();
-
-    let mut next_quiz_id = 1;
-    if let Some(doc) = last_quiz_doc {
-        let last_id = doc.id;
-        next_quiz_id = last_id + 1;
+    if let Err(e) = validate_quiz_limits(body.passing_score, body.max_attempts, body.cooldown) {
+        return get_error(e);
     }
 
+    let next_quiz_id = match get_next_id(&state.db, "quizzes").await {
+        Ok(id) => id as u32,
+        Err(e) => return get_error(e),
+    };
+    let next_id = match get_next_id(&state.db, "tasks").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
+
     let new_quiz_document = QuizInsertDocument {
         name: body.name.clone(),
         desc: body.desc.clone(),
-        id: next_quiz_id.clone(),
+        id: next_quiz_id,
         intro: body.intro.clone(),
+        passing_score: body.passing_score,
+        max_attempts: body.max_attempts,
+        cooldown: body.cooldown,
     };
 
-    match quiz_collection.insert_one(new_quiz_document, None).await {
-        Ok(res) => res,
-        Err(_e) => return get_error("Error creating quiz".to_string()),
-    };
-
-    let last_task_doc = &tasks_collection
-        .find_one(last_id_filter.clone(), options.clone())
-        .await
-        .unwrap();
-    let mut next_id = 1;
-    if let Some(doc) = last_task_doc {
-        let last_id = doc.id;
-        next_id = last_id + 1;
-    }
-
     let new_document = QuestTaskDocument {
         name: body.name.clone(),
         desc: body.desc.clone(),
         href: body.help_link.clone(),
         total_amount: None,
         cta: body.cta.clone(),
-        quest_id: body.quest_id.clone(),
-        id: next_id.clone(),
+        quest_id: body.quest_id,
+        id: next_id,
         verify_endpoint: "/quests/verify_quiz".to_string(),
         verify_endpoint_type: "quiz".to_string(),
-        quiz_name: Some(next_quiz_id.clone() as i64),
+        quiz_name: Some(next_quiz_id as i64),
         task_type: Some("quiz".to_string()),
         discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
         verify_redirect: None,
         contracts: None,
         api_url: None,
         regex: None,
+        verifier: None,
+        custom_api: None,
     };
 
-    return match tasks_collection.insert_one(new_document, None).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"id": &next_quiz_id })).into_response(),
-        )
-            .into_response(),
-        Err(_e) => return get_error("Error creating quiz".to_string()),
+    // the quiz and its task are created together or not at all
+    let Ok(mut session) = state.client.start_session(None).await else {
+        return get_error("Error creating quiz".to_string());
+    };
+    if session.start_transaction(None).await.is_err() {
+        return get_error("Error creating quiz".to_string());
+    }
+    let inserted = match quiz_collection
+        .insert_one_with_session(new_quiz_document, None, &mut session)
+        .await
+    {
+        Ok(_) => {
+            tasks_collection
+                .insert_one_with_session(new_document, None, &mut session)
+                .await
+        }
+        Err(e) => Err(e),
     };
+    if inserted.is_err() || session.commit_transaction().await.is_err() {
+        let _ = session.abort_transaction().await;
+        return get_error("Error creating quiz".to_string());
+    }
+
+    let route = "/admin/tasks/quiz/create";
+    let quiz_entry = AuditEntry::create(&admin.sub, route, "quizzes", next_quiz_id.into());
+    record_admin_audit(&state, &quiz_entry, None).await;
+    let task_entry = AuditEntry::create(&admin.sub, route, "tasks", next_id.into());
+    record_admin_audit(&state, &task_entry, None).await;
+    (
+        StatusCode::OK,
+        Json(json!({"id": &next_quiz_id })).into_response(),
+    )
+        .into_response()
 }
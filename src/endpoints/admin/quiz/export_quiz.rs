@@ -0,0 +1,52 @@
+use crate::common::quiz_file::{export_quiz, serialize_quiz, QuizFileFormat};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quiz_view_auth},
+};
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ExportQuizQuery {
+    id: i64,
+    format: Option<QuizFileFormat>,
+}
+
+#[route(get, "/admin/quiz/export", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewQuests>,
+    Query(query): Query<ExportQuizQuery>,
+) -> impl IntoResponse {
+    // exports hold the correct answers, only admins who can see the quest get them
+    if !verify_quiz_view_auth(&admin, &state.db, query.id).await {
+        return get_error("Quiz not found".to_string());
+    }
+    let quiz = match export_quiz(&state, query.id).await {
+        Ok(Some(quiz)) => quiz,
+        Ok(None) => return get_error("Quiz not found".to_string()),
+        Err(e) => return get_error(e),
+    };
+
+    let format = query.format.unwrap_or_default();
+    let content_type = match format {
+        QuizFileFormat::Json => "application/json",
+        QuizFileFormat::Toml => "application/toml",
+    };
+    match serialize_quiz(&quiz, format) {
+        Ok(content) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, content_type)],
+            content,
+        )
+            .into_response(),
+        Err(e) => get_error(e),
+    }
+}
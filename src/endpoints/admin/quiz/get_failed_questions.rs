@@ -0,0 +1,98 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::QuizAttemptDocument;
+use crate::utils::verify_quiz_view_auth;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+const MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct GetFailedQuestionsQuery {
+    id: i64,
+    limit: Option<i64>,
+}
+
+// Questions of a quiz sorted by the number of attempts in which they were answered wrong
+#[route(get, "/admin/quiz/get_failed_questions", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewAnalytics>,
+    Query(query): Query<GetFailedQuestionsQuery>,
+) -> impl IntoResponse {
+    if !verify_quiz_view_auth(&admin, &state.db, query.id).await {
+        return get_error("Error querying quiz attempts".to_string());
+    }
+    let limit = query.limit.unwrap_or(10).clamp(1, MAX_LIMIT);
+    let collection = state.db.collection::<QuizAttemptDocument>("quiz_attempts");
+
+    let filter = doc! { "quiz_id": query.id };
+    let attempts = match collection.count_documents(filter.clone(), None).await {
+        Ok(attempts) => attempts,
+        Err(_e) => return get_error("Error querying quiz attempts".to_string()),
+    };
+    let passed_filter = doc! { "quiz_id": query.id, "passed": true };
+    let passed = match collection.count_documents(passed_filter, None).await {
+        Ok(passed) => passed,
+        Err(_e) => return get_error("Error querying quiz attempts".to_string()),
+    };
+
+    let pipeline = vec![
+        doc! { "$match": filter },
+        doc! { "$unwind": "$results" },
+        doc! {
+            "$group": {
+                "_id": "$results.question_id",
+                "attempts": { "$sum": 1 },
+                "failures": {
+                    "$sum": { "$cond": [{ "$eq": ["$results.correct", false] }, 1, 0] }
+                },
+            }
+        },
+        doc! { "$sort": { "failures": -1, "_id": 1 } },
+        doc! { "$limit": limit },
+        doc! {
+            "$lookup": {
+                "from": "quiz_questions",
+                "localField": "_id",
+                "foreignField": "id",
+                "as": "question"
+            }
+        },
+        doc! {
+            "$project": {
+                "_id": 0,
+                "question_id": "$_id",
+                "question": { "$first": "$question.question" },
+                "attempts": 1,
+                "failures": 1,
+                "failure_rate": { "$divide": ["$failures", "$attempts"] },
+            }
+        },
+    ];
+
+    match collection.aggregate(pipeline, None).await {
+        Ok(cursor) => match cursor.try_collect::<Vec<Document>>().await {
+            Ok(questions) => (
+                StatusCode::OK,
+                Json(json!({
+                    "attempts": attempts,
+                    "passed": passed,
+                    "questions": questions,
+                })),
+            )
+                .into_response(),
+            Err(_e) => get_error("Error querying quiz attempts".to_string()),
+        },
+        Err(_e) => get_error("Error querying quiz attempts".to_string()),
+    }
+}
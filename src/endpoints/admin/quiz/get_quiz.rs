@@ -1,6 +1,9 @@
 use crate::models::QuizInsertDocument;
-use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::{
+    models::AppState,
+    utils::{get_error, verify_quiz_view_auth},
+};
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -20,8 +23,12 @@ pub struct GetQuestsQuery {
 #[route(get, "/admin/quiz/get_quiz", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    admin: Admin<require::ViewQuests>,
     Query(query): Query<GetQuestsQuery>,
 ) -> impl IntoResponse {
+    if !verify_quiz_view_auth(&admin, &state.db, query.id).await {
+        return get_error("Error querying quest".to_string());
+    }
     let collection = state.db.collection::<QuizInsertDocument>("quizzes");
     let pipeline = vec![
         doc! {
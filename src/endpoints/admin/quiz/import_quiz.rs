@@ -0,0 +1,80 @@
+use crate::common::admin_audit::{record_admin_audit, AuditEntry};
+use crate::common::quiz_file::{import_quiz, parse_quiz, QuizFileFormat};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::QuestDocument;
+use crate::utils::verify_quest_auth;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ImportQuizQuery {
+    quest_id: i64,
+    format: Option<QuizFileFormat>,
+}
+
+// The body is a quiz file as produced by /admin/quiz/export
+#[route(post, "/admin/quiz/import", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::EditQuests>,
+    Query(query): Query<ImportQuizQuery>,
+    body: String,
+) -> impl IntoResponse {
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+    if !verify_quest_auth(&admin, &quests_collection, &query.quest_id).await {
+        return get_error("Error creating task".to_string());
+    }
+    // admins allowed on every quest pass the check above without the quest being read
+    match quests_collection
+        .find_one(doc! { "id": query.quest_id }, None)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => return get_error("quest does not exist".to_string()),
+        Err(_) => return get_error("Error creating task".to_string()),
+    }
+
+    let quiz = match parse_quiz(&body, query.format.unwrap_or_default()) {
+        Ok(quiz) => quiz,
+        Err(e) => return get_error(format!("Invalid quiz file: {}", e)),
+    };
+    let imported = match import_quiz(&state, query.quest_id, &quiz).await {
+        Ok(imported) => imported,
+        Err(e) => return get_error(e),
+    };
+
+    let route = "/admin/quiz/import";
+    let mut entries = vec![
+        AuditEntry::create(&admin.sub, route, "quizzes", imported.quiz_id.into()),
+        AuditEntry::create(&admin.sub, route, "tasks", imported.task_id.into()),
+    ];
+    for question_id in &imported.question_ids {
+        entries.push(AuditEntry::create(
+            &admin.sub,
+            route,
+            "quiz_questions",
+            *question_id,
+        ));
+    }
+    for entry in &entries {
+        record_admin_audit(&state, entry, None).await;
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "id": imported.quiz_id,
+            "task_id": imported.task_id,
+            "question_ids": imported.question_ids,
+        })),
+    )
+        .into_response()
+}
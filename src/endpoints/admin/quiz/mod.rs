@@ -1,5 +1,8 @@
 pub mod create_question;
 pub mod create_quiz;
+pub mod export_quiz;
+pub mod get_failed_questions;
 pub mod get_quiz;
+pub mod import_quiz;
 pub mod update_question;
 pub mod update_quiz;
@@ -1,11 +1,12 @@
-use crate::models::{
-    QuestDocument, QuestTaskDocument, QuizInsertDocument, QuizQuestionDocument,
-};
-use crate::utils::verify_quest_auth;
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::common::quiz_grading::validate_question_document;
+use crate::config::QuizQuestionType;
+use crate::models::{QuestDocument, QuizInsertDocument, QuizQuestionDocument};
+use crate::utils::{get_quiz_quest_id, verify_quest_auth};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -22,15 +23,20 @@ pub_struct!(Deserialize; UpdateQuiz {
     question: Option<String>,
     options:Option<Vec<String>>,
     correct_answers: Option<Vec<i64>>,
+    kind: Option<QuizQuestionType>,
+    layout: Option<String>,
+    correct_value: Option<f64>,
+    tolerance: Option<f64>,
+    accepted_answers: Option<Vec<String>>,
+    answer_regex: Option<String>,
 });
 
 #[route(post, "/admin/tasks/quiz/question/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     body: Json<UpdateQuiz>,
 ) -> impl IntoResponse {
-    let tasks_collection = state.db.collection::<QuestTaskDocument>("tasks");
     let quiz_collection = state.db.collection::<QuizInsertDocument>("quizzes");
 
     let quests_collection = state.db.collection::<QuestDocument>("quests");
@@ -38,18 +44,12 @@ pub async fn handler(
         .db
         .collection::<QuizQuestionDocument>("quiz_questions");
 
-    let pipeline = doc! {
-        "quiz_name": &body.quiz_id,
-    };
-    let res = &tasks_collection.find_one(pipeline, None).await.unwrap();
-    if res.is_none() {
+    // the quest of the quiz is the one of its task
+    let Some(quest_id) = get_quiz_quest_id(&state.db, body.quiz_id as i64).await else {
         return get_error("quiz does not exist".to_string());
-    }
-
-    // get the quest id
-    let quest_id = res.as_ref().unwrap().id as i64;
+    };
 
-    let res = verify_quest_auth(sub, &quests_collection, &quest_id).await;
+    let res = verify_quest_auth(&admin, &quests_collection, &quest_id).await;
     if !res {
         return get_error("Error creating task".to_string());
     };
@@ -78,25 +78,71 @@ pub async fn handler(
     if let Some(correct_answers) = &body.correct_answers {
         update_doc.insert("correct_answers", correct_answers);
     }
+    if let Some(kind) = &body.kind {
+        update_doc.insert("kind", kind.as_str());
+    }
+    if let Some(layout) = &body.layout {
+        update_doc.insert("layout", layout);
+    }
+    if let Some(correct_value) = body.correct_value {
+        update_doc.insert("correct_value", correct_value);
+    }
+    if let Some(tolerance) = body.tolerance {
+        update_doc.insert("tolerance", tolerance);
+    }
+    if let Some(accepted_answers) = &body.accepted_answers {
+        update_doc.insert("accepted_answers", accepted_answers);
+    }
+    if let Some(answer_regex) = &body.answer_regex {
+        update_doc.insert("answer_regex", answer_regex);
+    }
 
+    // the question must belong to the quiz the permission was checked on
     let question_filter = doc! {
         "id": &body.id,
+        "quiz_id": &body.quiz_id,
     };
 
+    // the question must still be gradable once updated
+    let mut updated_question = match state
+        .db
+        .collection::<Document>("quiz_questions")
+        .find_one(question_filter.clone(), None)
+        .await
+    {
+        Ok(Some(question)) => question,
+        Ok(None) => return get_error("Question not found".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+    updated_question.extend(update_doc.clone());
+    if let Err(e) = validate_question_document(updated_question) {
+        return get_error(e);
+    }
+
     // update question
     let update = doc! {
         "$set": update_doc,
     };
     let options = FindOneAndUpdateOptions::default();
-    return match quiz_questions_collection
-        .find_one_and_update(question_filter, update.clone(), options)
-        .await
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/quiz/question/update",
+            "quiz_questions",
+            body.id.into(),
+        ),
+        quiz_questions_collection.find_one_and_update(question_filter, update.clone(), options),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
 
         Err(_e) => get_error("error updating task".to_string()),
     };
@@ -1,9 +1,11 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::{QuestTaskDocument, QuizInsertDocument};
+use crate::common::quiz_attempts::validate_quiz_limits;
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -23,21 +25,27 @@ pub_struct!(Deserialize; UpdateQuiz {
     help_link: Option<String>,
     cta: Option<String>,
     intro: Option<String>,
+    passing_score: Option<u32>,
+    max_attempts: Option<u32>,
+    cooldown: Option<i64>,
 });
 
 #[route(post, "/admin/tasks/quiz/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
     body: Json<UpdateQuiz>,
 ) -> impl IntoResponse {
     let tasks_collection = state.db.collection::<QuestTaskDocument>("tasks");
     let quiz_collection = state.db.collection::<QuizInsertDocument>("quizzes");
 
-    let res = verify_task_auth(sub, &tasks_collection, &(body.id as i32)).await;
+    let res = verify_task_auth(&admin, &tasks_collection, &(body.id as i32)).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
+    if let Err(e) = validate_quiz_limits(body.passing_score, body.max_attempts, body.cooldown) {
+        return get_error(e);
+    }
 
     // filter to get existing quiz
     let filter = doc! {
@@ -64,21 +72,40 @@ pub async fn handler(
     if let Some(cta) = &body.intro {
         quiz_update_doc.insert("intro", cta);
     }
+    if let Some(passing_score) = body.passing_score {
+        quiz_update_doc.insert("passing_score", passing_score);
+    }
+    if let Some(max_attempts) = body.max_attempts {
+        quiz_update_doc.insert("max_attempts", max_attempts);
+    }
+    if let Some(cooldown) = body.cooldown {
+        quiz_update_doc.insert("cooldown", cooldown);
+    }
 
     // update quiz
     let update = doc! {
         "$set": quiz_update_doc
     };
     let options = FindOneAndUpdateOptions::default();
-    match quiz_collection
-        .find_one_and_update(filter, update, options)
-        .await
+    match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/quiz/update",
+            "quizzes",
+            body.quiz_id.into(),
+        ),
+        quiz_collection.find_one_and_update(filter, update, options),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("error updating task".to_string()),
     };
 
@@ -105,15 +132,25 @@ pub async fn handler(
         "id": &body.id,
     };
     let options = FindOneAndUpdateOptions::default();
-    return match tasks_collection
-        .find_one_and_update(task_filter, task_update, options)
-        .await
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/quiz/update",
+            "tasks",
+            body.id.into(),
+        ),
+        tasks_collection.find_one_and_update(task_filter, task_update, options),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("error updating task".to_string()),
     };
 }
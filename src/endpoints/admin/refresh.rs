@@ -0,0 +1,108 @@
+use crate::common::admin_session::{get_access_token, parse_refresh_token, revoke_admin_sessions};
+use crate::models::{AdminSessionDocument, LoginDetails};
+use crate::utils::{get_random_hex, hash_password, verify_password};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct RefreshQuery {
+    refresh_token: String,
+}
+
+#[route(post, "/admin/refresh")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<RefreshQuery>,
+) -> impl IntoResponse {
+    let Some((session_id, secret)) = parse_refresh_token(&body.refresh_token) else {
+        return (StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()).into_response();
+    };
+
+    let now = Utc::now().timestamp();
+    let sessions_collection = state.db.collection::<AdminSessionDocument>("admin_sessions");
+    let session = match sessions_collection
+        .find_one(doc! { "id": session_id }, None)
+        .await
+    {
+        Ok(Some(session)) if !session.revoked && session.expiry > now => session,
+        Ok(_) => {
+            return (StatusCode::UNAUTHORIZED, "Session expired or revoked".to_string())
+                .into_response()
+        }
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    if !verify_password(secret, &session.refresh_token_hash) {
+        // the refresh token already rotated being replayed means it leaked, kill the whole
+        // session, any other secret is only rejected so that guessing can't log admins out
+        let reused = session
+            .previous_refresh_token_hash
+            .as_deref()
+            .map_or(false, |previous| verify_password(secret, previous));
+        if reused {
+            if let Err(e) = revoke_admin_sessions(&state, doc! { "id": session_id }).await {
+                return get_error(e.to_string());
+            }
+        }
+        return (StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()).into_response();
+    }
+
+    let new_secret = get_random_hex(32);
+    let Ok(refresh_token_hash) = hash_password(&new_secret) else {
+        return get_error("Error creating refresh token".to_string());
+    };
+    // filtering on the previous hash makes concurrent refreshes with the same token fail
+    let filter = doc! { "id": session_id, "refresh_token_hash": &session.refresh_token_hash };
+    let update = doc! {
+        "$set": {
+            "refresh_token_hash": refresh_token_hash,
+            "previous_refresh_token_hash": &session.refresh_token_hash,
+            "last_used_at": now,
+        },
+    };
+    match sessions_collection
+        .find_one_and_update(filter, update, None)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string())
+                .into_response()
+        }
+        Err(e) => return get_error(e.to_string()),
+    }
+
+    // roles are read again so that changes apply without a new login
+    let login = match state
+        .db
+        .collection::<LoginDetails>("login_details")
+        .find_one(doc! { "user": &session.user }, None)
+        .await
+    {
+        Ok(Some(login)) => login,
+        Ok(None) => return get_error("User not found".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    match get_access_token(&state, &login, session_id) {
+        Ok(token) => (
+            StatusCode::OK,
+            Json(json!({
+                "token": token,
+                "refresh_token": format!("{}.{}", session_id, new_secret),
+            })),
+        )
+            .into_response(),
+        Err(_e) => get_error("Error creating token".to_string()),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::middleware::auth::{auth_middleware, roles_have_permission};
+use crate::models::{AdminSessionDocument, JWTClaims, Permission};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::options::FindOptions;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct GetSessionsQuery {
+    user: Option<String>,
+}
+
+#[route(get, "/admin/session/get_sessions", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JWTClaims>,
+    Query(query): Query<GetSessionsQuery>,
+) -> impl IntoResponse {
+    // admins can list their own sessions, user managers can list anyone's
+    let mut filter = doc! {
+        "revoked": false,
+        "expiry": { "$gt": Utc::now().timestamp() },
+    };
+    match &query.user {
+        Some(user) if *user == claims.sub => filter.insert("user", user),
+        Some(user) if roles_have_permission(&claims.roles, Permission::ManageUsers) => {
+            filter.insert("user", user)
+        }
+        Some(_) => return get_error("Operation not allowed with your account".to_string()),
+        None if roles_have_permission(&claims.roles, Permission::ManageUsers) => None,
+        None => filter.insert("user", &claims.sub),
+    };
+
+    let options = FindOptions::builder().sort(doc! { "last_used_at": -1 }).build();
+    let collection = state.db.collection::<AdminSessionDocument>("admin_sessions");
+    match collection.find(filter, options).await {
+        Ok(mut cursor) => {
+            let mut sessions = vec![];
+            while let Ok(Some(session)) = cursor.try_next().await {
+                sessions.push(json!({
+                    "id": session.id,
+                    "user": session.user,
+                    "created_at": session.created_at,
+                    "last_used_at": session.last_used_at,
+                    "expiry": session.expiry,
+                    "current": session.id == claims.jti,
+                }));
+            }
+            (StatusCode::OK, Json(sessions)).into_response()
+        }
+        Err(_e) => get_error("Error querying sessions".to_string()),
+    }
+}
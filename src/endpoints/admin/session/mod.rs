@@ -0,0 +1,2 @@
+pub mod get_sessions;
+pub mod revoke_session;
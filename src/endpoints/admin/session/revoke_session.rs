@@ -0,0 +1,55 @@
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::common::admin_session::revoke_admin_sessions;
+use crate::middleware::auth::{auth_middleware, roles_have_permission};
+use crate::models::{JWTClaims, Permission};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; RevokeSession {
+    id: String,
+});
+
+#[route(post, "/admin/session/revoke", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JWTClaims>,
+    body: Json<RevokeSession>,
+) -> impl IntoResponse {
+    let mut filter = doc! { "id": &body.id };
+    if !roles_have_permission(&claims.roles, Permission::ManageUsers) {
+        filter.insert("user", &claims.sub);
+    }
+
+    let revoke = async {
+        match revoke_admin_sessions(&state, filter).await {
+            Ok(0) => Err("Session not found".to_string()),
+            Ok(count) => Ok(count),
+            Err(_e) => Err("Error revoking session".to_string()),
+        }
+    };
+    let entry = AuditEntry::keyed(
+        AuditAction::Update,
+        &claims.sub,
+        "/admin/session/revoke",
+        "admin_sessions",
+        "id",
+        &body.id,
+    );
+    match with_admin_audit(&state, entry, revoke).await {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({"message": "Session revoked successfully"})),
+        )
+            .into_response(),
+        Err(e) => get_error(e),
+    }
+}
@@ -1,9 +1,10 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
 use crate::models::QuestTaskDocument;
 use crate::utils::verify_task_auth;
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
@@ -24,12 +25,12 @@ pub_struct!(Deserialize; UpdateTwitterRw {
 #[route(post, "/admin/tasks/twitter_rw/update", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::EditQuests>,
    Json(body): Json<UpdateTwitterRw>,
 ) -> impl IntoResponse {
     let collection = state.db.collection::<QuestTaskDocument>("tasks");
 
-    let res = verify_task_auth(sub, &collection, &body.id).await;
+    let res = verify_task_auth(&admin, &collection, &body.id).await;
     if !res {
         return get_error("Error updating tasks".to_string());
     }
@@ -64,15 +65,25 @@ pub async fn handler(
     };
     let options = FindOneAndUpdateOptions::default();
 
-    return match collection
-        .find_one_and_update(filter, update, options)
-        .await
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/twitter_rw/update",
+            "tasks",
+            body.id.into(),
+        ),
+        collection.find_one_and_update(filter, update, options),
+    )
+    .await
     {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({"message": "updated successfully"})),
-        )
-            .into_response(),
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "updated successfully"})),
+            )
+                .into_response()
+        }
         Err(_e) => get_error("error updating task".to_string()),
     };
 }
@@ -1,14 +1,14 @@
-use crate::models::LoginDetails;
-use crate::utils::calculate_hash;
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::models::{AdminRole, LoginDetails};
+use crate::utils::{hash_password, is_duplicate_key_error};
 use crate::{models::AppState, utils::get_error};
-use crate::middleware::auth::auth_middleware;
+use crate::middleware::auth::{auth_middleware, require, Admin};
 use axum::{
-    extract::{Extension, State},
+    extract::State,
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use axum_auto_routes::route;
-use mongodb::bson::doc;
 use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
@@ -16,33 +16,47 @@ use std::sync::Arc;
 pub_struct!(Deserialize; CreateCustom {
     user: String,
     password: String,
+    roles: Option<Vec<AdminRole>>,
 });
 
 #[route(post, "/admin/user/create", auth_middleware)]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Extension(sub): Extension<String>,
+    admin: Admin<require::ManageUsers>,
     body: Json<CreateCustom>,
 ) -> impl IntoResponse {
-    if sub != "super_user" {
-        return get_error("Operation not allowed with your account".to_string());
-    };
-
     let collection = state.db.collection::<LoginDetails>("login_details");
-    let hashed_password = calculate_hash(&body.password);
+    let Ok(password_hash) = hash_password(&body.password) else {
+        return get_error("Error hashing password".to_string());
+    };
 
     let new_document = LoginDetails {
         user: body.user.clone(),
-        code: hashed_password.to_string(),
+        code: None,
+        password_hash: Some(password_hash),
+        roles: Some(
+            body.roles
+                .clone()
+                .unwrap_or_else(|| vec![AdminRole::Issuer]),
+        ),
     };
 
-    // insert document to boost collection
-    return match collection.insert_one(new_document, None).await {
+    // the unique index on user rejects a concurrent creation of the same user
+    let entry = AuditEntry::keyed(
+        AuditAction::Create,
+        &admin.sub,
+        "/admin/user/create",
+        "login_details",
+        "user",
+        &body.user,
+    );
+    return match with_admin_audit(&state, entry, collection.insert_one(new_document, None)).await {
         Ok(_) => (
             StatusCode::OK,
             Json(json!({"message": "User added successfully"})).into_response(),
         )
             .into_response(),
+        Err(e) if is_duplicate_key_error(&e) => get_error("User already exists".to_string()),
         Err(_e) => get_error("Error creating user".to_string()),
     };
 }
@@ -0,0 +1,33 @@
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::LoginDetails;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use serde_json::json;
+use std::sync::Arc;
+
+#[route(get, "/admin/user/get_users", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    _admin: Admin<require::ManageUsers>,
+) -> impl IntoResponse {
+    let collection = state.db.collection::<LoginDetails>("login_details");
+    match collection.find(None, None).await {
+        Ok(mut cursor) => {
+            let mut users = vec![];
+            while let Ok(Some(login)) = cursor.try_next().await {
+                users.push(json!({
+                    "roles": login.get_roles(),
+                    "user": login.user,
+                }));
+            }
+            (StatusCode::OK, Json(users)).into_response()
+        }
+        Err(_e) => get_error("Error querying users".to_string()),
+    }
+}
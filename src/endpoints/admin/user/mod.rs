@@ -1 +1,5 @@
 pub mod create_user;
+pub mod get_users;
+pub mod reset_password;
+pub mod update_password;
+pub mod update_roles;
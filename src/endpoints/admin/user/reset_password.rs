@@ -0,0 +1,67 @@
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::common::admin_session::revoke_admin_sessions;
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::LoginDetails;
+use crate::utils::hash_password;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; ResetPassword {
+    user: String,
+    password: String,
+});
+
+#[route(post, "/admin/user/reset_password", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ManageUsers>,
+    body: Json<ResetPassword>,
+) -> impl IntoResponse {
+    let Ok(password_hash) = hash_password(&body.password) else {
+        return get_error("Error hashing password".to_string());
+    };
+
+    let collection = state.db.collection::<LoginDetails>("login_details");
+    let update = doc! {
+        "$set": { "password_hash": password_hash },
+        "$unset": { "code": "" },
+    };
+    let entry = AuditEntry::keyed(
+        AuditAction::Update,
+        &admin.sub,
+        "/admin/user/reset_password",
+        "login_details",
+        "user",
+        &body.user,
+    );
+    match with_admin_audit(
+        &state,
+        entry,
+        collection.update_many(doc! { "user": &body.user }, update, None),
+    )
+    .await
+    {
+        Ok(result) if result.matched_count == 0 => get_error("User not found".to_string()),
+        Ok(_) => {
+            // sessions opened with the old password must not survive the reset
+            if let Err(e) = revoke_admin_sessions(&state, doc! { "user": &body.user }).await {
+                return get_error(e.to_string());
+            }
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Password reset successfully"})),
+            )
+                .into_response()
+        }
+        Err(_e) => get_error("Error resetting password".to_string()),
+    }
+}
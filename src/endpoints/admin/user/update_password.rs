@@ -0,0 +1,77 @@
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::common::admin_session::revoke_admin_sessions;
+use crate::middleware::auth::auth_middleware;
+use crate::models::{JWTClaims, LoginDetails};
+use crate::utils::{hash_password, verify_login};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; UpdatePassword {
+    old_password: String,
+    new_password: String,
+});
+
+#[route(post, "/admin/user/update_password", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<JWTClaims>,
+    body: Json<UpdatePassword>,
+) -> impl IntoResponse {
+    let sub = &claims.sub;
+    let collection = state.db.collection::<LoginDetails>("login_details");
+    let login = match collection.find_one(doc! { "user": sub }, None).await {
+        Ok(Some(login)) => login,
+        Ok(None) => return get_error("User not found".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    if !verify_login(&login, &body.old_password) {
+        return get_error("Incorrect Password".to_string());
+    }
+
+    let Ok(password_hash) = hash_password(&body.new_password) else {
+        return get_error("Error hashing password".to_string());
+    };
+    let update = doc! {
+        "$set": { "password_hash": password_hash },
+        "$unset": { "code": "" },
+    };
+    let entry = AuditEntry::keyed(
+        AuditAction::Update,
+        sub,
+        "/admin/user/update_password",
+        "login_details",
+        "user",
+        sub,
+    );
+    match with_admin_audit(
+        &state,
+        entry,
+        collection.update_one(doc! { "user": sub }, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            // the session changing the password stays open, the others are closed
+            let filter = doc! { "user": sub, "id": { "$ne": &claims.jti } };
+            if let Err(e) = revoke_admin_sessions(&state, filter).await {
+                return get_error(e.to_string());
+            }
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Password updated successfully"})),
+            )
+                .into_response()
+        }
+        Err(_e) => get_error("Error updating password".to_string()),
+    }
+}
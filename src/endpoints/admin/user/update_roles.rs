@@ -0,0 +1,60 @@
+use crate::common::admin_audit::{with_admin_audit, AuditAction, AuditEntry};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use crate::models::{AdminRole, LoginDetails};
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, to_bson};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; UpdateRoles {
+    user: String,
+    roles: Vec<AdminRole>,
+});
+
+#[route(post, "/admin/user/update_roles", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::ManageUsers>,
+    body: Json<UpdateRoles>,
+) -> impl IntoResponse {
+    let Ok(roles) = to_bson(&body.roles) else {
+        return get_error("Invalid roles".to_string());
+    };
+
+    // roles are read at login, the user needs to log in again to get them
+    let collection = state.db.collection::<LoginDetails>("login_details");
+    let entry = AuditEntry::keyed(
+        AuditAction::Update,
+        &admin.sub,
+        "/admin/user/update_roles",
+        "login_details",
+        "user",
+        &body.user,
+    );
+    match with_admin_audit(
+        &state,
+        entry,
+        collection.update_many(
+            doc! { "user": &body.user },
+            doc! { "$set": { "roles": roles } },
+            None,
+        ),
+    )
+    .await
+    {
+        Ok(result) if result.matched_count == 0 => get_error("User not found".to_string()),
+        Ok(_) => (
+            StatusCode::OK,
+            Json(json!({"message": "Roles updated successfully"})),
+        )
+            .into_response(),
+        Err(_e) => get_error("Error updating roles".to_string()),
+    }
+}
@@ -0,0 +1,88 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{QuestDocument, QuestTaskDocument, TaskVerifier};
+use crate::utils::{get_next_id, verify_quest_auth};
+use crate::{models::AppState, utils::get_error};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; CreateVerifierTask {
+    quest_id: i64,
+    name: String,
+    desc: String,
+    cta: String,
+    href: String,
+    verifier: TaskVerifier,
+});
+
+#[route(post, "/admin/tasks/verifier/create", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::EditQuests>,
+    Json(body): Json<CreateVerifierTask>,
+) -> impl IntoResponse {
+    let collection = state.db.collection::<QuestTaskDocument>("tasks");
+    let quests_collection = state.db.collection::<QuestDocument>("quests");
+
+    let res = verify_quest_auth(&admin, &quests_collection, &body.quest_id).await;
+    if !res {
+        return get_error("Error creating task".to_string());
+    };
+
+    if let Err(e) = body.verifier.validate() {
+        return get_error(e);
+    }
+
+    let next_id = match get_next_id(&state.db, "tasks").await {
+        Ok(id) => id as i32,
+        Err(e) => return get_error(e),
+    };
+
+    let new_document = QuestTaskDocument {
+        name: body.name.clone(),
+        desc: body.desc.clone(),
+        verify_redirect: Some(body.href.clone()),
+        href: body.href.clone(),
+        quest_id: body.quest_id,
+        total_amount: None,
+        id: next_id,
+        cta: body.cta.clone(),
+        verify_endpoint: "/quests/verify".to_string(),
+        verify_endpoint_type: "default".to_string(),
+        task_type: Some("verifier".to_string()),
+        discord_guild_id: None,
+        discord_role_ids: None,
+        discord_min_account_age: None,
+        quiz_name: None,
+        contracts: None,
+        api_url: None,
+        regex: None,
+        verifier: Some(body.verifier),
+        custom_api: None,
+    };
+
+    return match with_admin_audit(
+        &state,
+        AuditEntry::create(
+            &admin.sub,
+            "/admin/tasks/verifier/create",
+            "tasks",
+            next_id.into(),
+        ),
+        collection.insert_one(new_document, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (StatusCode::OK, Json(json!({"id": next_id}))).into_response()
+        }
+        Err(_e) => get_error("Error creating task".to_string()),
+    };
+}
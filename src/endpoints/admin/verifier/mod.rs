@@ -0,0 +1,2 @@
+pub mod create_verifier;
+pub mod update_verifier;
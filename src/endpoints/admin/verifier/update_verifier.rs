@@ -0,0 +1,96 @@
+use crate::common::admin_audit::{with_admin_audit, AuditEntry};
+use crate::models::{QuestTaskDocument, TaskVerifier};
+use crate::utils::verify_task_auth;
+use crate::{models::AppState, utils::get_error};
+use crate::middleware::auth::{auth_middleware, require, Admin};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, to_bson};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; UpdateVerifierTask {
+    id: i32,
+    name: Option<String>,
+    desc: Option<String>,
+    cta: Option<String>,
+    href: Option<String>,
+    verifier: Option<TaskVerifier>,
+});
+
+#[route(post, "/admin/tasks/verifier/update", auth_middleware)]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    admin: Admin<require::EditQuests>,
+    Json(body): Json<UpdateVerifierTask>,
+) -> impl IntoResponse {
+    let collection = state.db.collection::<QuestTaskDocument>("tasks");
+
+    let res = verify_task_auth(&admin, &collection, &body.id).await;
+    if !res {
+        return get_error("Error updating tasks".to_string());
+    }
+
+    let filter = doc! {
+        "id": &body.id,
+    };
+
+    let mut update_doc = doc! {};
+
+    if let Some(name) = &body.name {
+        update_doc.insert("name", name);
+    }
+    if let Some(desc) = &body.desc {
+        update_doc.insert("desc", desc);
+    }
+    if let Some(cta) = &body.cta {
+        update_doc.insert("cta", cta);
+    }
+    if let Some(href) = &body.href {
+        update_doc.insert("href", href);
+        update_doc.insert("verify_redirect", href);
+    }
+    if let Some(verifier) = &body.verifier {
+        if let Err(e) = verifier.validate() {
+            return get_error(e);
+        }
+        match to_bson(verifier) {
+            Ok(verifier) => update_doc.insert("verifier", verifier),
+            Err(_e) => return get_error("Invalid verifier".to_string()),
+        };
+        // tasks of legacy protocol handlers move to the generic endpoint
+        update_doc.insert("verify_endpoint", "/quests/verify");
+        update_doc.insert("verify_endpoint_type", "default");
+    }
+
+    let update = doc! {
+        "$set": update_doc
+    };
+
+    return match with_admin_audit(
+        &state,
+        AuditEntry::update(
+            &admin.sub,
+            "/admin/tasks/verifier/update",
+            "tasks",
+            body.id.into(),
+        ),
+        collection.find_one_and_update(filter, update, None),
+    )
+    .await
+    {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({"message": "Task updated successfully"})).into_response(),
+            )
+                .into_response()
+        }
+        Err(_e) => get_error("Error updating task".to_string()),
+    };
+}
@@ -12,9 +12,9 @@ use std::sync::Arc;
 
 #[route(get, "/discover/defi/get_lend_stats")]
 pub async fn handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let endpoint = &state.conf.discover.lending_api_endpoint;
+    let endpoint = state.get_reloadable_conf().discover.lending_api_endpoint.clone();
     let client = reqwest::Client::new();
-    let request_builder = client.get(endpoint);
+    let request_builder = client.get(&endpoint);
 
     let mut new_map = HashMap::new();
     match request_builder.send().await {
@@ -1,4 +1,4 @@
-use crate::{models::AppState, utils::get_error};
+use crate::{common::quiz_order::shuffle_quiz, models::AppState, utils::get_error};
 use axum::{
     extract::{Query, State},
     http::StatusCode,
@@ -14,9 +14,6 @@ use std::sync::Arc;
 #[derive(Deserialize)]
 pub struct GetQuizQuery {
     id: i64,
-    // addr could be used as entropy for sending a server side randomized order
-    // let's keep on client side for now
-    #[allow(dead_code)]
     addr: FieldElement,
 }
 
@@ -44,9 +41,19 @@ pub async fn handler(
                             "quiz_id": &query.id
                         }
                     },
+                    doc! {
+                        "$sort": doc! {
+                            "id": 1
+                        }
+                    },
                     doc! {
                         "$project": doc! {
                             "correct_answers": 0,
+                            "correct_order": 0,
+                            "correct_value": 0,
+                            "tolerance": 0,
+                            "accepted_answers": 0,
+                            "answer_regex": 0,
                             "quiz_id": 0,
                             "_id": 0
                         }
@@ -66,7 +73,14 @@ pub async fn handler(
         Ok(mut cursor) => {
             while let Some(result) = cursor.next().await {
                 match result {
-                    Ok(document) => {
+                    Ok(mut document) => {
+                        // questions and options are served in an order specific to addr
+                        shuffle_quiz(
+                            &mut document,
+                            &state.conf.session.quiz_order_secret,
+                            query.addr,
+                            query.id,
+                        );
                         return (StatusCode::OK, Json(document)).into_response();
                     }
                     Err(e) => {
@@ -0,0 +1,31 @@
+use crate::{models::AppState, utils::get_error, utils::to_hex};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde_json::json;
+use std::sync::Arc;
+
+// Public keys of the active reward signers, the contracts must be updated with them
+// whenever a key is rotated
+#[route(get, "/get_signer_keys")]
+pub async fn handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let nft = match state.nft_signer.public_key().await {
+        Ok(public_key) => public_key,
+        Err(e) => return get_error(format!("Error getting nft public key: {}", e)),
+    };
+    let boost = match state.boost_signer.public_key().await {
+        Ok(public_key) => public_key,
+        Err(e) => return get_error(format!("Error getting boost public key: {}", e)),
+    };
+    (
+        StatusCode::OK,
+        Json(json!({
+            "nft_contract": to_hex(nft),
+            "quest_boost": to_hex(boost),
+        })),
+    )
+        .into_response()
+}
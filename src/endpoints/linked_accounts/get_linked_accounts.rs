@@ -0,0 +1,47 @@
+use crate::middleware::session::VerifiedAddress;
+use crate::models::LinkedAccountDocument;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use serde_json::json;
+use std::sync::Arc;
+
+#[route(get, "/linked_accounts/get")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+) -> impl IntoResponse {
+    let cursor = match state
+        .db
+        .collection::<LinkedAccountDocument>("linked_accounts")
+        .find(doc! { "address": addr.to_string() }, None)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(e) => return get_error(e.to_string()),
+    };
+    let accounts: Vec<LinkedAccountDocument> = match cursor.try_collect().await {
+        Ok(accounts) => accounts,
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    // tokens never leave the server
+    let accounts: Vec<_> = accounts
+        .into_iter()
+        .map(|account| {
+            json!({
+                "platform": account.platform,
+                "account_id": account.account_id,
+                "username": account.username,
+                "linked_at": account.linked_at,
+            })
+        })
+        .collect();
+    (StatusCode::OK, Json(accounts)).into_response()
+}
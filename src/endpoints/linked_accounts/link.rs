@@ -0,0 +1,38 @@
+use crate::common::{discord, twitter};
+use crate::middleware::session::VerifiedAddress;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; LinkQuery {
+    platform: String,
+});
+
+// Returns the url to authorize, the account is linked by the platform callback
+#[route(get, "/linked_accounts/link")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<LinkQuery>,
+) -> impl IntoResponse {
+    let url = match query.platform.as_str() {
+        "discord" => {
+            discord::get_authorization_url(&state, addr, None, None, discord::CALLBACKS[0])
+                .await
+                .map(|(_, url)| url)
+        }
+        "twitter" => twitter::get_authorization_url(&state, addr, None, None).await,
+        _ => Err("Unsupported platform".to_string()),
+    };
+    match url {
+        Ok(url) => (StatusCode::OK, Json(json!({ "url": url }))).into_response(),
+        Err(e) => get_error(e),
+    }
+}
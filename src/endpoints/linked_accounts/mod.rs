@@ -0,0 +1,3 @@
+pub mod get_linked_accounts;
+pub mod link;
+pub mod unlink;
@@ -0,0 +1,45 @@
+use crate::common::linked_accounts::PLATFORMS;
+use crate::middleware::session::VerifiedAddress;
+use crate::models::LinkedAccountDocument;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; UnlinkBody {
+    platform: String,
+});
+
+#[route(post, "/linked_accounts/unlink")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Json(body): Json<UnlinkBody>,
+) -> impl IntoResponse {
+    if !PLATFORMS.contains(&body.platform.as_str()) {
+        return get_error("Unsupported platform".to_string());
+    }
+
+    match state
+        .db
+        .collection::<LinkedAccountDocument>("linked_accounts")
+        .delete_one(
+            doc! { "address": addr.to_string(), "platform": &body.platform },
+            None,
+        )
+        .await
+    {
+        Ok(res) if res.deleted_count > 0 => {
+            (StatusCode::OK, Json(json!({"res": true}))).into_response()
+        }
+        Ok(_) => get_error("No linked account for this platform".to_string()),
+        Err(e) => get_error(e.to_string()),
+    }
+}
@@ -10,10 +10,14 @@ pub mod get_quest_category;
 pub mod get_quest_participants;
 pub mod get_quests;
 pub mod get_quiz;
+pub mod get_signer_keys;
 pub mod get_tasks;
 pub mod get_trending_quests;
 pub mod has_completed_quest;
 pub mod leaderboard;
+pub mod linked_accounts;
+pub mod partners;
 pub mod quest_boost;
 pub mod quests;
+pub mod session;
 pub mod unique_page_visit;
@@ -0,0 +1,23 @@
+use crate::common::partner_webhook::handle_webhook;
+use crate::models::AppState;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde_json::json;
+use std::sync::Arc;
+
+// Lets partners push task completions, see common::partner_webhook for the signature
+#[route(post, "/partners/webhook")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> impl IntoResponse {
+    match handle_webhook(&state, &headers, &body).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
+        Err((status, e)) => (status, Json(json!({"error": e}))).into_response(),
+    }
+}
@@ -7,13 +7,12 @@ use axum::{
 use axum_auto_routes::route;
 use std::str::FromStr;
 
-use crate::utils::to_hex;
+use crate::utils::{get_boost_claim_hash, to_hex};
 use mongodb::bson::{doc, Bson, Document};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use starknet::core::crypto::ecdsa_sign;
-use starknet::core::{crypto::pedersen_hash, types::FieldElement};
+use starknet::core::types::FieldElement;
 use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,21 +58,14 @@ pub async fn handler(
         ));
     }
 
-    let hashed = pedersen_hash(
-        &FieldElement::from(boost_id),
-        &pedersen_hash(
-            &FieldElement::from(modified_amount),
-            &pedersen_hash(
-                &FieldElement::from(0 as u32),
-                &pedersen_hash(
-                    &FieldElement::from_str(token).unwrap(),
-                    &FieldElement::from_str(&*address).unwrap(),
-                ),
-            ),
-        ),
+    let hashed = get_boost_claim_hash(
+        boost_id,
+        modified_amount,
+        FieldElement::from_str(token).unwrap(),
+        FieldElement::from_str(&*address).unwrap(),
     );
 
-    match ecdsa_sign(&state.conf.quest_boost.private_key, &hashed) {
+    match state.boost_signer.sign(&hashed).await {
         Ok(signature) => (
             StatusCode::OK,
             Json(json!({"address": address, "r": signature.r, "s": signature.s})),
@@ -0,0 +1,124 @@
+use crate::common::boost_raffle::{
+    draw_winners, get_boost_quests, get_eligible, get_eligible_hash, get_saved_eligible,
+    verify_seed_block, DRAW_ALGORITHM_VERSION,
+};
+use crate::models::{AppState, BoostDraw};
+use crate::utils::{bson_as_i64, get_error, to_hex};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use mongodb::bson::{doc, from_document, Document};
+use serde::Deserialize;
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct VerifyDrawQuery {
+    boost_id: u32,
+}
+
+// Recomputes the draw of a boost from public data: the seed from its block, the
+// eligible list saved at draw time (or the completed tasks for older draws) and the
+// winners from both
+#[route(get, "/boost/verify_draw")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VerifyDrawQuery>,
+) -> impl IntoResponse {
+    let boost = match state
+        .db
+        .collection::<Document>("boosts")
+        .find_one(doc! { "id": query.boost_id }, None)
+        .await
+    {
+        Ok(Some(boost)) => boost,
+        Ok(None) => return get_error(format!("Boost with id {} not found", query.boost_id)),
+        Err(_) => return get_error("Error querying boost".to_string()),
+    };
+    let Some(draw) = boost
+        .get_document("draw")
+        .ok()
+        .and_then(|draw| from_document::<BoostDraw>(draw.clone()).ok())
+    else {
+        return get_error(format!(
+            "Boost with id {} has no verifiable draw",
+            query.boost_id
+        ));
+    };
+    if draw.algorithm_version != DRAW_ALGORITHM_VERSION {
+        return get_error(format!(
+            "Unsupported draw algorithm version {}",
+            draw.algorithm_version
+        ));
+    }
+    let (Some(expiry), Ok(seed)) = (
+        boost.get("expiry").and_then(bson_as_i64),
+        FieldElement::from_hex_be(&draw.seed),
+    ) else {
+        return get_error("Invalid boost draw".to_string());
+    };
+    let num_of_winners = boost
+        .get("num_of_winners")
+        .and_then(bson_as_i64)
+        .unwrap_or(0)
+        .max(0) as usize;
+    let winners: Vec<String> = boost
+        .get_array("winner")
+        .map(|winners| {
+            winners
+                .iter()
+                .filter_map(|winner| winner.as_str().map(|winner| winner.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // the saved seed block is checked rather than searched again, which would take a
+    // few dozen RPC calls on every request
+    let seed_valid = match verify_seed_block(&state, expiry, draw.seed_block, seed).await {
+        Ok(seed_valid) => seed_valid,
+        Err(e) => return get_error(format!("Error fetching the seed block: {}", e)),
+    };
+    let saved = match get_saved_eligible(&state, query.boost_id.into()).await {
+        Ok(saved) => saved,
+        Err(_) => return get_error("Error querying eligible addresses".to_string()),
+    };
+    let eligible_saved = saved.is_some();
+    let eligible = match saved {
+        Some(eligible) => eligible,
+        None => match get_eligible(&state, &get_boost_quests(&boost), expiry).await {
+            Ok(eligible) => eligible,
+            Err(_) => return get_error("Error querying eligible addresses".to_string()),
+        },
+    };
+    let eligible_hash = get_eligible_hash(&eligible);
+    let eligible_valid = eligible_hash == draw.eligible_hash;
+    let expected_winners: Vec<String> = draw_winners(&eligible, seed, num_of_winners)
+        .into_iter()
+        .map(to_hex)
+        .collect();
+    let winners_valid = expected_winners == winners;
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "boost_id": query.boost_id,
+            "algorithm_version": draw.algorithm_version,
+            "seed": draw.seed,
+            "seed_block": draw.seed_block,
+            "seed_valid": seed_valid,
+            "eligible_hash": eligible_hash,
+            "eligible_valid": eligible_valid,
+            "eligible_saved": eligible_saved,
+            "eligible": eligible.into_iter().map(to_hex).collect::<Vec<String>>(),
+            "winners": winners,
+            "expected_winners": expected_winners,
+            "winners_valid": winners_valid,
+            "verified": seed_valid && eligible_valid && winners_valid,
+        })),
+    )
+        .into_response()
+}
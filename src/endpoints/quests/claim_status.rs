@@ -0,0 +1,72 @@
+use crate::common::claim_rewards::is_claim_expired;
+use crate::models::{AppState, RewardClaimDocument};
+use crate::utils::get_error;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::FindOptions};
+use serde::Deserialize;
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ClaimStatusQuery {
+    addr: FieldElement,
+    quest_id: Option<u32>,
+}
+
+// Reward levels signed for an address, and whether they were minted or expired
+#[route(get, "/quests/claim_status")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClaimStatusQuery>,
+) -> impl IntoResponse {
+    let mut filter = doc! { "address": query.addr.to_string() };
+    if let Some(quest_id) = query.quest_id {
+        filter.insert("quest_id", quest_id);
+    }
+    let options = FindOptions::builder()
+        .sort(doc! { "quest_id": 1, "level": 1 })
+        .build();
+    let claims = match state
+        .db
+        .collection::<RewardClaimDocument>("reward_claims")
+        .find(filter, options)
+        .await
+    {
+        Ok(cursor) => cursor.try_collect::<Vec<RewardClaimDocument>>().await,
+        Err(e) => Err(e),
+    };
+    match claims {
+        Ok(claims) => {
+            let now = Utc::now().timestamp_millis();
+            let claims: Vec<_> = claims
+                .into_iter()
+                .map(|claim| {
+                    let status = match is_claim_expired(&claim, now) {
+                        true => json!("expired"),
+                        false => json!(claim.status),
+                    };
+                    json!({
+                        "quest_id": claim.quest_id,
+                        "level": claim.level,
+                        "token_id": claim.token_id,
+                        "status": status,
+                        "expiry": claim.expiry,
+                        "minted_at": claim.minted_at,
+                        "mint_transaction": claim.mint_transaction,
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({ "claims": claims }))).into_response()
+        }
+        Err(_) => get_error("Error querying claims".to_string()),
+    }
+}
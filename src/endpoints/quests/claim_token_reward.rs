@@ -0,0 +1,44 @@
+use crate::common::quest_rewards::get_token_reward_claim;
+use crate::middleware::session::VerifiedAddress;
+use crate::models::AppState;
+use crate::utils::get_error;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ClaimTokenRewardQuery {
+    quest_id: u32,
+    claim_id: u32,
+}
+
+// Parameters of the claim call of the boost contract for a token reward of a quest
+#[route(get, "/quests/claim_token_reward")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<ClaimTokenRewardQuery>,
+) -> impl IntoResponse {
+    match get_token_reward_claim(&state, &addr, query.quest_id, query.claim_id).await {
+        Ok(claim) => (
+            StatusCode::OK,
+            Json(json!({
+                "claim_id": claim.claim_id,
+                "token": claim.token,
+                "amount": claim.amount,
+                "address": claim.address,
+                "r": claim.sig.0,
+                "s": claim.sig.1,
+            })),
+        )
+            .into_response(),
+        Err(e) => get_error(e),
+    }
+}
@@ -1,183 +1,70 @@
-use crate::{models::AppState, utils::get_error};
+use crate::common::claim_rewards::get_claimable_rewards;
+use crate::middleware::session::VerifiedAddress;
+use crate::models::{AppState, RewardResponse};
+use crate::utils::get_error;
 use axum::{
-    extract::{Query, State},
-    response::IntoResponse,
-    Json,
+    extract::{MatchedPath, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
 };
-
-use crate::models::{Reward, RewardResponse};
-use crate::utils::get_nft;
 use axum_auto_routes::route;
-use futures::TryStreamExt;
-use mongodb::bson::{doc, Document};
-use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use starknet::core::types::FieldElement;
-use starknet::signers::{LocalWallet, SigningKey};
 use std::sync::Arc;
 
+// Routes of the quests which had their own claimable module, kept for old clients
+const LEGACY_ROUTES: [(&str, u32); 7] = [
+    ("/quests/starknetid/claimable", 1),
+    ("/quests/ekubo/claimable", 9),
+    ("/quests/starknet/gigabrain/claimable", 13),
+    ("/quests/starknet/aa_mastery/claimable", 14),
+    ("/quests/focustree/claimable", 15),
+    ("/quests/nostra/claimable", 20),
+    ("/quests/nostra/staking_quest/claimable", 27),
+];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HasCompletedQuestsQuery {
-    addr: FieldElement,
     quest_id: u32,
 }
 
+async fn get_claimable_response(state: &AppState, addr: FieldElement, quest_id: u32) -> Response {
+    match get_claimable_rewards(state, &addr, quest_id).await {
+        Ok(rewards) => (StatusCode::OK, Json(RewardResponse { rewards })).into_response(),
+        Err(e) => get_error(e),
+    }
+}
+
 #[route(get, "/quests/claimable")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<HasCompletedQuestsQuery>,
 ) -> impl IntoResponse {
-    let address = query.addr.to_string();
-    let quest_id = query.quest_id;
-    let pipeline = vec![
-        doc! {
-            "$match": doc! {
-                "address": address
-            }
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "tasks",
-                "localField": "task_id",
-                "foreignField": "id",
-                "as": "associatedTask"
-            }
-        },
-        doc! {
-            "$unwind": "$associatedTask"
-        },
-        doc! {
-            "$project": doc! {
-                "_id": 0,
-                "address": 1,
-                "task_id": 1,
-                "quest_id": "$associatedTask.quest_id"
-            }
-        },
-        doc! {
-            "$group": doc! {
-                "_id": "$quest_id",
-                "done": doc! {
-                    "$sum": 1
-                }
-            }
-        },
-        doc! {
-            "$match": doc! {
-                "_id": quest_id
-            }
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "tasks",
-                "localField": "_id",
-                "foreignField": "quest_id",
-                "as": "tasks"
-            }
-        },
-        doc! {
-            "$project": doc! {
-                "_id": 1,
-                "tasks": "$tasks",
-                "result": doc! {
-                    "$cond": doc! {
-                        "if": doc! {
-                            "$eq": [
-                                doc! {
-                                    "$size": "$tasks"
-                                },
-                                "$done"
-                            ]
-                        },
-                        "then": true,
-                        "else": false
-                    }
-                }
-            }
-        },
-        doc! {
-            "$unwind": doc! {
-                "path": "$tasks"
-            }
-        },
-        doc! {
-            "$sort": doc! {
-                "tasks.id": -1
-            }
-        },
-        doc! {
-            "$limit": 1
-        },
-        doc! {
-            "$lookup": doc! {
-                "from": "nft_uri",
-                "localField": "_id",
-                "foreignField": "quest_id",
-                "as": "nft_uri"
-            }
-        },
-        doc! {
-            "$project": doc! {
-                "result": 1,
-                "_id": 0,
-                "last_task": "$tasks.id",
-                "nft_level": "$nft_uri.id"
-            }
-        },
-        doc! {
-            "$unwind": doc! {
-                "path": "$nft_level"
-            }
-        },
-    ];
-    let tasks_collection = state.db.collection::<Document>("completed_tasks");
-    match tasks_collection.aggregate(pipeline, None).await {
-        Ok(cursor) => {
-            let mut cursor = cursor;
-            let mut result = false;
-            let mut nft_level = 0;
-            let mut last_task = 0;
-            while let Some(doc) = cursor.try_next().await.unwrap() {
-                result = doc.get("result").unwrap().as_bool().unwrap();
-                nft_level = doc.get("nft_level").unwrap().as_i64().unwrap();
-                last_task = doc.get("last_task").unwrap().as_i32().unwrap();
-            }
-
-            if !result {
-                return get_error("User hasn't completed all tasks".to_string());
-            }
-
-            let signer = LocalWallet::from(SigningKey::from_secret_scalar(
-                state.conf.nft_contract.private_key,
-            ));
-
-            let mut rewards = vec![];
-
-            let Ok((token_id, sig)) = get_nft(
-                quest_id,
-                last_task as u32,
-                &query.addr,
-                nft_level as u32,
-                &signer,
-            )
-            .await
-            else {
-                return get_error("Signature failed".into());
-            };
-
-            rewards.push(Reward {
-                task_id: last_task as u32,
-                nft_contract: state.conf.nft_contract.address.clone(),
-                token_id: token_id.to_string(),
-                sig: (sig.r, sig.s),
-            });
+    get_claimable_response(&state, addr, query.quest_id).await
+}
 
-            if rewards.is_empty() {
-                get_error("No rewards found for this user".into())
-            } else {
-                (StatusCode::OK, Json(RewardResponse { rewards })).into_response()
-            }
-        }
-        Err(_) => get_error("Error querying status".to_string()),
+async fn legacy_handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    path: MatchedPath,
+) -> impl IntoResponse {
+    match LEGACY_ROUTES
+        .iter()
+        .find(|(route, _)| *route == path.as_str())
+    {
+        Some((_, quest_id)) => get_claimable_response(&state, addr, *quest_id).await,
+        None => get_error("Quest not found".to_string()),
     }
 }
+
+// The legacy routes all share one handler, they can't be declared with #[route]
+pub fn get_legacy_router() -> Router<Arc<AppState>> {
+    LEGACY_ROUTES
+        .iter()
+        .fold(Router::new(), |router, (route, _)| {
+            router.route(route, get(legacy_handler))
+        })
+}
@@ -0,0 +1,43 @@
+use crate::common::discord::{get_authorization_url, CALLBACKS};
+use crate::middleware::session::VerifiedAddress;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; DiscordOAuthQuery {
+    quest_id: i64,
+    task_id: u32,
+    callback: Option<String>,
+});
+
+#[route(get, "/quests/discord_oauth")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<DiscordOAuthQuery>,
+) -> impl IntoResponse {
+    let callback = query.callback.as_deref().unwrap_or(CALLBACKS[0]);
+    match get_authorization_url(
+        &state,
+        addr,
+        Some(query.quest_id),
+        Some(query.task_id),
+        callback,
+    )
+    .await
+    {
+        Ok((oauth_state, url)) => (
+            StatusCode::OK,
+            Json(json!({ "state": oauth_state, "url": url })),
+        )
+            .into_response(),
+        Err(e) => get_error(e),
+    }
+}
@@ -1,55 +1,18 @@
 use std::sync::Arc;
 
 use crate::{
-    models::{AppState, VerifyQuery},
-    utils::{get_error, CompletedTasksTrait},
-};
-use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
+    common::verify_task::{verify_legacy_task, LegacyTask},
+    middleware::session::VerifiedAddress,
+    models::AppState,
 };
+use axum::{extract::State, response::IntoResponse};
 use axum_auto_routes::route;
-use serde_json::json;
-use starknet::{
-    core::types::{BlockId, BlockTag, FieldElement, FunctionCall},
-    macros::selector,
-    providers::Provider,
-};
 
 #[route(get, "/quests/ekubo/verify_added_liquidity")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 38;
-    let addr = &query.addr;
-
-    // check if user has provider liquidity
-    let call_result = state
-        .provider
-        .call(
-            FunctionCall {
-                contract_address: state.conf.quests.ekubo.contract,
-                entry_point_selector: selector!("balanceOf"),
-                calldata: vec![*addr],
-            },
-            BlockId::Tag(BlockTag::Latest),
-        )
-        .await;
-
-    match call_result {
-        Ok(result) => {
-            if result[0] == FieldElement::ZERO {
-                get_error("You didn't provided any liquidity on Ekubo.".to_string())
-            } else {
-                match state.upsert_completed_task(query.addr, task_id).await {
-                    Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
-                }
-            }
-        }
-        Err(e) => get_error(format!("{}", e)),
-    }
+    verify_legacy_task(&state, addr, task_id, LegacyTask::EkuboLiquidity).await
 }
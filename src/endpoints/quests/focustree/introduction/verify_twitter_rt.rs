@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -16,10 +17,10 @@ use serde_json::json;
 #[route(get, "/quests/focustree/verify_twitter_rt")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 63;
-    match state.upsert_completed_task(query.addr, task_id).await {
+    match state.upsert_completed_task(addr, task_id).await {
         Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
         Err(e) => get_error(format!("{}", e)),
     }
@@ -1,16 +1,23 @@
 pub mod carmine;
+pub mod claim_status;
+pub mod claim_token_reward;
 pub mod claimable;
 pub mod contract_uri;
 pub mod discord_fw_callback;
+pub mod discord_oauth;
 pub mod ekubo;
 pub mod focustree;
 pub mod nostra;
 pub mod proscore;
-pub mod starknet;
+pub mod rewards;
 pub mod starknetid;
+pub mod twitter_callback;
+pub mod twitter_oauth;
 pub mod uri;
+pub mod verify;
 pub mod verify_balance;
 pub mod verify_custom_api;
+pub mod verify_discord;
 pub mod verify_quiz;
 pub mod verify_twitter_fw;
 pub mod verify_twitter_rw;
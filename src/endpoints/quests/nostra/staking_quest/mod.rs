@@ -1,3 +1,2 @@
-pub mod claimable;
 pub mod verify_stake;
 pub mod verify_twitter_tw;
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -21,16 +22,16 @@ use starknet::{
 #[route(get, "/quests/nostra/staking_quest/verify_stake")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 133;
-    let addr = &query.addr;
-    let balance_calldata = vec![*addr];
+    let conf = state.get_reloadable_conf();
+    let balance_calldata = vec![addr];
     let balance_result = state
         .provider
         .call(
             FunctionCall {
-                contract_address: state.conf.quests.nostra.staking_contract,
+                contract_address: conf.quests.nostra.staking_contract,
                 entry_point_selector: selector!("balance_of"),
                 calldata: balance_calldata,
             },
@@ -51,7 +52,7 @@ pub async fn handler(
         .provider
         .call(
             FunctionCall {
-                contract_address: state.conf.quests.nostra.staking_contract,
+                contract_address: conf.quests.nostra.staking_contract,
                 entry_point_selector: selector!("convert_to_assets"),
                 calldata: balance_result.unwrap().to_vec(),
             },
@@ -64,7 +65,7 @@ pub async fn handler(
             if result[0] < FieldElement::from_dec_str("10").unwrap() {
                 get_error("You need to stake atleast 10 STRK".to_string())
             } else {
-                match state.upsert_completed_task(query.addr, task_id).await {
+                match state.upsert_completed_task(addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
                     Err(e) => get_error(format!("{}", e)),
                 }
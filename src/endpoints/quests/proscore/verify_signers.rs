@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -21,14 +22,14 @@ use starknet::{
 #[route(get, "/quests/proscore/verify_signers")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 192;
     match state
         .provider
         .call(
             FunctionCall {
-                contract_address: query.addr,
+                contract_address: addr,
                 entry_point_selector: selector!("get_signers"),
                 calldata: vec![],
             },
@@ -37,7 +38,7 @@ pub async fn handler(
         .await
     {
         Ok(result) => match parse_braavos_signers(&result) {
-            Ok(true) => match state.upsert_completed_task(query.addr, task_id).await {
+            Ok(true) => match state.upsert_completed_task(addr, task_id).await {
                 Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
                 Err(e) => get_error(format!("{}", e)),
             },
@@ -0,0 +1,164 @@
+use crate::common::quest_rewards::{
+    get_allocated_rewards, get_completed_quest_ids, get_quest_rewards, get_recipients_cap,
+};
+use crate::models::{
+    AppState, QuestDocument, QuestReward, RewardClaimDocument, TokenRewardClaimDocument,
+};
+use crate::utils::get_error;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use futures::TryStreamExt;
+use mongodb::{bson::doc, options::FindOptions};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct RewardsQuery {
+    addr: FieldElement,
+    quest_id: Option<u32>,
+}
+
+// Status of a token reward for a completer who wasn't issued a claim yet
+async fn get_token_status(
+    state: &AppState,
+    claim_id: u32,
+    amount: &str,
+    max_recipients: Option<u32>,
+    budget: Option<&str>,
+) -> &'static str {
+    let Ok(Some(cap)) = get_recipients_cap(amount, max_recipients, budget) else {
+        return "available";
+    };
+    match get_allocated_rewards(state, claim_id).await {
+        Ok(allocated) if allocated >= cap as i64 => "exhausted",
+        _ => "available",
+    }
+}
+
+// Rewards of the quests completed by an address (or of quest_id), with the claims issued.
+// Token claims are paid by the boost contract, whether they were sent on chain isn't known
+// here so they stay "issued".
+#[route(get, "/quests/rewards")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RewardsQuery>,
+) -> impl IntoResponse {
+    let completed = match get_completed_quest_ids(&state.db, &query.addr).await {
+        Ok(completed) => completed,
+        Err(_) => return get_error("Error querying quests".to_string()),
+    };
+    let quest_ids = match query.quest_id {
+        Some(quest_id) => vec![quest_id],
+        None => completed.clone(),
+    };
+    let address = query.addr.to_string();
+    let options = FindOptions::builder().sort(doc! { "id": 1 }).build();
+    let loaded = async {
+        let quests: Vec<QuestDocument> = state
+            .db
+            .collection::<QuestDocument>("quests")
+            .find(doc! { "id": { "$in": &quest_ids } }, options)
+            .await?
+            .try_collect()
+            .await?;
+        let nft_claims: Vec<RewardClaimDocument> = state
+            .db
+            .collection::<RewardClaimDocument>("reward_claims")
+            .find(
+                doc! { "address": &address, "quest_id": { "$in": &quest_ids } },
+                None,
+            )
+            .await?
+            .try_collect()
+            .await?;
+        let token_claims: Vec<TokenRewardClaimDocument> = state
+            .db
+            .collection::<TokenRewardClaimDocument>("token_reward_claims")
+            .find(
+                doc! { "address": &address, "quest_id": { "$in": &quest_ids } },
+                None,
+            )
+            .await?
+            .try_collect()
+            .await?;
+        Ok::<_, mongodb::error::Error>((quests, nft_claims, token_claims))
+    }
+    .await;
+    let Ok((quests, nft_claims, token_claims)) = loaded else {
+        return get_error("Error querying rewards".to_string());
+    };
+
+    let mut result = vec![];
+    for quest in quests {
+        let is_completed = completed.contains(&quest.id);
+        let mut rewards: Vec<Value> = vec![];
+        for reward in get_quest_rewards(&quest) {
+            match reward {
+                QuestReward::Nft => {
+                    let claims: Vec<Value> = nft_claims
+                        .iter()
+                        .filter(|claim| claim.quest_id == quest.id)
+                        .map(|claim| {
+                            json!({
+                                "level": claim.level,
+                                "token_id": claim.token_id,
+                                "status": claim.status,
+                                "minted_at": claim.minted_at,
+                            })
+                        })
+                        .collect();
+                    rewards
+                        .push(json!({ "type": "nft", "earned": is_completed, "claims": claims }));
+                }
+                QuestReward::Token {
+                    claim_id,
+                    token,
+                    amount,
+                    max_recipients,
+                    budget,
+                } => {
+                    let claim = token_claims.iter().find(|claim| claim.claim_id == claim_id);
+                    let status = match (claim, is_completed) {
+                        (Some(_), _) => "issued",
+                        (None, false) => "locked",
+                        (None, true) => {
+                            get_token_status(
+                                &state,
+                                claim_id,
+                                &amount,
+                                max_recipients,
+                                budget.as_deref(),
+                            )
+                            .await
+                        }
+                    };
+                    rewards.push(json!({
+                        "type": "token",
+                        "claim_id": claim_id,
+                        "token": token,
+                        "amount": amount,
+                        "status": status,
+                        "issued_at": claim.map(|claim| claim.created_at),
+                    }));
+                }
+                QuestReward::Xp { amount } => {
+                    rewards.push(json!({ "type": "xp", "amount": amount, "earned": is_completed }));
+                }
+            }
+        }
+        result.push(json!({
+            "quest_id": quest.id,
+            "name": quest.name,
+            "completed": is_completed,
+            "rewards": rewards,
+        }));
+    }
+    (StatusCode::OK, Json(json!({ "quests": result }))).into_response()
+}
@@ -1,2 +0,0 @@
-pub mod aa_mastery;
-pub mod gigabrain;
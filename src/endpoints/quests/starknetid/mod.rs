@@ -1,4 +1,3 @@
-pub mod claimable;
 pub mod verify_has_domain;
 pub mod verify_has_root_domain;
 pub mod verify_socials;
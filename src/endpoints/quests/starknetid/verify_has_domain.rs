@@ -1,18 +1,18 @@
 use std::sync::Arc;
 
 use crate::{
+    middleware::session::VerifiedAddress,
     models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use axum_auto_routes::route;
 use mongodb::bson::doc;
-use serde::Deserialize;
 use serde_json::json;
 use starknet::{
     core::types::{BlockId, BlockTag, FieldElement, FunctionCall},
@@ -20,18 +20,12 @@ use starknet::{
     providers::Provider,
 };
 
-#[derive(Deserialize)]
-pub struct StarknetIdQuery {
-    addr: FieldElement,
-}
-
 #[route(get, "/quests/starknetid/verify_has_domain")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<StarknetIdQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 1;
-    let addr = &query.addr;
 
     // get starkname from address
     let call_result = state
@@ -40,7 +34,7 @@ pub async fn handler(
             FunctionCall {
                 contract_address: state.conf.starknetid_contracts.naming_contract,
                 entry_point_selector: selector!("address_to_domain"),
-                calldata: vec![*addr, FieldElement::ZERO],
+                calldata: vec![addr, FieldElement::ZERO],
             },
             BlockId::Tag(BlockTag::Latest),
         )
@@ -51,7 +45,7 @@ pub async fn handler(
             let domain_len = i64::from_str_radix(&FieldElement::to_string(&result[0]), 16).unwrap();
 
             if domain_len > 0 {
-                match state.upsert_completed_task(query.addr, task_id).await {
+                match state.upsert_completed_task(addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
                     Err(e) => get_error(format!("{}", e)),
                 }
@@ -1,9 +1,10 @@
 use crate::{
     common::verify_has_root_domain::execute_has_root_domain,
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     response::IntoResponse,
 };
 use axum_auto_routes::route;
@@ -12,7 +13,7 @@ use std::sync::Arc;
 #[route(get, "/quests/starknetid/verify_has_root_domain")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
-    execute_has_root_domain(state, &query.addr, 5).await
+    execute_has_root_domain(state, &addr, 5).await
 }
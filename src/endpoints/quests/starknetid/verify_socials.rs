@@ -1,9 +1,10 @@
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -41,20 +42,19 @@ async fn call_contract_helper(
 #[route(get, "/quests/starknetid/verify_socials")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     async fn inner(
         state: Arc<AppState>,
-        query: VerifyQuery,
+        addr: FieldElement,
     ) -> Result<(StatusCode, Json<serde_json::Value>), String> {
         let task_id = 6;
-        let addr = &query.addr;
 
         let domain_res = call_contract_helper(
             &state,
             state.conf.starknetid_contracts.naming_contract,
             selector!("address_to_domain"),
-            vec![*addr, FieldElement::ZERO],
+            vec![addr, FieldElement::ZERO],
         )
         .await?;
 
@@ -103,7 +103,7 @@ pub async fn handler(
         }
 
         if twitter && discord {
-            match state.upsert_completed_task(query.addr, task_id).await {
+            match state.upsert_completed_task(addr, task_id).await {
                 Ok(_) => Ok((StatusCode::OK, Json(json!({"res": true})))),
                 Err(e) => Err(e.to_string()),
             }
@@ -114,7 +114,7 @@ pub async fn handler(
         }
     }
 
-    match inner(state, query).await {
+    match inner(state, addr).await {
         Ok(val) => val.into_response(),
         Err(err) => get_error(err),
     }
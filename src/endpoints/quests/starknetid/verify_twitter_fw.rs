@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use crate::{
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -17,13 +18,13 @@ use starknet::core::types::FieldElement;
 #[route(get, "/quests/starknetid/verify_twitter_follow")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
     let task_id = 7;
-    if query.addr == FieldElement::ZERO {
+    if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
-    match state.upsert_completed_task(query.addr, task_id).await {
+    match state.upsert_completed_task(addr, task_id).await {
         Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
         Err(e) => get_error(format!("{}", e)),
     }
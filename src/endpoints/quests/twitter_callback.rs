@@ -0,0 +1,122 @@
+use crate::common::oauth_state::{consume_oauth_state, OAuthStatePayload};
+use crate::common::twitter::{get_redirect_uri, verify_twitter_task};
+use crate::common::linked_accounts::link_account;
+use crate::models::QuestTaskDocument;
+use crate::utils::CompletedTasksTrait;
+use crate::{
+    models::AppState,
+    utils::{get_error_redirect, success_redirect},
+};
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use axum_auto_routes::route;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct TwitterOAuthCallbackQuery {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+}
+
+fn get_redirect(state: &AppState, oauth_state: Option<&OAuthStatePayload>, res: bool) -> String {
+    match oauth_state.and_then(|s| Some((s.quest_id?, s.task_id?))) {
+        Some((quest_id, task_id)) => format!(
+            "{}/quest/{}?task_id={}&res={}",
+            state.conf.variables.app_link, quest_id, task_id, res
+        ),
+        None => format!(
+            "{}/?platform=twitter&res={}",
+            state.conf.variables.app_link, res
+        ),
+    }
+}
+
+#[route(get, "/quests/twitter_callback")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TwitterOAuthCallbackQuery>,
+) -> impl IntoResponse {
+    let (oauth_state, pending) = match consume_oauth_state(&state, "twitter", &query.state).await {
+        Ok(res) => res,
+        Err(e) => return get_error_redirect(get_redirect(&state, None, false), e),
+    };
+    let error_redirect_uri = get_redirect(&state, Some(&oauth_state), false);
+
+    let Some(code) = &query.code else {
+        return get_error_redirect(
+            error_redirect_uri,
+            format!(
+                "Twitter authorization failed: {}",
+                query.error.as_deref().unwrap_or("unknown error")
+            ),
+        );
+    };
+
+    let Some(code_verifier) = &pending.code_verifier else {
+        return get_error_redirect(error_redirect_uri, "Invalid authorization".to_string());
+    };
+    let tokens = match state
+        .twitter
+        .exchange_code(code, code_verifier, &get_redirect_uri(&state))
+        .await
+    {
+        Ok(tokens) => tokens,
+        Err(e) => return get_error_redirect(error_redirect_uri, e),
+    };
+    let user = match state.twitter.get_me(&tokens.access_token).await {
+        Ok(user) => user,
+        Err(e) => return get_error_redirect(error_redirect_uri, e),
+    };
+
+    let account = match link_account(
+        &state,
+        &oauth_state.address,
+        "twitter",
+        &user.id,
+        &user.username,
+        &tokens,
+    )
+    .await
+    {
+        Ok(account) => account,
+        Err(e) => return get_error_redirect(error_redirect_uri, e),
+    };
+
+    // the authorization was started from a task, verify it right away
+    let (Some(quest_id), Some(task_id)) = (oauth_state.quest_id, oauth_state.task_id) else {
+        return success_redirect(get_redirect(&state, Some(&oauth_state), true));
+    };
+    let task = match state
+        .db
+        .collection::<QuestTaskDocument>("tasks")
+        .find_one(
+            doc! {
+                "id": task_id,
+                "quest_id": quest_id,
+                "task_type": { "$in": ["twitter_fw", "twitter_rw"] },
+            },
+            None,
+        )
+        .await
+    {
+        Ok(Some(task)) => task,
+        _ => return get_error_redirect(error_redirect_uri, "Task not found".to_string()),
+    };
+    if let Err(e) = verify_twitter_task(&state, &account, &task).await {
+        return get_error_redirect(error_redirect_uri, e);
+    }
+
+    let Ok(addr) = FieldElement::from_dec_str(&oauth_state.address) else {
+        return get_error_redirect(error_redirect_uri, "Invalid address".to_string());
+    };
+    match state.upsert_completed_task(addr, task_id).await {
+        Ok(_) => success_redirect(get_redirect(&state, Some(&oauth_state), true)),
+        Err(e) => get_error_redirect(error_redirect_uri, e.to_string()),
+    }
+}
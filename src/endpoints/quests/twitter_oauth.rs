@@ -0,0 +1,29 @@
+use crate::common::twitter::get_authorization_url;
+use crate::middleware::session::VerifiedAddress;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; TwitterOAuthQuery {
+    quest_id: Option<i64>,
+    task_id: Option<u32>,
+});
+
+#[route(get, "/quests/twitter_oauth")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<TwitterOAuthQuery>,
+) -> impl IntoResponse {
+    match get_authorization_url(&state, addr, query.quest_id, query.task_id).await {
+        Ok(url) => (StatusCode::OK, Json(json!({ "url": url }))).into_response(),
+        Err(e) => get_error(e),
+    }
+}
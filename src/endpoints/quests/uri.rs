@@ -1,4 +1,4 @@
-use crate::models::{AppState, NFTUri};
+use crate::models::{AppState, NFTUri, QuestDocument, QuestTaskDocument, RewardClaimDocument};
 use crate::utils::get_error;
 use axum::{
     extract::{Query, State},
@@ -7,28 +7,121 @@ use axum::{
     Json,
 };
 use axum_auto_routes::route;
-use futures::StreamExt;
-use mongodb::bson::{doc, from_document};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::sync::Arc;
 
+// OpenSea metadata standard, also read by the Starknet marketplaces
 #[derive(Serialize)]
 pub struct TokenURI {
     name: String,
     description: String,
     image: String,
-    attributes: Option<Vec<Attribute>>,
+    external_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    animation_url: Option<String>,
+    attributes: Vec<Attribute>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Attribute {
     trait_type: String,
-    value: u32,
+    value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_type: Option<String>,
+}
+
+impl Attribute {
+    fn new(trait_type: &str, value: Value, display_type: Option<&str>) -> Self {
+        Attribute {
+            trait_type: trait_type.to_string(),
+            value,
+            display_type: display_type.map(|display_type| display_type.to_string()),
+        }
+    }
 }
 
 #[derive(Deserialize)]
 pub struct LevelQuery {
     level: Option<String>,
+    token_id: Option<String>,
+}
+
+// Token ids minted for levels under 99 end with their level, see utils::get_token_id,
+// used for the tokens minted before reward claims were saved
+fn get_level_from_token_id(token_id: u64) -> Option<i64> {
+    match token_id % 100 {
+        99 => None,
+        level => Some(level as i64),
+    }
+}
+
+fn get_url(state: &AppState, url: &str) -> String {
+    match url.starts_with('/') {
+        true => format!("{}{}", state.conf.variables.app_link, url),
+        false => url.to_string(),
+    }
+}
+
+fn not_found(error: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({ "error": error }))).into_response()
+}
+
+// Reward claim of the token, it knows the level of every token id and the address the
+// token was minted for
+async fn get_claim(state: &AppState, token_id: u64) -> Option<RewardClaimDocument> {
+    state
+        .db
+        .collection::<RewardClaimDocument>("reward_claims")
+        .find_one(doc! { "token_id": token_id.to_string() }, None)
+        .await
+        .ok()
+        .flatten()
+}
+
+// When the address the token was minted for completed the last task required by its
+// level, in seconds. Falls back to the mint date for tasks completed before completion
+// timestamps were saved.
+async fn get_completion_date(
+    state: &AppState,
+    nft_uri: &NFTUri,
+    claim: &RewardClaimDocument,
+) -> Option<i64> {
+    let task_ids = match &nft_uri.task_ids {
+        Some(task_ids) => task_ids.clone(),
+        None => state
+            .db
+            .collection::<QuestTaskDocument>("tasks")
+            .find(doc! { "quest_id": nft_uri.quest_id }, None)
+            .await
+            .ok()?
+            .try_collect::<Vec<QuestTaskDocument>>()
+            .await
+            .ok()?
+            .iter()
+            .map(|task| task.id as i64)
+            .collect(),
+    };
+    let completed: Vec<Document> = state
+        .db
+        .collection::<Document>("completed_tasks")
+        .find(
+            doc! { "address": &claim.address, "task_id": { "$in": task_ids } },
+            None,
+        )
+        .await
+        .ok()?
+        .try_collect()
+        .await
+        .ok()?;
+    completed
+        .iter()
+        .filter_map(|task| task.get_i64("timestamp").ok())
+        .max()
+        .or(claim.minted_at)
+        .map(|timestamp| timestamp / 1000)
 }
 
 #[route(get, "/quests/uri")]
@@ -36,44 +129,83 @@ pub async fn handler(
     State(state): State<Arc<AppState>>,
     Query(level_query): Query<LevelQuery>,
 ) -> Response {
-    let level = level_query
-        .level
-        .and_then(|level_str| level_str.parse::<i64>().ok());
+    let token_id = match level_query.token_id {
+        Some(token_id) => match token_id.parse::<u64>() {
+            Ok(token_id) => Some(token_id),
+            Err(_) => return not_found("Invalid token id"),
+        },
+        None => None,
+    };
+    // tokens of levels from 99 don't encode their level, their claim does
+    let claim = match token_id {
+        Some(token_id) => get_claim(&state, token_id).await,
+        None => None,
+    };
+    let level = match (level_query.level, &claim) {
+        (Some(level), _) => level.parse::<i64>().ok(),
+        (None, Some(claim)) => Some(claim.level),
+        (None, None) => token_id.and_then(get_level_from_token_id),
+    };
+    let Some(level) = level else {
+        return not_found("Invalid level");
+    };
 
-    let uri_collection = state.db.collection::<NFTUri>("nft_uri");
-    let pipeline = vec![doc! {
-        "$match":{
-            "id":&level.unwrap()
-        }
-    }];
+    let nft_uri = match state
+        .db
+        .collection::<NFTUri>("nft_uri")
+        .find_one(doc! { "id": level }, None)
+        .await
+    {
+        Ok(Some(nft_uri)) => nft_uri,
+        Ok(None) => return not_found("NFT URI not found"),
+        Err(_) => return get_error("Error querying NFT URI".to_string()),
+    };
+    // quest traits are left out rather than failing the whole metadata
+    let quest = state
+        .db
+        .collection::<QuestDocument>("quests")
+        .find_one(doc! { "id": nft_uri.quest_id }, None)
+        .await
+        .ok()
+        .flatten();
 
-    match uri_collection.aggregate(pipeline, None).await {
-        Ok(mut cursor) => {
-            while let Some(result) = cursor.next().await {
-                return match result {
-                    Ok(document) => {
-                        if let Ok(nft_uri) = from_document::<NFTUri>(document) {
-                            return (
-                                StatusCode::OK,
-                                Json(TokenURI {
-                                    name: (&*nft_uri.name).to_string(),
-                                    description: (&*nft_uri.description).to_string(),
-                                    image: format!(
-                                        "{}{}",
-                                        state.conf.variables.app_link, &*nft_uri.image
-                                    ),
-                                    attributes: None,
-                                }),
-                            )
-                                .into_response();
-                        }
-                        get_error("Error querying NFT URI".to_string())
-                    }
-                    _ => get_error("Error querying NFT URI".to_string()),
-                };
-            }
-            get_error("NFT URI not found".to_string())
+    let mut attributes = vec![Attribute::new("Level", json!(level), Some("number"))];
+    if let Some(quest) = &quest {
+        attributes.push(Attribute::new("Quest", json!(quest.name), None));
+        attributes.push(Attribute::new("Issuer", json!(quest.issuer), None));
+    }
+    if let Some(rarity) = &nft_uri.rarity {
+        attributes.push(Attribute::new("Rarity", json!(rarity), None));
+    }
+    if let Some(claim) = &claim {
+        if let Some(date) = get_completion_date(&state, &nft_uri, claim).await {
+            attributes.push(Attribute::new("Completion date", json!(date), Some("date")));
         }
-        Err(_) => get_error("Error querying NFT URI".to_string()),
     }
+    if let Some(attribute) = nft_uri.attributes.clone() {
+        attributes.push(attribute);
+    }
+
+    let external_url = match &nft_uri.external_url {
+        Some(url) => get_url(&state, url),
+        None => format!(
+            "{}/quest/{}",
+            state.conf.variables.app_link, nft_uri.quest_id
+        ),
+    };
+    (
+        StatusCode::OK,
+        Json(TokenURI {
+            name: nft_uri.name.clone(),
+            description: nft_uri.description.clone(),
+            image: get_url(&state, &nft_uri.image),
+            external_url,
+            animation_url: nft_uri
+                .animation_url
+                .as_ref()
+                .map(|url| get_url(&state, url)),
+            attributes,
+        }),
+    )
+        .into_response()
 }
@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use crate::{
+    common::verify_task::verify_task, middleware::session::VerifiedAddress, models::AppState,
+};
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+};
+use axum_auto_routes::route;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct VerifyTaskQuery {
+    task_id: u32,
+}
+
+// Verifies any task declaring a verifier, see TaskVerifier
+#[route(get, "/quests/verify")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<VerifyTaskQuery>,
+) -> impl IntoResponse {
+    verify_task(&state, addr, query.task_id).await
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
+    middleware::session::VerifiedAddress,
     models::{AppState, QuestTaskDocument},
     utils::{get_error, CompletedTasksTrait},
 };
@@ -22,13 +23,13 @@ use starknet::{
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct VerifyBalanceQuery {
-    pub addr: FieldElement,
     pub task_id: u32,
 }
 
 #[route(get, "/quests/verify_balance")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyBalanceQuery>,
 ) -> impl IntoResponse {
     let task_id = query.task_id;
@@ -44,10 +45,9 @@ pub async fn handler(
         return get_error("Invalid task type.".to_string());
     }
 
-    let addr = &query.addr;
-    let utils_contract = state.conf.quests.utils_contract;
+    let utils_contract = state.get_reloadable_conf().quests.utils_contract;
 
-    let mut calldata = vec![addr.clone(), task.contracts.clone().unwrap().len().into()];
+    let mut calldata = vec![addr, task.contracts.clone().unwrap().len().into()];
     calldata.append(&mut task.contracts.unwrap().clone());
 
     let call_result = state
@@ -72,7 +72,7 @@ pub async fn handler(
             if result[0] < required_amount {
                 get_error("You didn't invest (enough).".to_string())
             } else {
-                match state.upsert_completed_task(query.addr, task_id).await {
+                match state.upsert_completed_task(addr, task_id).await {
                     Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
                     Err(e) => get_error(format!("{}", e)),
                 }
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use crate::common::discord::verify_discord_task;
+use crate::common::linked_accounts::get_linked_account;
+use crate::models::VerifyNewQuery;
+use crate::{
+    middleware::session::VerifiedAddress,
+    models::AppState,
+    utils::{get_error, CompletedTasksTrait},
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auto_routes::route;
+use serde_json::json;
+
+// Verifies a discord task with the linked account instead of a new authorization
+#[route(get, "/quests/verify_discord")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
+    Query(query): Query<VerifyNewQuery>,
+) -> impl IntoResponse {
+    let account = match get_linked_account(&state, &addr.to_string(), "discord").await {
+        Ok(account) => account,
+        Err(e) => return get_error(e),
+    };
+    if let Err(e) = verify_discord_task(&state, &account, query.quest_id, query.task_id).await {
+        return get_error(e);
+    }
+
+    match state.upsert_completed_task(addr, query.task_id).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
+        Err(e) => get_error(format!("{}", e)),
+    }
+}
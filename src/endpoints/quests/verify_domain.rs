@@ -1,9 +1,10 @@
 use crate::{
     common::verify_has_root_or_braavos_domain::verify_has_root_or_braavos_domain,
-    models::{AppState, VerifyQuery},
+    middleware::session::VerifiedAddress,
+    models::AppState,
 };
 use axum::{
-    extract::{Query, State},
+    extract::State,
     response::IntoResponse,
 };
 use axum_auto_routes::route;
@@ -12,7 +13,7 @@ use std::sync::Arc;
 #[route(get, "/quests/verify_has_domain")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
-    Query(query): Query<VerifyNewQuery>,
+    VerifiedAddress(addr): VerifiedAddress,
 ) -> impl IntoResponse {
-    verify_has_root_or_braavos_domain(state, &query.addr, 82).await
+    verify_has_root_or_braavos_domain(state, &addr, 82).await
 }
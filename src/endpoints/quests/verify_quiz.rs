@@ -1,12 +1,21 @@
 use std::sync::Arc;
 
-use crate::models::QuestTaskDocument;
+use crate::models::{QuestTaskDocument, QuizInsertDocument};
 use crate::{
-    common::verify_quiz::verify_quiz,
+    common::{
+        quiz_attempts::{record_attempt, release_attempt, reserve_attempt, QuizAttemptStatus},
+        verify_quiz::verify_quiz,
+    },
+    middleware::session::VerifiedAddress,
     models::{AppState, VerifyQuizQuery},
     utils::{get_error, CompletedTasksTrait},
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use axum_auto_routes::route;
 use futures::TryStreamExt;
 use mongodb::bson::doc;
@@ -16,9 +25,10 @@ use starknet::core::types::FieldElement;
 #[route(post, "/quests/verify_quiz")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     body: Json<VerifyQuizQuery>,
 ) -> impl IntoResponse {
-    if body.addr == FieldElement::ZERO {
+    if addr == FieldElement::ZERO {
         return get_error("Please connect your wallet first".to_string());
     }
 
@@ -40,18 +50,56 @@ pub async fn handler(
         Err(_) => return get_error("Quiz name does not match".to_string()),
     };
 
-    match verify_quiz(
+    let quiz = match state
+        .db
+        .collection::<QuizInsertDocument>("quizzes")
+        .find_one(doc! { "id": &body.quiz_name }, None)
+        .await
+    {
+        Ok(Some(quiz)) => quiz,
+        Ok(None) => return get_error("Quiz not found".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    let reservation = match reserve_attempt(&state, &quiz, addr).await {
+        Ok(QuizAttemptStatus::Allowed(reservation)) => reservation,
+        // passing again only makes sure the task is marked as completed
+        Ok(QuizAttemptStatus::Passed) => return complete_quiz_task(&state, addr, task_id).await,
+        Ok(QuizAttemptStatus::Denied(reason)) => return get_error(reason),
+        Err(e) => return get_error(e),
+    };
+
+    let results = match verify_quiz(
         &state.db,
-        body.addr,
+        &state.conf.session.quiz_order_secret,
+        addr,
         &body.quiz_name,
         &body.user_answers_list,
     )
     .await
     {
-        true => match state.upsert_completed_task(body.addr, task_id).await {
-            Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-            Err(e) => get_error(format!("{}", e)),
-        },
-        false => get_error("Incorrect answers".to_string()),
+        Ok(results) => results,
+        // answers that can't be graded or saved don't count as an attempt
+        Err(e) => {
+            release_attempt(&state, &quiz, addr, reservation).await;
+            return get_error(e);
+        }
+    };
+
+    // results per question are only kept for analytics, returning them would help guessing
+    match record_attempt(&state, &quiz, addr, results).await {
+        Ok(attempt) if attempt.passed => complete_quiz_task(&state, addr, task_id).await,
+        Ok(_) => get_error("Incorrect answers".to_string()),
+        Err(e) => {
+            release_attempt(&state, &quiz, addr, reservation).await;
+            get_error(e)
+        }
+    }
+}
+
+async fn complete_quiz_task(state: &AppState, addr: FieldElement, task_id: u32) -> Response {
+    match state.upsert_completed_task(addr, task_id).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
+        Err(e) => get_error(format!("{}", e)),
     }
 }
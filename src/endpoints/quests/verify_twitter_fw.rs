@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
+use crate::common::linked_accounts::get_linked_account;
+use crate::common::twitter::verify_twitter_task;
 use crate::models::{QuestTaskDocument, VerifyNewQuery};
 use crate::{
+    middleware::session::VerifiedAddress,
     models::AppState,
     utils::{get_error, CompletedTasksTrait},
 };
@@ -12,36 +15,45 @@ use axum::{
     Json,
 };
 use axum_auto_routes::route;
-use futures::TryStreamExt;
 use mongodb::bson::doc;
 use serde_json::json;
 
 #[route(get, "/quests/verify_twitter_fw")]
 pub async fn handler(
     State(state): State<Arc<AppState>>,
+    VerifiedAddress(addr): VerifiedAddress,
     Query(query): Query<VerifyNewQuery>,
 ) -> impl IntoResponse {
     let quest_id = query.quest_id;
     let task_id = query.task_id;
-    let pipeline = vec![doc! {
-        "$match": doc! {
-            "quest_id": quest_id,
-            "id":task_id ,
-            "task_type": "twitter_fw"
-        }
-    }];
+    let task = match state
+        .db
+        .collection::<QuestTaskDocument>("tasks")
+        .find_one(
+            doc! {
+                "quest_id": quest_id,
+                "id": task_id,
+                "task_type": "twitter_fw"
+            },
+            None,
+        )
+        .await
+    {
+        Ok(Some(task)) => task,
+        Ok(None) => return get_error("Error querying task".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
 
-    let tasks_collection = state.db.collection::<QuestTaskDocument>("tasks");
-    match tasks_collection.aggregate(pipeline, None).await {
-        Ok(mut cursor) => {
-            while let Some(_result) = cursor.try_next().await.unwrap() {
-                match state.upsert_completed_task(query.addr, task_id).await {
-                    Ok(_) => return (StatusCode::OK, Json(json!({"res": true}))).into_response(),
-                    Err(e) => get_error(format!("{}", e)),
-                };
-            }
-            get_error("Error querying task".to_string())
-        }
-        Err(e) => get_error(e.to_string()),
+    let account = match get_linked_account(&state, &addr.to_string(), "twitter").await {
+        Ok(account) => account,
+        Err(e) => return get_error(e),
+    };
+    if let Err(e) = verify_twitter_task(&state, &account, &task).await {
+        return get_error(e);
+    }
+
+    match state.upsert_completed_task(addr, task_id).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"res": true}))).into_response(),
+        Err(e) => get_error(format!("{}", e)),
     }
 }
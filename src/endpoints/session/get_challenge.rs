@@ -0,0 +1,59 @@
+use crate::common::verify_signature::get_challenge_typed_data;
+use crate::models::SessionChallengeDocument;
+use crate::utils::to_hex;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use mongodb::bson::doc;
+use serde::Deserialize;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; GetChallengeQuery {
+    addr: FieldElement,
+});
+
+#[route(get, "/session/get_challenge")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetChallengeQuery>,
+) -> impl IntoResponse {
+    if query.addr == FieldElement::ZERO {
+        return get_error("Please connect your wallet first".to_string());
+    }
+
+    let collection = state
+        .db
+        .collection::<SessionChallengeDocument>("session_challenges");
+    let now = Utc::now().timestamp();
+
+    // clean up challenges that were never answered
+    if let Err(e) = collection
+        .delete_many(doc! { "expiry": { "$lt": now } }, None)
+        .await
+    {
+        return get_error(e.to_string());
+    }
+
+    let nonce = FieldElement::from_byte_slice_be(&rand::random::<[u8; 31]>()).unwrap();
+    let expiry = now + state.conf.session.challenge_expiry;
+    let challenge = SessionChallengeDocument {
+        addr: to_hex(query.addr),
+        nonce: to_hex(nonce),
+        expiry,
+    };
+
+    match collection.insert_one(challenge, None).await {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(get_challenge_typed_data(&state.conf, nonce, expiry)),
+        )
+            .into_response(),
+        Err(_e) => get_error("Error creating challenge".to_string()),
+    }
+}
@@ -0,0 +1,2 @@
+pub mod get_challenge;
+pub mod verify_signature;
@@ -0,0 +1,69 @@
+use crate::common::verify_signature::{get_challenge_hash, verify_signature};
+use crate::models::{SessionChallengeDocument, SessionClaims};
+use crate::utils::to_hex;
+use crate::{models::AppState, utils::get_error};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json},
+};
+use axum_auto_routes::route;
+use chrono::Utc;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mongodb::bson::doc;
+use serde::Deserialize;
+use serde_json::json;
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+pub_struct!(Deserialize; VerifySignatureBody {
+    addr: FieldElement,
+    nonce: FieldElement,
+    signature: Vec<FieldElement>,
+});
+
+#[route(post, "/session/verify_signature")]
+pub async fn handler(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<VerifySignatureBody>,
+) -> impl IntoResponse {
+    let collection = state
+        .db
+        .collection::<SessionChallengeDocument>("session_challenges");
+
+    // a challenge can only be answered once
+    let filter = doc! { "addr": to_hex(body.addr), "nonce": to_hex(body.nonce) };
+    let challenge = match collection.find_one_and_delete(filter, None).await {
+        Ok(Some(challenge)) => challenge,
+        Ok(None) => return get_error("Challenge not found, please request a new one".to_string()),
+        Err(e) => return get_error(e.to_string()),
+    };
+
+    let now = Utc::now().timestamp();
+    if challenge.expiry < now {
+        return get_error("Challenge expired, please request a new one".to_string());
+    }
+
+    let hash = get_challenge_hash(&state.conf, body.addr, body.nonce, challenge.expiry);
+    if !verify_signature(&state.provider, body.addr, hash, &body.signature).await {
+        return (StatusCode::UNAUTHORIZED, "Invalid signature".to_string()).into_response();
+    }
+
+    let exp = now + state.conf.session.expiry_duration;
+    let claims = SessionClaims {
+        sub: to_hex(body.addr),
+        exp: exp as usize,
+    };
+    match encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.conf.session.secret_key.as_ref()),
+    ) {
+        Ok(token) => (
+            StatusCode::OK,
+            Json(json!({"token": token, "expiry": exp})),
+        )
+            .into_response(),
+        Err(_e) => get_error("Error creating session".to_string()),
+    }
+}
@@ -7,14 +7,32 @@ mod logger;
 mod models;
 mod middleware;
 
-use crate::utils::{add_leaderboard_table, run_boosts_raffle};
-use axum::{http::StatusCode, Router};
+use crate::common::admin_session::init_admin_users;
+use crate::common::boost_raffle::{init_boost_draws, run_boosts_raffle};
+use crate::common::claim_rewards::{init_legacy_reward_levels, init_reward_claims};
+use crate::common::linked_accounts::init_linked_accounts;
+use crate::common::mint_indexer::run_mint_indexer;
+use crate::common::partner_webhook::init_partner_webhooks;
+use crate::common::quest_rewards::init_quest_rewards;
+use crate::common::quiz_attempts::init_quiz_attempts;
+use crate::common::reward_signer::get_reward_signer;
+use crate::common::twitter::TwitterHttpApi;
+use crate::utils::{
+    add_leaderboard_table, init_id_sequences, init_quest_progress, reconcile_leaderboard,
+};
+#[cfg(unix)]
+use crate::utils::run_config_reload;
+use axum::{http::StatusCode, Extension, Router};
 use axum_auto_routes::route;
 use mongodb::{bson::doc, options::ClientOptions, Client};
 use reqwest::Url;
 use serde_derive::Serialize;
 use starknet::providers::{jsonrpc::HttpTransport, JsonRpcClient};
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    env,
+    sync::{Arc, RwLock},
+};
 use std::{net::SocketAddr, sync::Mutex};
 use tower_http::cors::{Any, CorsLayer};
 use utils::WithState;
@@ -51,15 +69,33 @@ async fn main() {
         .await
         .unwrap();
 
+    let client = Client::with_options(client_options).unwrap();
+    let signers = get_reward_signer(conf.nft_contract.private_key, &conf.nft_contract.signer)
+        .map_err(|e| format!("nft_contract: {}", e))
+        .and_then(|nft_signer| {
+            get_reward_signer(conf.quest_boost.private_key, &conf.quest_boost.signer)
+                .map(|boost_signer| (nft_signer, boost_signer))
+                .map_err(|e| format!("quest_boost: {}", e))
+        });
+    let (nft_signer, boost_signer) = match signers {
+        Ok(signers) => signers,
+        Err(e) => {
+            logger.async_severe(format!("Unable to load signer: {}", e)).await;
+            return;
+        }
+    };
     let shared_state = Arc::new(models::AppState {
         logger: logger.clone(),
         conf: conf.clone(),
+        reloadable_conf: RwLock::new(Arc::new(conf.get_reloadable())),
         provider: JsonRpcClient::new(HttpTransport::new(
             Url::parse(&conf.variables.rpc_url).unwrap(),
         )),
-        db: Client::with_options(client_options)
-            .unwrap()
-            .database(&conf.database.name),
+        db: client.database(&conf.database.name),
+        client,
+        twitter: Arc::new(TwitterHttpApi::new(&conf.twitter, logger.clone())),
+        nft_signer,
+        boost_signer,
     });
     if shared_state
         .db
@@ -73,14 +109,53 @@ async fn main() {
         logger.info("Connected to database");
     }
 
-    let db_instance = shared_state.db.clone();
-    run_boosts_raffle(
-        &db_instance,
-        conf.quest_boost.update_interval,
-        logger.clone(),
-    );
+    if env::args().any(|arg| arg == "--reconcile-leaderboard") {
+        match reconcile_leaderboard(&shared_state.db).await {
+            Ok(drifts) => {
+                for drift in &drifts {
+                    logger
+                        .async_warning(format!(
+                            "leaderboard: {} had {} experience instead of {}",
+                            drift.address, drift.actual, drift.expected
+                        ))
+                        .await;
+                }
+                logger
+                    .async_info(format!(
+                        "leaderboard: rebuilt from user_exp, {} entries had drifted",
+                        drifts.len()
+                    ))
+                    .await;
+            }
+            Err(e) => {
+                logger
+                    .async_severe(format!("leaderboard: reconciliation failed: {}", e))
+                    .await;
+            }
+        }
+        return;
+    }
+
+    run_boosts_raffle(&shared_state);
+    run_mint_indexer(&shared_state);
+    #[cfg(unix)]
+    run_config_reload(shared_state.clone());
     add_leaderboard_table(&shared_state.db).await;
+    init_id_sequences(&shared_state.db, &logger).await;
+    init_quest_progress(&shared_state.db, &logger).await;
+    init_admin_users(&shared_state.db, &logger).await;
+    init_linked_accounts(&shared_state.db, &logger).await;
+    init_partner_webhooks(&shared_state.db, &logger).await;
+    init_quiz_attempts(&shared_state.db, &logger).await;
+    init_reward_claims(&shared_state.db, &logger).await;
+    init_legacy_reward_levels(&shared_state.db, &logger).await;
+    init_quest_rewards(&shared_state.db, &logger).await;
+    init_boost_draws(&shared_state.db, &logger).await;
 
+    ROUTE_REGISTRY
+        .lock()
+        .unwrap()
+        .push(Box::new(endpoints::quests::claimable::get_legacy_router()));
     let cors = CorsLayer::new().allow_headers(Any).allow_origin(Any);
     let app = ROUTE_REGISTRY
         .lock()
@@ -90,6 +165,8 @@ async fn main() {
         .fold(Router::new().with_state(shared_state.clone()), |acc, r| {
             acc.merge(r.to_router(shared_state.clone()))
         })
+        // lets middlewares reach the state, they can't use the State extractor
+        .layer(Extension(shared_state.clone()))
         .layer(cors);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], conf.server.port));
@@ -1,21 +1,24 @@
 // src/middleware.rs
-use crate::models::JWTClaims;
+use crate::models::{AdminRole, AdminSessionDocument, AppState, JWTClaims, Permission};
+use async_trait::async_trait;
 use axum::{
-    http::{Request, StatusCode},
+    extract::{Extension, FromRequestParts},
+    http::{request::Parts, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
+use chrono::Utc;
 use jsonwebtoken::{decode, DecodingKey, Validation};
-
-use crate::config;
+use mongodb::bson::doc;
+use std::{marker::PhantomData, sync::Arc};
 
 pub async fn auth_middleware<B>(
+    Extension(state): Extension<Arc<AppState>>,
     mut req: Request<B>,
     next: Next<B>,
 ) -> Result<Response, (StatusCode, String)> {
     let headers = req.headers();
-    let conf = config::load();
-    let secret_key = &conf.auth.secret_key; 
+    let secret_key = &state.conf.auth.secret_key;
 
     let auth_header = headers
         .get(axum::http::header::AUTHORIZATION)
@@ -30,9 +33,28 @@ pub async fn auth_middleware<B>(
                     &DecodingKey::from_secret(secret_key.as_bytes()),
                     &Validation::new(jsonwebtoken::Algorithm::HS256),
                 ) {
-                    Ok(token_data) => 
+                    Ok(token_data) =>
                     {
-                        req.extensions_mut().insert(token_data.claims.sub);
+                        // access tokens stay valid only while their session is active
+                        let session = state
+                            .db
+                            .collection::<AdminSessionDocument>("admin_sessions")
+                            .find_one(
+                                doc! {
+                                    "id": &token_data.claims.jti,
+                                    "revoked": false,
+                                    "expiry": { "$gt": Utc::now().timestamp() },
+                                },
+                                None,
+                            )
+                            .await;
+                        match session {
+                            Ok(Some(_)) => {}
+                            Ok(None) => return Err((StatusCode::UNAUTHORIZED, "Session revoked or expired".to_string())),
+                            Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Error checking session".to_string())),
+                        }
+                        req.extensions_mut().insert(token_data.claims.sub.clone());
+                        req.extensions_mut().insert(token_data.claims);
                         Ok(next.run(req).await)
                     },
                     Err(_) => Err((StatusCode::UNAUTHORIZED, "Invalid token was provided".to_string())),
@@ -47,3 +69,87 @@ pub async fn auth_middleware<B>(
         Err((StatusCode::UNAUTHORIZED, "Missing Authorization header".to_string()))
     }
 }
+
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+// Marker types used by admin routes to declare the permission they need,
+// e.g. `admin: Admin<require::EditQuests>`
+pub mod require {
+    use super::RequiredPermission;
+    use crate::models::Permission;
+
+    macro_rules! required_permission {
+        ($($name:ident),*) => {
+            $(
+                pub struct $name;
+
+                impl RequiredPermission for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    required_permission!(
+        ManageUsers,
+        ViewQuests,
+        EditQuests,
+        ManageBoosts,
+        ViewAnalytics,
+        ViewAuditLog,
+        ManagePartners
+    );
+}
+
+// Admin authenticated by auth_middleware whose roles grant the permission P
+pub struct Admin<P> {
+    pub sub: String,
+    pub roles: Vec<AdminRole>,
+    permission: PhantomData<P>,
+}
+
+impl<P> Admin<P> {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        roles_have_permission(&self.roles, permission)
+    }
+}
+
+// For the routes open to every admin which behave differently depending on its roles
+pub fn roles_have_permission(roles: &[AdminRole], permission: Permission) -> bool {
+    roles
+        .iter()
+        .any(|role| role.permissions().contains(&permission))
+}
+
+#[async_trait]
+impl<P, S> FromRequestParts<S> for Admin<P>
+where
+    P: RequiredPermission + Send + Sync,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(claims) = parts.extensions.get::<JWTClaims>() else {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                "Missing Authorization header".to_string(),
+            ));
+        };
+
+        let admin = Admin {
+            sub: claims.sub.clone(),
+            roles: claims.roles.clone(),
+            permission: PhantomData,
+        };
+        if !admin.has_permission(P::PERMISSION) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Operation not allowed with your account".to_string(),
+            ));
+        }
+        Ok(admin)
+    }
+}
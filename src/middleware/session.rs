@@ -0,0 +1,57 @@
+use crate::models::{AppState, SessionClaims};
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use starknet::core::types::FieldElement;
+use std::sync::Arc;
+
+// Address proven by a wallet signature through /session/verify_signature,
+// handlers must use it instead of trusting an address sent in the query
+pub struct VerifiedAddress(pub FieldElement);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for VerifiedAddress {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let auth_header = parts
+            .headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok());
+
+        let token = match auth_header.and_then(|h| h.strip_prefix("Bearer ")) {
+            Some(token) => token.trim(),
+            None => {
+                return Err((
+                    StatusCode::UNAUTHORIZED,
+                    "Missing session token, please sign in with your wallet".to_string(),
+                ))
+            }
+        };
+
+        let secret_key = &state.conf.session.secret_key;
+        match decode::<SessionClaims>(
+            token,
+            &DecodingKey::from_secret(secret_key.as_bytes()),
+            &Validation::new(jsonwebtoken::Algorithm::HS256),
+        ) {
+            Ok(token_data) => match FieldElement::from_hex_be(&token_data.claims.sub) {
+                Ok(addr) => Ok(VerifiedAddress(addr)),
+                Err(_) => Err((
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid session token".to_string(),
+                )),
+            },
+            Err(_) => Err((
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired session token".to_string(),
+            )),
+        }
+    }
+}
@@ -1,21 +1,42 @@
-use mongodb::Database;
+use mongodb::{
+    bson::{Bson, Document},
+    Client, Database,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use starknet::{
     core::types::FieldElement,
     providers::{jsonrpc::HttpTransport, JsonRpcClient},
 };
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
 use crate::endpoints::quests::uri::Attribute;
-use crate::{config::Config, logger::Logger};
+use crate::{
+    common::{reward_signer::RewardSigner, twitter::TwitterApi},
+    config::{Config, ReloadableConfig},
+    logger::Logger,
+};
 
 pub_struct!(;AppState {
     conf: Config,
+    reloadable_conf: RwLock<Arc<ReloadableConfig>>,
     provider: JsonRpcClient<HttpTransport>,
+    client: Client,
     db: Database,
     logger: Logger,
+    twitter: Arc<dyn TwitterApi>,
+    nft_signer: Arc<dyn RewardSigner>,
+    boost_signer: Arc<dyn RewardSigner>,
 });
 
+impl AppState {
+    // quests, discover and boost settings must be read from here, they change on SIGHUP
+    pub fn get_reloadable_conf(&self) -> Arc<ReloadableConfig> {
+        self.reloadable_conf.read().unwrap().clone()
+    }
+}
+
 pub_struct!(Debug, Serialize, Deserialize; NFTItem {
     img: String,
     level: u32,
@@ -44,7 +65,32 @@ pub_struct!(Debug, Serialize, Deserialize; QuestDocument {
     expired: Option<bool>,
     experience: i64,
     start_time: i64,
-});
+    rewards: Option<Vec<QuestReward>>,
+});
+
+// Reward of the completers of a quest, quests without reward definitions give their
+// NFT levels and experience
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestReward {
+    // the levels of nft_uri, claimed through /quests/claimable
+    Nft,
+    // paid by the boost contract from the funds deposited under claim_id, a boost id
+    // reserved for the reward. Amounts are in the smallest unit of the token.
+    Token {
+        claim_id: u32,
+        token: String,
+        amount: String,
+        // only the first max_recipients completers are paid
+        #[serde(default)]
+        max_recipients: Option<u32>,
+        // total amount paid to completers
+        #[serde(default)]
+        budget: Option<String>,
+    },
+    // credited on completion like the experience of the quest
+    Xp { amount: i64 },
+}
 
 pub_struct!(Debug, Serialize, Deserialize; QuestInsertDocument {
     id: u32,
@@ -68,14 +114,43 @@ pub_struct!(Debug, Serialize, Deserialize; QuestInsertDocument {
     start_time: i64,
 });
 
-pub_struct!(Debug, Serialize, Deserialize;  QuizInsertDocument {
+pub_struct!(Debug, Clone, Serialize, Deserialize;  QuizInsertDocument {
     id: u32,
     name: String,
     desc: String,
    intro:String,
+    // percentage of correct answers needed to pass, 100 if not set
+    passing_score: Option<u32>,
+    // unlimited if not set
+    max_attempts: Option<u32>,
+    // seconds to wait between two attempts
+    cooldown: Option<i64>,
+});
+
+pub_struct!(Debug, Clone, Serialize, Deserialize; QuizQuestionResult {
+    question_id: i64,
+    correct: bool,
+});
+
+// One graded submission of a quiz
+pub_struct!(Debug, Serialize, Deserialize; QuizAttemptDocument {
+    quiz_id: i64,
+    address: String,
+    results: Vec<QuizQuestionResult>,
+    score: u32,
+    passed: bool,
+    timestamp: i64,
 });
 
-pub_struct!(Debug, Serialize, Deserialize; QuizQuestionDocument {
+// Attempts taken by an address on a quiz, see common::quiz_attempts::reserve_attempt
+pub_struct!(Debug, Serialize, Deserialize; QuizAttemptCounterDocument {
+    quiz_id: i64,
+    address: String,
+    attempts: i64,
+    last_attempt: i64,
+});
+
+pub_struct!(Debug, Clone, Serialize, Deserialize; QuizQuestionDocument {
     id: i64,
     question: String,
     options:Vec<String>,
@@ -83,6 +158,12 @@ pub_struct!(Debug, Serialize, Deserialize; QuizQuestionDocument {
     kind: String,
     layout: String,
     quiz_id: i64,
+    correct_order: Option<Vec<String>>,
+    image_for_layout: Option<String>,
+    correct_value: Option<f64>,
+    tolerance: Option<f64>,
+    accepted_answers: Option<Vec<String>>,
+    answer_regex: Option<String>,
 });
 
 pub_struct!(Serialize, Deserialize; NFTUri {
@@ -91,7 +172,12 @@ pub_struct!(Serialize, Deserialize; NFTUri {
     description:String,
     image: String,
     quest_id: i64,
-    attributes: Option<Attribute>
+    attributes: Option<Attribute>,
+    task_ids: Option<Vec<i64>>,
+    rarity: Option<String>,
+    animation_url: Option<String>,
+    // the quest page if not set
+    external_url: Option<String>,
 });
 
 pub_struct!(Deserialize; CompletedTasks {
@@ -107,7 +193,7 @@ pub struct CompletedTaskDocument {
     timestamp: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct QuestTaskDocument {
     pub(crate) id: i32,
     pub quest_id: i64,
@@ -128,15 +214,170 @@ pub struct QuestTaskDocument {
     pub task_type: Option<String>,
     #[serde(default)]
     pub(crate) discord_guild_id: Option<String>,
+    // role ids the member must all hold in the guild
+    #[serde(default)]
+    pub(crate) discord_role_ids: Option<Vec<String>>,
+    // minimum age of the Discord account in days
+    #[serde(default)]
+    pub(crate) discord_min_account_age: Option<i64>,
     #[serde(default)]
     pub(crate) contracts: Option<Vec<FieldElement>>,
+    #[serde(default)]
+    pub verifier: Option<TaskVerifier>,
+    #[serde(default)]
+    pub custom_api: Option<CustomApiOptions>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+// Condition on the values selected by the JSONPath of a json_api verifier,
+// it holds when at least one selected value matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JsonPredicate {
+    Exists,
+    Equals { value: Value },
+    Compare { comparison: Comparison, value: f64 },
+    // the value is an address equal to the one of the user
+    IsAddr,
+}
+
+// How a task is verified by /quests/verify. Templated strings accept {addr}
+// (decimal) and {addr_hex}.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskVerifier {
+    // calls a view function and compares one felt of the result with value
+    ContractCall {
+        contract: String,
+        entrypoint: String,
+        calldata: Vec<String>,
+        #[serde(default)]
+        result_index: usize,
+        comparison: Comparison,
+        value: String,
+        #[serde(default)]
+        error_msg: Option<String>,
+    },
+    JsonApi {
+        url: String,
+        path: String,
+        predicate: JsonPredicate,
+        #[serde(default)]
+        error_msg: Option<String>,
+    },
+    // the user holds at least min_count tokens of an ERC721 contract
+    NftOwnership {
+        contract: FieldElement,
+        min_count: u64,
+        #[serde(default)]
+        error_msg: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CustomApiMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+// Request and checks of a custom_api task on top of its api_url and regex. The url
+// and body accept {addr} (decimal), {addr_hex} and {quest_id}, the headers of the
+// partner are added by the server so its api key is never sent to users.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomApiOptions {
+    #[serde(default)]
+    pub method: CustomApiMethod,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub partner: Option<String>,
+    // JSONPath of the values to check, they must be true unless a predicate is set
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub predicate: Option<JsonPredicate>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
 }
 
+// Partner integration settings that must stay on the server, the webhook secret
+// is encrypted like the tokens of linked accounts
+pub_struct!(Debug, Serialize, Deserialize; PartnerDocument {
+    name: String,
+    api_headers: HashMap<String, String>,
+    allowed_hosts: Option<Vec<String>>,
+    webhook_secret: Option<String>,
+    webhook_task_ids: Option<Vec<i64>>,
+});
+
+// One call of a partner to /partners/webhook, accepted or not
+pub_struct!(Debug, Serialize, Deserialize; WebhookDeliveryDocument {
+    partner: String,
+    address: Option<String>,
+    task_id: Option<u32>,
+    nonce: Option<String>,
+    accepted: bool,
+    error: Option<String>,
+    timestamp: i64,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RewardClaimStatus {
+    Issued,
+    Minted,
+}
+
+// Reward signature issued for one level of a quest. The token id is the nonce of the
+// signature: it is kept for the address and level so that the level can be minted once.
+pub_struct!(Debug, Clone, Serialize, Deserialize; RewardClaimDocument {
+    address: String,
+    quest_id: u32,
+    level: i64,
+    task_id: u32,
+    token_id: String,
+    sig: (String, String),
+    // key the signature was made with, claims are signed again after a key rotation
+    signer_public_key: Option<String>,
+    status: RewardClaimStatus,
+    // deadline of the claim, the server stops serving it once passed
+    expiry: i64,
+    created_at: i64,
+    minted_at: Option<i64>,
+    mint_transaction: Option<String>,
+});
+
+// Token reward signed for an address, the boost contract only pays a claim id once
+// per address
+pub_struct!(Debug, Clone, Serialize, Deserialize; TokenRewardClaimDocument {
+    address: String,
+    quest_id: u32,
+    claim_id: u32,
+    token: String,
+    amount: String,
+    sig: (String, String),
+    signer_public_key: Option<String>,
+    created_at: i64,
+});
+
 pub_struct!(Serialize; Reward {
     task_id: u32,
     nft_contract: String,
     token_id: String,
     sig: (FieldElement, FieldElement),
+    expiry: i64,
 });
 
 pub_struct!(Serialize; RewardResponse {
@@ -144,12 +385,10 @@ pub_struct!(Serialize; RewardResponse {
 });
 
 pub_struct!(Deserialize; VerifyQuery {
-    addr: FieldElement,
     task_id: Option<u32>,
 });
 
 pub_struct!(Deserialize; VerifyNewQuery {
-    addr: FieldElement,
     quest_id: i64,
     task_id: u32,
 });
@@ -159,14 +398,22 @@ pub_struct!(Deserialize; EmailQuery {
     email: String,
 });
 
+// Answer to one question: option indices for choice and ordering questions,
+// a number for numeric questions and a string for short text questions
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum QuizAnswer {
+    Choices(Vec<usize>),
+    Number(f64),
+    Text(String),
+}
+
 pub_struct!(Deserialize; VerifyQuizQuery {
-    addr: FieldElement,
     quiz_name: i64,
-    user_answers_list: Vec<Vec<usize>>,
+    user_answers_list: Vec<QuizAnswer>,
 });
 
 pub_struct!(Deserialize; VerifyBalanceQuery {
-    addr: FieldElement,
     task_id: u32,
 });
 
@@ -181,7 +428,6 @@ pub_struct!(Deserialize; AchievementQuery {
 });
 
 pub_struct!(Deserialize; VerifyAchievementQuery {
-    addr: FieldElement,
     id: u32,
 });
 
@@ -251,6 +497,12 @@ pub_struct!(Debug, Serialize, Deserialize; LeaderboardTable {
     timestamp:f64,
 });
 
+pub_struct!(Debug; LeaderboardDrift {
+    address: String,
+    expected: i64,
+    actual: i64,
+});
+
 pub_struct!(Debug, Serialize, Deserialize; BoostTable {
     amount: i32,
     token: String,
@@ -263,6 +515,25 @@ pub_struct!(Debug, Serialize, Deserialize; BoostTable {
     hidden: bool,
     num_of_winners: i64,
     token_decimals: i64,
+    draw: Option<BoostDraw>,
+});
+
+// Public inputs of a boost raffle, enough for anyone to recompute its winners. The
+// seed is the hash of seed_block, the first block produced after the boost expiry.
+pub_struct!(Debug, Clone, Serialize, Deserialize; BoostDraw {
+    algorithm_version: i32,
+    seed: String,
+    seed_block: u64,
+    eligible_hash: String,
+    eligible_count: u64,
+    drawn_at: i64,
+});
+
+// Eligible list a boost was drawn from, kept so that the draw can be verified after the
+// tasks or completions of its quests changed
+pub_struct!(Debug, Clone, Serialize, Deserialize; BoostEligibleDocument {
+    boost_id: i64,
+    eligible: Vec<String>,
 });
 
 pub_struct!(Debug, Serialize, Deserialize; NftBalance {
@@ -314,7 +585,6 @@ pub_struct!(Deserialize, Debug; DeployedTime {
 });
 
 pub_struct!(Deserialize; VerifyAchievementBatchedQuery {
-    addr: FieldElement,
     category_id: u32,
 });
 
@@ -336,16 +606,151 @@ pub_struct!(Debug, Serialize, Deserialize; QuestCategoryDocument {
     img_url: String,
 });
 
-pub_struct!(Debug, Serialize, Deserialize; JWTClaims {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    SuperAdmin,
+    Issuer,
+    Analyst,
+    BoostManager,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ManageUsers,
+    ViewQuests,
+    ViewAllQuests,
+    EditQuests,
+    EditAllQuests,
+    ManageBoosts,
+    ManageAllBoosts,
+    ViewAnalytics,
+    ViewAuditLog,
+    ManagePartners,
+}
+
+impl AdminRole {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            AdminRole::SuperAdmin => &[
+                Permission::ManageUsers,
+                Permission::ViewQuests,
+                Permission::ViewAllQuests,
+                Permission::EditQuests,
+                Permission::EditAllQuests,
+                Permission::ManageBoosts,
+                Permission::ManageAllBoosts,
+                Permission::ViewAnalytics,
+                Permission::ViewAuditLog,
+                Permission::ManagePartners,
+            ],
+            // issuers can only see and edit the quests they own
+            AdminRole::Issuer => &[
+                Permission::ViewQuests,
+                Permission::EditQuests,
+                Permission::ManageBoosts,
+            ],
+            AdminRole::Analyst => &[
+                Permission::ViewQuests,
+                Permission::ViewAllQuests,
+                Permission::ViewAnalytics,
+                Permission::ViewAuditLog,
+            ],
+            AdminRole::BoostManager => &[
+                Permission::ViewQuests,
+                Permission::ViewAllQuests,
+                Permission::ManageBoosts,
+                Permission::ManageAllBoosts,
+            ],
+        }
+    }
+}
+
+pub_struct!(Debug, Clone, Serialize, Deserialize; JWTClaims {
     sub: String,
     exp: usize,
+    jti: String,
+    roles: Vec<AdminRole>,
 });
 
-pub_struct!(Debug, Serialize, Deserialize; LoginDetails {
+pub_struct!(Debug, Serialize, Deserialize; AdminSessionDocument {
+    id: String,
     user: String,
-    code: String,
+    refresh_token_hash: String,
+    // hash of the refresh token replaced by the last refresh, to detect its reuse
+    previous_refresh_token_hash: Option<String>,
+    created_at: i64,
+    last_used_at: i64,
+    expiry: i64,
+    revoked: bool,
 });
 
+// One entry per admin create/update/delete, diff maps each changed field to its
+// before and after values
+pub_struct!(Debug, Serialize, Deserialize; AdminAuditDocument {
+    user: String,
+    route: String,
+    collection: String,
+    entity_id: Bson,
+    quest_ids: Vec<i64>,
+    action: String,
+    diff: Document,
+    timestamp: i64,
+});
+
+pub_struct!(Debug, Serialize, Deserialize; SessionClaims {
+    sub: String,
+    exp: usize,
+});
+
+pub_struct!(Debug, Serialize, Deserialize; SessionChallengeDocument {
+    addr: String,
+    nonce: String,
+    expiry: i64,
+});
+
+// Social account linked to a wallet, its tokens are only stored encrypted and are
+// used to check tasks against the platform API
+pub_struct!(Debug, Serialize, Deserialize; LinkedAccountDocument {
+    address: String,
+    platform: String,
+    account_id: String,
+    username: String,
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    token_expiry: Option<i64>,
+    linked_at: i64,
+});
+
+// Pending OAuth authorization keyed by the nonce of its signed state, deleted
+// as soon as the callback uses it
+pub_struct!(Debug, Serialize, Deserialize; OAuthStateDocument {
+    state: String,
+    platform: String,
+    address: String,
+    code_verifier: Option<String>,
+    expiry: i64,
+});
+
+pub_struct!(Debug, Serialize, Deserialize; LoginDetails {
+    user: String,
+    code: Option<String>,
+    password_hash: Option<String>,
+    roles: Option<Vec<AdminRole>>,
+});
+
+impl LoginDetails {
+    // accounts created before roles existed keep their previous rights
+    pub fn get_roles(&self) -> Vec<AdminRole> {
+        match &self.roles {
+            Some(roles) => roles.clone(),
+            None if self.user == "super_user" => vec![AdminRole::SuperAdmin],
+            None => vec![AdminRole::Issuer],
+        }
+    }
+}
+
 pub_struct!(Deserialize; CreateBoostQuery {
     quest_id: i32,
     amount: i32,
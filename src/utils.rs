@@ -1,7 +1,14 @@
+use crate::common::reward_signer::RewardSigner;
+use crate::config;
 use crate::logger::Logger;
+use crate::middleware::auth::Admin;
 use crate::models::{
-    AchievementDocument, AppState, BoostTable, CompletedTasks, LeaderboardTable, QuestDocument,
-    QuestTaskDocument, UserExperience,
+    AchievementDocument, AppState, CompletedTasks, LeaderboardDrift, LeaderboardTable,
+    LoginDetails, Permission, QuestDocument, QuestTaskDocument, UserExperience,
+};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
 };
 use async_trait::async_trait;
 use axum::{
@@ -11,27 +18,28 @@ use axum::{
     Router,
 };
 use chrono::{Duration as dur, Utc};
-use futures::TryStreamExt;
+use futures::{future::BoxFuture, TryStreamExt};
 use mongodb::{
-    bson::doc, options::UpdateOptions, results::UpdateResult, Collection, Cursor, Database,
-    IndexModel,
+    bson::{doc, Bson, Document},
+    options::{
+        FindOneAndUpdateOptions, FindOneOptions, IndexOptions, ReturnDocument, UpdateOptions,
+    },
+    error::{
+        ErrorKind, WriteFailure, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT,
+    },
+    results::UpdateResult,
+    Client, ClientSession, Collection, Database, IndexModel,
 };
-use rand::distributions::{Distribution, Uniform};
 use serde_json::json;
-use starknet::signers::Signer;
-use starknet::{
-    core::{
-        crypto::{pedersen_hash, Signature},
-        types::FieldElement,
-    },
-    signers::LocalWallet,
+use starknet::core::{
+    crypto::{pedersen_hash, Signature},
+    types::FieldElement,
 };
-use std::collections::hash_map::DefaultHasher;
+use std::collections::{hash_map::DefaultHasher, HashMap};
 use std::hash::{Hash, Hasher};
 use std::result::Result;
 use std::str::FromStr;
 use std::{fmt::Write, sync::Arc};
-use tokio::time::{sleep, Duration};
 
 #[macro_export]
 macro_rules! pub_struct {
@@ -44,17 +52,22 @@ macro_rules! pub_struct {
 }
    
 
-pub async fn get_nft(
+// Random token id encoding the level, it is also the nonce of the reward signature
+// since the NFT contract can only mint it once
+pub fn get_token_id(nft_level: u32) -> u64 {
+    match nft_level < 100 {
+        true => nft_level as u64 + 100 * (rand::random::<u64>() % (2u64.pow(32))),
+        false => (rand::random::<u64>() + nft_level as u64 * 0x2000000) * 100 + 99,
+    }
+}
+
+pub async fn sign_nft(
     quest_id: u32,
     task_id: u32,
     addr: &FieldElement,
-    nft_level: u32,
-    signer: &LocalWallet,
-) -> Result<(u64, Signature), Box<dyn std::error::Error + Send + Sync>> {
-    let token_id = match nft_level < 100 {
-        true => nft_level as u64 + 100 * (rand::random::<u64>() % (2u64.pow(32))),
-        false => (rand::random::<u64>() + nft_level as u64 * 0x2000000) * 100 + 99,
-    };
+    token_id: u64,
+    signer: &dyn RewardSigner,
+) -> Result<Signature, String> {
     let hashed = pedersen_hash(
         &pedersen_hash(
             &pedersen_hash(
@@ -65,16 +78,56 @@ pub async fn get_nft(
         ),
         addr,
     );
-    let sig = signer.sign_hash(&hashed).await?;
-    Ok((token_id, sig))
+    signer.sign(&hashed).await
+}
+
+// Hash signed for the claim entrypoint of the boost contract, also used to pay the
+// token rewards of quests under a boost id reserved for them
+pub fn get_boost_claim_hash(
+    boost_id: u32,
+    amount: u128,
+    token: FieldElement,
+    addr: FieldElement,
+) -> FieldElement {
+    pedersen_hash(
+        &FieldElement::from(boost_id),
+        &pedersen_hash(
+            &FieldElement::from(amount),
+            &pedersen_hash(&FieldElement::ZERO, &pedersen_hash(&token, &addr)),
+        ),
+    )
 }
 
+// legacy admin password hash, only kept to migrate old login_details on first login
 pub fn calculate_hash(t: &String) -> u64 {
     let mut hasher = DefaultHasher::new();
     t.hash(&mut hasher);
     hasher.finish()
 }
 
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())?;
+    let password_hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+    Ok(password_hash.to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed_hash) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub fn verify_login(login: &LoginDetails, password: &str) -> bool {
+    match (&login.password_hash, &login.code) {
+        (Some(password_hash), _) => verify_password(password, password_hash),
+        (None, Some(code)) => *code == calculate_hash(&password.to_string()).to_string(),
+        (None, None) => false,
+    }
+}
+
 pub fn get_error(error: String) -> Response {
     (StatusCode::INTERNAL_SERVER_ERROR, error).into_response()
 }
@@ -135,136 +188,138 @@ impl CompletedTasksTrait for AppState {
         addr: FieldElement,
         task_id: u32,
     ) -> Result<UpdateResult, mongodb::error::Error> {
-        let completed_tasks_collection: Collection<CompletedTasks> =
-            self.db.collection("completed_tasks");
-        let created_at = Utc::now().timestamp_millis();
-        let filter = doc! { "address": addr.to_string(), "task_id": task_id };
-        let update = doc! { "$setOnInsert": { "address": addr.to_string(), "task_id": task_id , "timestamp":created_at} };
+        let db = self.db.clone();
+        run_transaction(&self.client, move |session| {
+            Box::pin(complete_task(db.clone(), session, addr, task_id))
+        })
+        .await
+    }
+}
 
-        let options = UpdateOptions::builder().upsert(true).build();
+// Marks the task as completed and, when it was the last task of its quest, credits
+// the quest experience, all in the transaction of the session
+async fn complete_task(
+    db: Database,
+    session: &mut ClientSession,
+    addr: FieldElement,
+    task_id: u32,
+) -> Result<UpdateResult, mongodb::error::Error> {
+    let completed_tasks_collection: Collection<CompletedTasks> = db.collection("completed_tasks");
+    let created_at = Utc::now().timestamp_millis();
+    let filter = doc! { "address": addr.to_string(), "task_id": task_id };
+    let update = doc! { "$setOnInsert": { "address": addr.to_string(), "task_id": task_id , "timestamp":created_at} };
 
-        let result = completed_tasks_collection
-            .update_one(filter, update, options)
-            .await?;
+    let options = UpdateOptions::builder().upsert(true).build();
 
-        match &result.upserted_id {
-            Some(_id) => {
-                let pipeline = vec![
-                    doc! {
-                        "$match": doc!{
-                        "address": addr.to_string(),
-                    },
-                    },
-                    doc! {
-                        "$lookup": doc! {
-                        "from": "tasks",
-                        "localField": "task_id",
-                        "foreignField": "id",
-                        "as": "associatedTask",
-                    },
-                    },
-                    doc! {
-                        "$unwind": "$associatedTask",
-                    },
-                    doc! {
-                       "$project": doc! {
-                        "address": "$address",
-                        "task_id": "$task_id",
-                       "quest_id": "$associatedTask.quest_id",
-                    },
-                    },
-                    doc! {
-                        "$group": doc! {
-                        "_id": "$quest_id",
-                        "done": doc! {
-                            "$sum": 1,
-                        },
-                    },
-                    },
-                    doc! {
-                        "$lookup": doc! {
-                        "from": "tasks",
-                        "localField": "_id",
-                        "foreignField": "quest_id",
-                        "as": "tasks",
-                    },
-                    },
-                    doc! {
-                        "$match": doc! {
-                        "$expr": {
-                            "$eq": [
-                            "$done",
-                            {
-                                "$size": "$tasks",
-                            },
-                            ],
-                        },
-                    },
-                    },
-                    doc! {
-                        "$match": doc! {
-                        "tasks": doc! {
-                            "$elemMatch": {
-                                "id": task_id,
-                            },
-                        },
-                    },
-                    },
-                    doc! {
-                        "$lookup": doc! {
-                        "from": "quests",
-                        "localField": "_id",
-                        "foreignField": "id",
-                        "as": "associatedQuests",
-                    }
-                    },
-                    doc! {
-                        "$unwind": "$associatedQuests",
-                    },
-                    doc! {
-                        "$project": doc! {
-                            "_id": 0,
-                            "experience": "$associatedQuests.experience",
-                        }
-                    },
-                ];
-                match completed_tasks_collection.aggregate(pipeline, None).await {
-                    Ok(mut cursor) => {
-                        let mut experience = 0;
-                        while let Some(response) = cursor.try_next().await.unwrap() {
-                            experience = response.get("experience").unwrap().as_i32().unwrap();
-                        }
-
-                        // return result if experience is 0 (quest is not completed)
-                        if experience == 0 {
-                            return Ok(result);
-                        }
-
-                        // save the user_exp document in the collection
-                        let user_exp_collection = self.db.collection("user_exp");
-                        // add doc with address ,experience and timestamp
-                        let timestamp: f64 = Utc::now().timestamp_millis() as f64;
-                        let document = doc! { "address": addr.to_string(), "experience":experience, "timestamp":timestamp};
-                        user_exp_collection.insert_one(document, None).await?;
-                        let view_collection: Collection<LeaderboardTable> =
-                            self.db.collection("leaderboard_table");
-                        update_leaderboard(
-                            view_collection,
-                            addr.to_string(),
-                            experience.into(),
-                            timestamp,
-                        )
-                        .await;
-                    }
-                    Err(_e) => {
-                        get_error("Error querying quests".to_string());
-                    }
-                }
-            }
-            None => {}
-        }
-        Ok(result)
+    let result = completed_tasks_collection
+        .update_one_with_session(filter, update, options, session)
+        .await?;
+
+    if result.upserted_id.is_none() {
+        return Ok(result);
+    }
+
+    let tasks_collection = db.collection::<Document>("tasks");
+    let Some(task) = tasks_collection
+        .find_one_with_session(doc! { "id": task_id }, None, session)
+        .await?
+    else {
+        return Ok(result);
+    };
+    let Some(quest_id) = task.get("quest_id").and_then(bson_as_i64) else {
+        return Ok(result);
+    };
+
+    // every completion of the quest writes this document, so that two transactions
+    // completing its last tasks concurrently conflict instead of both seeing the quest
+    // as not completed, the one retried then sees the other task and credits the quest
+    db.collection::<Document>("quest_progress")
+        .update_one_with_session(
+            doc! { "address": addr.to_string(), "quest_id": quest_id },
+            doc! {
+                "$addToSet": { "task_ids": task_id },
+                "$set": { "updated_at": created_at },
+            },
+            UpdateOptions::builder().upsert(true).build(),
+            session,
+        )
+        .await?;
+
+    let mut quest_task_ids = vec![];
+    let mut cursor = tasks_collection
+        .find_with_session(doc! { "quest_id": quest_id }, None, session)
+        .await?;
+    while let Some(quest_task) = cursor.next(session).await.transpose()? {
+        quest_task_ids.extend(quest_task.get("id").and_then(bson_as_i64));
+    }
+    let mut completed_task_ids = vec![];
+    let mut cursor = db
+        .collection::<Document>("completed_tasks")
+        .find_with_session(
+            doc! { "address": addr.to_string(), "task_id": { "$in": &quest_task_ids } },
+            None,
+            session,
+        )
+        .await?;
+    while let Some(completed) = cursor.next(session).await.transpose()? {
+        completed_task_ids.extend(completed.get("task_id").and_then(bson_as_i64));
+    }
+    let experience = db
+        .collection::<Document>("quests")
+        .find_one_with_session(doc! { "id": quest_id }, None, session)
+        .await?
+        .and_then(|quest| quest.get("experience").and_then(bson_as_i64))
+        .unwrap_or(0);
+
+    if let Some(experience) = get_quest_experience(&quest_task_ids, &completed_task_ids, experience)
+    {
+        let source = get_quest_source(quest_id);
+        credit_experience(&db, session, &addr.to_string(), experience, &source).await?;
+    }
+    Ok(result)
+}
+
+// Experience credited for a quest, once every one of its tasks is completed
+fn get_quest_experience(
+    quest_task_ids: &[i64],
+    completed_task_ids: &[i64],
+    experience: i64,
+) -> Option<i64> {
+    let completed = !quest_task_ids.is_empty()
+        && quest_task_ids
+            .iter()
+            .all(|task_id| completed_task_ids.contains(task_id));
+    match completed && experience != 0 {
+        true => Some(experience),
+        false => None,
+    }
+}
+
+// Source under which the experience of a quest is credited, at most once per address
+fn get_quest_source(quest_id: i64) -> String {
+    format!("quest_{}", quest_id)
+}
+
+pub async fn init_quest_progress(db: &Database, logger: &Logger) {
+    let index = IndexModel::builder()
+        .keys(doc! { "address": 1, "quest_id": 1 })
+        .options(IndexOptions::builder().unique(true).build())
+        .build();
+    if let Err(e) = db
+        .collection::<Document>("quest_progress")
+        .create_index(index, None)
+        .await
+    {
+        logger.warning(format!("unable to create index on quest_progress: {}", e));
+    }
+}
+
+pub fn get_random_hex(bytes_len: usize) -> String {
+    let mut result = String::with_capacity(bytes_len * 2);
+    for _ in 0..bytes_len {
+        write!(&mut result, "{:02x}", rand::random::<u8>()).unwrap();
     }
+    result
 }
 
 pub fn to_hex(felt: FieldElement) -> String {
@@ -304,49 +359,11 @@ impl AchievementsTrait for AppState {
         addr: FieldElement,
         achievement_id: u32,
     ) -> Result<UpdateResult, mongodb::error::Error> {
-        let achieved_collection: Collection<CompletedTasks> = self.db.collection("achieved");
-        let created_at = Utc::now().timestamp_millis();
-        let filter = doc! { "addr": addr.to_string(), "achievement_id": achievement_id };
-        let update = doc! { "$setOnInsert": { "addr": addr.to_string(), "achievement_id": achievement_id , "timestamp":created_at } };
-        let options = UpdateOptions::builder().upsert(true).build();
-
-        let result = achieved_collection
-            .update_one(filter, update, options)
-            .await?;
-
-        match &result.upserted_id {
-            Some(_id) => {
-                // Check if the document was modified
-                let achievement_collection: Collection<AchievementDocument> =
-                    self.db.collection("achievements");
-                // Define a query using the `doc!` macro.
-                let query = doc! { "id": achievement_id };
-                let mut experience: i32 = 0;
-
-                let mut cursor = achievement_collection.find(query, None).await?;
-                // Iterate over the results.
-                while let Some(doc) = cursor.try_next().await? {
-                    experience = doc.experience as i32;
-                }
-
-                let user_exp_collection = self.db.collection("user_exp");
-                // add doc with address ,experience and timestamp
-                let timestamp: f64 = Utc::now().timestamp_millis() as f64;
-                let document = doc! { "address": addr.to_string(), "experience":experience, "timestamp":timestamp};
-                user_exp_collection.insert_one(document, None).await?;
-                let view_collection: Collection<LeaderboardTable> =
-                    self.db.collection("leaderboard_table");
-                update_leaderboard(
-                    view_collection,
-                    addr.to_string(),
-                    experience.into(),
-                    timestamp,
-                )
-                .await;
-            }
-            None => {}
-        }
-        Ok(result)
+        let db = self.db.clone();
+        run_transaction(&self.client, move |session| {
+            Box::pin(complete_achievement(db.clone(), session, addr, achievement_id))
+        })
+        .await
     }
 
     async fn upsert_claimed_achievement(
@@ -380,6 +397,40 @@ impl AchievementsTrait for AppState {
     }
 }
 
+async fn complete_achievement(
+    db: Database,
+    session: &mut ClientSession,
+    addr: FieldElement,
+    achievement_id: u32,
+) -> Result<UpdateResult, mongodb::error::Error> {
+    let achieved_collection: Collection<CompletedTasks> = db.collection("achieved");
+    let created_at = Utc::now().timestamp_millis();
+    let filter = doc! { "addr": addr.to_string(), "achievement_id": achievement_id };
+    let update = doc! { "$setOnInsert": { "addr": addr.to_string(), "achievement_id": achievement_id , "timestamp":created_at } };
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    let result = achieved_collection
+        .update_one_with_session(filter, update, options, session)
+        .await?;
+
+    if result.upserted_id.is_some() {
+        let achievement_collection: Collection<AchievementDocument> =
+            db.collection("achievements");
+        let query = doc! { "id": achievement_id };
+        let experience = match achievement_collection
+            .find_one_with_session(query, None, session)
+            .await?
+        {
+            Some(achievement) => achievement.experience as i64,
+            None => 0,
+        };
+
+        let source = format!("achievement_{}", achievement_id);
+        credit_experience(&db, session, &addr.to_string(), experience, &source).await?;
+    }
+    Ok(result)
+}
+
 #[async_trait]
 pub trait DeployedTimesTrait {
     async fn upsert_deployed_timestamp(
@@ -430,29 +481,182 @@ pub async fn fetch_json_from_url(url: String) -> Result<serde_json::Value, Strin
     }
 }
 
-pub async fn update_leaderboard(
-    view_collection: Collection<LeaderboardTable>,
-    address: String,
+pub fn bson_as_i64(value: &Bson) -> Option<i64> {
+    match value {
+        Bson::Int32(value) => Some(*value as i64),
+        Bson::Int64(value) => Some(*value),
+        Bson::Double(value) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+// mongodb reports writes violating a unique index with this code
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+pub fn is_duplicate_key_error(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY_CODE
+    )
+}
+
+const MAX_TRANSACTION_ATTEMPTS: usize = 5;
+
+// The commit can be retried as is when its outcome is unknown, committing twice is safe
+async fn commit_transaction(session: &mut ClientSession) -> Result<(), mongodb::error::Error> {
+    let mut attempt = 1;
+    loop {
+        match session.commit_transaction().await {
+            Err(e)
+                if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                    && attempt < MAX_TRANSACTION_ATTEMPTS =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Runs the operation in a transaction, retrying it when MongoDB reports a transient
+// error (e.g. a write conflict with a concurrent transaction)
+pub async fn run_transaction<T, F>(
+    client: &Client,
+    mut operation: F,
+) -> Result<T, mongodb::error::Error>
+where
+    F: for<'a> FnMut(&'a mut ClientSession) -> BoxFuture<'a, Result<T, mongodb::error::Error>>,
+{
+    let mut session = client.start_session(None).await?;
+    let mut attempt = 1;
+    loop {
+        session.start_transaction(None).await?;
+        let result = match operation(&mut session).await {
+            Ok(result) => commit_transaction(&mut session).await.map(|_| result),
+            Err(e) => {
+                let _ = session.abort_transaction().await;
+                Err(e)
+            }
+        };
+        match result {
+            Err(e)
+                if e.contains_label(TRANSIENT_TRANSACTION_ERROR)
+                    && attempt < MAX_TRANSACTION_ATTEMPTS =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// Credits experience to the address at most once per source (a quest or an achievement),
+// user_exp and leaderboard_table are updated in the transaction of the session. Returns
+// false when the source was already credited.
+pub async fn credit_experience(
+    db: &Database,
+    session: &mut ClientSession,
+    address: &str,
     experience: i64,
-    timestamp: f64,
-) {
-    // get current experience and new experience to it
-    let mut old_experience = 0;
-    let filter = doc! { "_id": &*address };
-    let mut cursor: Cursor<LeaderboardTable> = view_collection.find(filter, None).await.unwrap();
-    while let Some(doc) = cursor.try_next().await.unwrap() {
-        old_experience = doc.experience;
-    }
-
-    // update the view collection
-    let filter = doc! { "_id": &*address };
-    let update =
-        doc! { "$set": { "experience": old_experience + experience, "timestamp": timestamp } };
+    source: &str,
+) -> Result<bool, mongodb::error::Error> {
+    let user_exp_collection = db.collection::<Document>("user_exp");
+    let already_credited = user_exp_collection
+        .find_one_with_session(doc! { "address": address, "source": source }, None, session)
+        .await?;
+    if already_credited.is_some() {
+        return Ok(false);
+    }
+
+    let timestamp: f64 = Utc::now().timestamp_millis() as f64;
+    let document = doc! {
+        "address": address,
+        "experience": experience,
+        "timestamp": timestamp,
+        "source": source,
+    };
+    user_exp_collection
+        .insert_one_with_session(document, None, session)
+        .await?;
+
+    let view_collection: Collection<LeaderboardTable> = db.collection("leaderboard_table");
+    let update = doc! {
+        "$inc": { "experience": experience },
+        "$set": { "timestamp": timestamp },
+    };
     let options = UpdateOptions::builder().upsert(true).build();
     view_collection
-        .update_one(filter, update, options)
-        .await
-        .unwrap();
+        .update_one_with_session(doc! { "_id": address }, update, options, session)
+        .await?;
+    Ok(true)
+}
+
+// Compares leaderboard_table with the experience recorded in user_exp, then rebuilds
+// it from user_exp. Returns the entries that had drifted.
+pub async fn reconcile_leaderboard(
+    db: &Database,
+) -> Result<Vec<LeaderboardDrift>, mongodb::error::Error> {
+    let pipeline = vec![doc! {
+        "$group": {
+            "_id": "$address",
+            "experience": { "$sum": "$experience" },
+        }
+    }];
+    let mut expected: HashMap<String, i64> = HashMap::new();
+    let mut cursor = db
+        .collection::<UserExperience>("user_exp")
+        .aggregate(pipeline, None)
+        .await?;
+    while let Some(entry) = cursor.try_next().await? {
+        if let Ok(address) = entry.get_str("_id") {
+            let experience = entry.get("experience").and_then(bson_as_i64).unwrap_or(0);
+            expected.insert(address.to_string(), experience);
+        }
+    }
+
+    let view_collection = db.collection::<Document>("leaderboard_table");
+    let mut actual: HashMap<String, i64> = HashMap::new();
+    let mut cursor = view_collection.find(None, None).await?;
+    while let Some(entry) = cursor.try_next().await? {
+        if let Ok(address) = entry.get_str("_id") {
+            let experience = entry.get("experience").and_then(bson_as_i64).unwrap_or(0);
+            actual.insert(address.to_string(), experience);
+        }
+    }
+
+    let mut drifts: Vec<LeaderboardDrift> = expected
+        .iter()
+        .map(|(address, experience)| LeaderboardDrift {
+            address: address.clone(),
+            expected: *experience,
+            actual: actual.get(address).copied().unwrap_or(0),
+        })
+        .chain(
+            actual
+                .iter()
+                .filter(|(address, _)| !expected.contains_key(*address))
+                .map(|(address, experience)| LeaderboardDrift {
+                    address: address.clone(),
+                    expected: 0,
+                    actual: *experience,
+                }),
+        )
+        .filter(|drift| drift.expected != drift.actual)
+        .collect();
+    drifts.sort_by(|a, b| a.address.cmp(&b.address));
+
+    // entries without any user_exp would survive the $merge, they are removed first
+    let orphans: Vec<&String> = actual
+        .keys()
+        .filter(|address| !expected.contains_key(*address))
+        .collect();
+    if !orphans.is_empty() {
+        view_collection
+            .delete_many(doc! { "_id": { "$in": orphans } }, None)
+            .await?;
+    }
+    add_leaderboard_table(db).await;
+    Ok(drifts)
 }
 
 pub async fn add_leaderboard_table(db: &Database) {
@@ -498,222 +702,127 @@ pub async fn add_leaderboard_table(db: &Database) {
         .create_index(compound_index, None)
         .await
         .unwrap();
+
+    // experience is credited at most once per (address, source), older entries have no source
+    let credit_key = IndexModel::builder()
+        .keys(doc! { "address": 1, "source": 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .partial_filter_expression(doc! { "source": { "$exists": true } })
+                .build(),
+        )
+        .build();
+    source_collection
+        .create_index(credit_key, None)
+        .await
+        .unwrap();
 }
 
-pub async fn fetch_and_update_boosts_winner(
-    boost_collection: Collection<BoostTable>,
-    completed_tasks_collection: Collection<CompletedTasks>,
-    interval: u64,
-    logger: Logger,
-) {
-    loop {
-        let pipeline = vec![doc! {
-            "$match": {
-                "expiry":{
-                    "$lt": Utc::now().timestamp_millis()
-                },
-                "winner": {
-                    "$eq": null,
-                },
-            }
-        }];
-        match boost_collection.aggregate(pipeline, None).await {
-            Ok(mut cursor) => {
-                while let Some(doc) = cursor.try_next().await.unwrap() {
-                    let mut num_of_winners = doc.get("num_of_winners").unwrap().as_i32().unwrap();
-                    // use this variable to add some extra winners so that we have some extra winners incase anyone user repeats
-                    let extra_winners = 10;
-                    match doc.get("quests") {
-                        Some(quests_res) => {
-                            let quests = quests_res.as_array().unwrap();
-                            let mut address_list: Vec<FieldElement> = Vec::new();
-                            for quest in quests {
-                                let get_users_per_quest_pipeline = vec![
-                                    doc! {
-                                        "$lookup": doc! {
-                                            "from": "tasks",
-                                            "localField": "task_id",
-                                            "foreignField": "id",
-                                            "as": "associated_tasks"
-                                        }
-                                    },
-                                    doc! {
-                                        "$match": doc! {
-                                            "$expr": doc! {
-                                                "$eq": [
-                                                    doc! {
-                                                        "$first": "$associated_tasks.quest_id"
-                                                    },
-                                                    quest
-                                                ]
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$group": doc! {
-                                            "_id": "$address",
-                                            "tasks_list": doc! {
-                                                "$push": doc! {
-                                                    "$arrayElemAt": [
-                                                        "$associated_tasks",
-                                                        0
-                                                    ]
-                                                }
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$unwind": "$tasks_list"
-                                    },
-                                    doc! {
-                                        "$group": doc! {
-                                            "_id": doc! {
-                                                "address": "$_id",
-                                                "quest_id": "$tasks_list.quest_id"
-                                            },
-                                            "tasks_array": doc! {
-                                                "$push": "$tasks_list"
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$project": doc! {
-                                            "_id": 0,
-                                            "address": "$_id.address",
-                                            "quest_id": "$_id.quest_id",
-                                            "tasks_array": 1
-                                        }
-                                    },
-                                    doc! {
-                                        "$lookup": doc! {
-                                            "from": "tasks",
-                                            "localField": "quest_id",
-                                            "foreignField": "quest_id",
-                                            "as": "associatedTasks"
-                                        }
-                                    },
-                                    doc! {
-                                        "$match": doc! {
-                                            "$expr": doc! {
-                                                "$eq": [
-                                                    doc! {
-                                                        "$size": "$tasks_array"
-                                                    },
-                                                    doc! {
-                                                        "$size": "$associatedTasks"
-                                                    }
-                                                ]
-                                            }
-                                        }
-                                    },
-                                    doc! {
-                                        "$project": doc! {
-                                            "address": "$address"
-                                        }
-                                    },
-                                    doc! {
-                                        "$sample":{
-                                            "size":num_of_winners+extra_winners
-                                        }
-                                    },
-                                ];
-                                match completed_tasks_collection
-                                    .aggregate(get_users_per_quest_pipeline, None)
-                                    .await
-                                {
-                                    Ok(mut cursor) => {
-                                        while let Some(doc) = cursor.try_next().await.unwrap() {
-                                            let address =
-                                                doc.get("address").unwrap().as_str().unwrap();
-                                            let formatted_address =
-                                                FieldElement::from_str(address).unwrap();
-                                            address_list.push(formatted_address);
-                                        }
-                                    }
-                                    Err(_err) => {}
-                                }
-                            }
-
-                            // skip if no user has completed quests
-                            if address_list.len() == 0 {
-                                continue;
-                            }
-                            let mut random_index;
-                            let mut winner_array: Vec<String> = Vec::new();
-
-                            // if length of address list is 1 then select the only user
-                            if address_list.len() == 1 {
-                                let winner = &address_list[0].to_string();
-                                let formatted_winner = FieldElement::from_str(winner).unwrap();
-                                winner_array.push(to_hex(formatted_winner));
-                            }
-                            // else select random users
-                            else {
-                                let mut current_winner_index = 0;
-                                // handle case when number of winners is greater than number of users then assign all users as winners
-                                if address_list.len() < num_of_winners as usize {
-                                    num_of_winners = address_list.len() as i32;
-                                }
-                                let mut iter_index = 0;
-                                loop {
-                                    let mut rng = rand::thread_rng();
-
-                                    let die = Uniform::new(0, address_list.len());
-                                    random_index = die.sample(&mut rng);
-
-                                    let winner = &address_list[random_index].to_string();
-                                    let formatted_winner = FieldElement::from_str(winner).unwrap();
-                                    if !winner_array.contains(&to_hex(formatted_winner)) {
-                                        winner_array.push(to_hex(formatted_winner));
-                                        current_winner_index += 1;
-                                    }
-                                    iter_index += 1;
-                                    if current_winner_index == (num_of_winners) as usize
-                                        || iter_index == address_list.len()
-                                    {
-                                        break;
-                                    }
-                                }
-                            }
-
-                            let filter = doc! { "id": doc.get("id").unwrap().as_i32().unwrap() };
-                            let update = doc! { "$set": { "winner": winner_array  } };
-                            let options = UpdateOptions::builder().upsert(true).build();
-                            boost_collection
-                                .update_one(filter, update, options)
-                                .await
-                                .unwrap();
-                        }
-                        None => {
-                            logger.info("No winners found");
-                        }
-                    }
-                }
-            }
-            Err(_err) => logger.info(_err.to_string()),
-        };
+// Collections whose documents are identified by a numeric `id` allocated by get_next_id
+const ID_COLLECTIONS: [&str; 6] = [
+    "quests",
+    "tasks",
+    "quizzes",
+    "quiz_questions",
+    "nft_uri",
+    "boosts",
+];
+
+pub async fn init_id_sequences(db: &Database, logger: &Logger) {
+    let counters_collection = db.collection::<Document>("counters");
+    for name in ID_COLLECTIONS {
+        let collection = db.collection::<Document>(name);
+        let unique_id = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = collection.create_index(unique_id, None).await {
+            logger.warning(format!("unable to create unique id index on {}: {}", name, e));
+        }
 
-        sleep(Duration::from_secs(interval)).await;
+        // sequences start after the highest existing id, $max never moves them backwards
+        let options = FindOneOptions::builder()
+            .sort(doc! { "id": -1 })
+            .projection(doc! { "id": 1 })
+            .build();
+        let last_id = match collection.find_one(None, options).await {
+            Ok(Some(last_doc)) => last_doc.get("id").and_then(bson_as_i64).unwrap_or(0),
+            _ => 0,
+        };
+        if let Err(e) = counters_collection
+            .update_one(
+                doc! { "_id": name },
+                doc! { "$max": { "seq": last_id } },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await
+        {
+            logger.warning(format!("unable to initialize id sequence of {}: {}", name, e));
+        }
     }
 }
 
-pub fn run_boosts_raffle(db: &Database, interval: u64, logger: Logger) {
-    let boost_collection = db.collection::<BoostTable>("boosts");
-    let completed_tasks_collection = db.collection::<CompletedTasks>("completed_tasks");
-    tokio::spawn(fetch_and_update_boosts_winner(
-        boost_collection,
-        completed_tasks_collection,
-        interval,
-        logger,
-    ));
+// Atomically allocates the next id of a collection, concurrent calls never get the same id
+pub async fn get_next_id(db: &Database, collection: &str) -> Result<i64, String> {
+    let options = FindOneAndUpdateOptions::builder()
+        .upsert(true)
+        .return_document(ReturnDocument::After)
+        .build();
+    let counter = db
+        .collection::<Document>("counters")
+        .find_one_and_update(
+            doc! { "_id": collection },
+            doc! { "$inc": { "seq": 1_i64 } },
+            options,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    counter
+        .and_then(|counter| counter.get_i64("seq").ok())
+        .ok_or_else(|| format!("unable to allocate an id for {}", collection))
 }
 
-pub async fn verify_task_auth(
-    user: String,
+// Reloads the non-secret sections of the config when the server receives SIGHUP,
+// the new values are only applied if they are valid
+#[cfg(unix)]
+pub fn run_config_reload(state: Arc<AppState>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(err) => {
+            state
+                .logger
+                .warning(format!("config: unable to listen for SIGHUP: {}", err));
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while sighup.recv().await.is_some() {
+            let reloadable = match config::try_load() {
+                Ok(conf) => conf.get_reloadable(),
+                Err(err) => {
+                    state.logger.warning(format!("config: reload failed: {}", err));
+                    continue;
+                }
+            };
+            if let Err(err) = reloadable.validate() {
+                state.logger.warning(format!("config: reload rejected: {}", err));
+                continue;
+            }
+            *state.reloadable_conf.write().unwrap() = Arc::new(reloadable);
+            state.logger.info("config: reloaded quests, discover and quest_boost.update_interval");
+        }
+    });
+}
+
+pub async fn verify_task_auth<P>(
+    admin: &Admin<P>,
     task_collection: &Collection<QuestTaskDocument>,
     id: &i32,
 ) -> bool {
-    if user == "super_user" {
+    if admin.has_permission(Permission::EditAllQuests) {
         return true;
     }
 
@@ -753,27 +862,60 @@ pub async fn verify_task_auth(
     while let Some(doc) = existing_quest.try_next().await.unwrap() {
         issuer = doc.get("issuer").unwrap().as_str().unwrap().to_string();
     }
-    if issuer == user {
+    if issuer == admin.sub {
         return true;
     }
     false
 }
 
-pub async fn verify_quest_auth(
-    user: String,
+async fn is_quest_issuer(
+    issuer: &str,
+    quest_collection: &Collection<QuestDocument>,
+    id: &i64,
+) -> bool {
+    let filter = doc! { "id": id, "issuer": issuer };
+    matches!(quest_collection.find_one(filter, None).await, Ok(Some(_)))
+}
+
+pub async fn verify_quest_auth<P>(
+    admin: &Admin<P>,
     quest_collection: &Collection<QuestDocument>,
     id: &i64,
 ) -> bool {
-    if user == "super_user" {
+    if admin.has_permission(Permission::EditAllQuests) {
         return true;
     }
+    is_quest_issuer(&admin.sub, quest_collection, id).await
+}
 
-    let filter = doc! { "id": id, "issuer": user };
+// Same as verify_quest_auth for the routes reading a quest, issuers can only read theirs
+pub async fn verify_quest_view_auth<P>(
+    admin: &Admin<P>,
+    quest_collection: &Collection<QuestDocument>,
+    id: &i64,
+) -> bool {
+    if admin.has_permission(Permission::ViewAllQuests) {
+        return true;
+    }
+    is_quest_issuer(&admin.sub, quest_collection, id).await
+}
 
-    let existing_quest = quest_collection.find_one(filter, None).await.unwrap();
+// Quest of a quiz, read from the quiz task which references the quiz by its id
+pub async fn get_quiz_quest_id(db: &Database, quiz_id: i64) -> Option<i64> {
+    db.collection::<QuestTaskDocument>("tasks")
+        .find_one(doc! { "quiz_name": quiz_id }, None)
+        .await
+        .ok()
+        .flatten()
+        .map(|task| task.quest_id)
+}
 
-    match existing_quest {
-        Some(_) => true,
+pub async fn verify_quiz_view_auth<P>(admin: &Admin<P>, db: &Database, quiz_id: i64) -> bool {
+    if admin.has_permission(Permission::ViewAllQuests) {
+        return true;
+    }
+    match get_quiz_quest_id(db, quiz_id).await {
+        Some(quest_id) => is_quest_issuer(&admin.sub, &db.collection("quests"), &quest_id).await,
         None => false,
     }
 }
@@ -826,4 +968,29 @@ impl Clone for Box<dyn WithState> {
     fn clone(&self) -> Box<dyn WithState> {
         self.box_clone()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quest_experience_is_credited_once_every_task_is_completed() {
+        assert_eq!(get_quest_experience(&[1, 2, 3], &[3, 1, 2], 50), Some(50));
+        assert_eq!(get_quest_experience(&[1, 2, 3], &[1, 2], 50), None);
+        // completions of other quests don't count
+        assert_eq!(get_quest_experience(&[1, 2], &[1, 4, 5], 50), None);
+    }
+
+    #[test]
+    fn quests_without_experience_or_tasks_are_not_credited() {
+        assert_eq!(get_quest_experience(&[1, 2], &[1, 2], 0), None);
+        assert_eq!(get_quest_experience(&[], &[1], 50), None);
+    }
+
+    #[test]
+    fn quest_experience_has_one_source_per_quest() {
+        assert_eq!(get_quest_source(12), "quest_12");
+        assert_ne!(get_quest_source(12), get_quest_source(120));
+    }
+}